@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Benchmarks of the color conversions the widgets run for every vertex of
+//! their meshes.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ok_picker::colors::{
+    batch::{oklab_to_rgb8_slice, rgb8_to_oklab_slice, srgb_to_oklab_slice},
+    Lab, LinSrgb, OkHsl, OkHsv, OkLCh, OkLab, Srgb,
+};
+
+const RGB: Srgb = Srgb {
+    red: 0.4,
+    green: 0.4,
+    blue: 0.7,
+};
+
+/// Benchmarks `From<$from> for $to`, starting from [`RGB`].
+macro_rules! bench_from {
+    ($c:expr, $from:ty => $to:ty) => {{
+        let color = <$from>::from(RGB);
+        $c.bench_function(concat!(stringify!($from), " to ", stringify!($to)), |b| {
+            b.iter(|| <$to>::from(black_box(color)))
+        });
+    }};
+}
+
+fn conversions(c: &mut Criterion) {
+    bench_from!(c, Srgb => LinSrgb);
+    bench_from!(c, LinSrgb => Srgb);
+    bench_from!(c, LinSrgb => OkLab);
+    bench_from!(c, OkLab => LinSrgb);
+    bench_from!(c, Srgb => OkLab);
+    bench_from!(c, OkLab => Srgb);
+    bench_from!(c, Srgb => OkHsv);
+    bench_from!(c, OkHsv => Srgb);
+    bench_from!(c, Srgb => OkHsl);
+    bench_from!(c, OkHsl => Srgb);
+    bench_from!(c, Srgb => Lab);
+    bench_from!(c, Lab => Srgb);
+
+    let lab = OkLab::from(RGB);
+    c.bench_function("OkLab to OkLCh", |b| b.iter(|| OkLCh::from(black_box(lab))));
+    let lch = OkLCh::from(lab);
+    c.bench_function("OkLCh to OkLab", |b| b.iter(|| OkLab::from(black_box(lch))));
+}
+
+/// Compares the batch conversions with `From` over a 256×256 image.
+fn batch(c: &mut Criterion) {
+    let bytes: Vec<u8> = (0..256 * 256 * 3)
+        .map(|index| (index % 251) as u8)
+        .collect();
+    let colors: Vec<Srgb> = bytes
+        .chunks(3)
+        .map(|pixel| Srgb {
+            red: f64::from(pixel[0]) / 255.0,
+            green: f64::from(pixel[1]) / 255.0,
+            blue: f64::from(pixel[2]) / 255.0,
+        })
+        .collect();
+    let mut labs = vec![OkLab::default(); colors.len()];
+
+    c.bench_function("Srgb to OkLab, From per color", |b| {
+        b.iter(|| {
+            for (color, lab) in black_box(&colors).iter().zip(&mut labs) {
+                *lab = OkLab::from(*color);
+            }
+        })
+    });
+    c.bench_function("Srgb to OkLab, slice", |b| {
+        b.iter(|| srgb_to_oklab_slice(black_box(&colors), &mut labs))
+    });
+    c.bench_function("RGB8 to OkLab, slice", |b| {
+        b.iter(|| rgb8_to_oklab_slice(black_box(&bytes), &mut labs))
+    });
+    let mut back = vec![0; bytes.len()];
+    c.bench_function("OkLab to RGB8, slice", |b| {
+        b.iter(|| oklab_to_rgb8_slice(black_box(&labs), &mut back))
+    });
+}
+
+criterion_group!(benches, conversions, batch);
+criterion_main!(benches);
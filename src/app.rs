@@ -0,0 +1,422 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The Ok Picker application, shared by the native and the web binaries.
+
+use eframe::{
+    egui::{
+        self, CentralPanel, CollapsingHeader, Color32, Context, FontData, FontDefinitions,
+        FontFamily, Hyperlink, Layout, ScrollArea, SidePanel, TopBottomPanel, Window,
+    },
+    emath::Align,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{colors, palette::Swatch, widgets};
+
+/// Serde support for [`egui::color::Hsva`], saved as a `[h, s, v, a]` array.
+mod hsva {
+    use eframe::egui::color::Hsva;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Hsva, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.h, color.s, color.v, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hsva, D::Error> {
+        let [h, s, v, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Hsva::new(h, s, v, a))
+    }
+}
+
+/// Version of the saved [`OkPicker`] state.
+///
+/// Bump it whenever the saved fields change in an incompatible way, and
+/// handle the older versions in [`OkPicker::restore`]. The state is saved
+/// since version 1.
+const STATE_VERSION: u32 = 2;
+
+/// The state of the application.
+///
+/// Everything is saved through eframe storage, and missing fields take their
+/// default value so older saves still load. eframe also saves the window
+/// layout on its own.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OkPicker {
+    version: u32,
+    #[serde(with = "hsva")]
+    color: egui::color::Hsva,
+    okhsv: colors::Alpha<colors::Srgb>,
+    okhsl: colors::Alpha<colors::Srgb>,
+    colour_lch: colors::Srgb,
+    palette: Vec<Swatch>,
+    background: colors::Srgb,
+    cvd: widgets::cvd::CvdCheck,
+    generator: widgets::generator::Generator,
+    gradient: widgets::gradient::GradientEditor,
+    export: widgets::export::Export,
+    #[serde(skip)]
+    export_open: bool,
+    import: widgets::import::Import,
+    #[serde(skip)]
+    import_open: bool,
+    eyedropper: widgets::eyedropper::Eyedropper,
+    #[serde(skip)]
+    eyedropper_open: bool,
+}
+
+/// The fields of the version 1 state that changed since.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct StateV1 {
+    /// The OkHSV picker color, without alpha
+    colour: colors::Srgb,
+    /// The OkHSL picker color, without alpha
+    colour_too: colors::Srgb,
+}
+
+impl OkPicker {
+    fn fonts() -> FontDefinitions {
+        let mut fonts = FontDefinitions::default();
+        fonts.font_data.insert(
+            "Iosevka Clapoto".to_owned(),
+            FontData::from_static(include_bytes!("../fonts/iosevka-clapoto-regular.ttf")),
+        );
+        fonts.font_data.insert(
+            "Asap".to_owned(),
+            FontData::from_static(include_bytes!("../fonts/Asap-Regular.ttf")),
+        );
+        fonts
+            .families
+            .get_mut(&FontFamily::Proportional)
+            .unwrap()
+            .insert(0, "Asap".to_owned());
+        fonts
+            .families
+            .get_mut(&FontFamily::Monospace)
+            .unwrap()
+            .insert(0, "Iosevka Clapoto".to_owned());
+        fonts
+    }
+
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        cc.egui_ctx.set_fonts(Self::fonts());
+        // Customize egui here with cc.egui_ctx.set_fonts and cc.egui_ctx.set_visuals.
+        // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
+        // for e.g. egui::PaintCallback.
+        cc.storage.and_then(Self::restore).unwrap_or_default()
+    }
+
+    /// Restores the state saved in `storage`, migrating it from older versions.
+    fn restore(storage: &dyn eframe::Storage) -> Option<Self> {
+        let mut state: Self = eframe::get_value(storage, eframe::APP_KEY)?;
+        match state.version {
+            // Version 1 saved the OkHSV and OkHSL pickers without alpha, as
+            // `colour` and `colour_too`
+            1 => {
+                let old: StateV1 = eframe::get_value(storage, eframe::APP_KEY)?;
+                state.okhsv = colors::Alpha::opaque(old.colour);
+                state.okhsl = colors::Alpha::opaque(old.colour_too);
+                Some(state)
+            }
+            STATE_VERSION => Some(state),
+            version => {
+                tracing::warn!(
+                    "Ignoring saved state with unknown version {version} (expected at most {STATE_VERSION})"
+                );
+                None
+            }
+        }
+    }
+
+    /// Loads the color in all the pickers.
+    fn load_color(&mut self, color: colors::Srgb) {
+        self.color = Color32::from(color).into();
+        self.okhsv.color = color;
+        self.okhsl.color = color;
+        self.colour_lch = color;
+    }
+
+    fn render_export(&mut self, ctx: &Context) {
+        Window::new("Save palette as…")
+            .open(&mut self.export_open)
+            .show(ctx, |ui| {
+                widgets::export::export_panel(ui, &mut self.export, &self.palette);
+            });
+    }
+
+    fn render_eyedropper(&mut self, ctx: &Context) {
+        if self.eyedropper.load_dropped_images(ctx) {
+            self.eyedropper_open = true;
+        }
+        let mut picked = None;
+        Window::new("Pick from an image")
+            .open(&mut self.eyedropper_open)
+            .show(ctx, |ui| {
+                picked = widgets::eyedropper::eyedropper_panel(
+                    ui,
+                    &mut self.eyedropper,
+                    &mut self.palette,
+                );
+            });
+        if let Some(color) = picked {
+            self.load_color(color);
+        }
+    }
+
+    fn render_import(&mut self, ctx: &Context) {
+        if self.import.load_dropped_files(ctx, &mut self.palette) {
+            self.import_open = true;
+        }
+        Window::new("Import palette")
+            .open(&mut self.import_open)
+            .show(ctx, |ui| {
+                widgets::import::import_panel(ui, &mut self.import, &mut self.palette);
+            });
+    }
+
+    fn render_palette(&mut self, ctx: &Context) {
+        SidePanel::right("palette").show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(5.);
+                ui.heading("Palette");
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("📂 Import…")
+                        .on_hover_text("Load colors from a palette file")
+                        .clicked()
+                    {
+                        self.import_open = true;
+                    }
+                    if ui
+                        .button("🖼 Image…")
+                        .on_hover_text("Pick colors from an image file")
+                        .clicked()
+                    {
+                        self.eyedropper_open = true;
+                    }
+                    if ui
+                        .button("💾 Save as…")
+                        .on_hover_text("Export the palette to a theme format")
+                        .clicked()
+                    {
+                        self.export_open = true;
+                    }
+                });
+                ui.add_space(5.);
+            });
+            ScrollArea::vertical().show(ui, |ui| {
+                if let Some(color) = widgets::palette::palette_panel(ui, &mut self.palette) {
+                    self.load_color(color);
+                }
+                if self.cvd.preview {
+                    widgets::cvd::confusion_warning_ui(ui, &self.cvd, &self.palette);
+                }
+
+                ui.add_space(5.0);
+                CollapsingHeader::new("Generator").show(ui, |ui| {
+                    if let Some(swatches) =
+                        widgets::generator::generator_panel(ui, &mut self.generator)
+                    {
+                        self.palette = swatches;
+                    }
+                });
+
+                ui.add_space(5.0);
+                CollapsingHeader::new("Gradient").show(ui, |ui| {
+                    if let Some(swatches) =
+                        widgets::gradient::gradient_panel(ui, &mut self.gradient)
+                    {
+                        self.palette.extend(swatches);
+                    }
+                });
+
+                ui.add_space(5.0);
+                CollapsingHeader::new("Contrast").show(ui, |ui| {
+                    widgets::contrast::contrast_panel(ui, &mut self.background, &self.palette);
+                });
+
+                ui.add_space(5.0);
+                CollapsingHeader::new("Color vision").show(ui, |ui| {
+                    widgets::cvd::cvd_panel(ui, &mut self.cvd, &self.palette);
+                });
+            });
+        });
+    }
+}
+
+fn render_footer(ctx: &Context) {
+    TopBottomPanel::bottom("Footer").show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(5.);
+            ui.add(Hyperlink::from_label_and_url(
+                "Source",
+                "https://github.com/gagbo/ok-picker",
+            ));
+            ui.add_space(5.);
+        })
+    });
+}
+
+fn render_header(ctx: &Context) {
+    TopBottomPanel::top("header").show(ctx, |ui| {
+        ui.vertical_centered(|ui| {
+            ui.add_space(5.);
+            ui.label("Experiments for a color picker app in a better color space.");
+            ui.add_space(5.);
+        })
+    });
+}
+impl eframe::App for OkPicker {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.version = STATE_VERSION;
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        // Set at the start of the frame, for all the widgets
+        self.cvd.preview().store(ctx);
+        render_footer(ctx);
+        render_header(ctx);
+        self.render_palette(ctx);
+        self.render_export(ctx);
+        // Before the import, which takes all the other dropped files
+        self.render_eyedropper(ctx);
+        self.render_import(ctx);
+        CentralPanel::default().show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("RGB");
+                    ui.spacing_mut().slider_width = 100.0;
+                    egui::widgets::color_picker::color_picker_hsva_2d(
+                        ui,
+                        &mut self.color,
+                        egui::color_picker::Alpha::Opaque,
+                    );
+                    let rgb = colors::Srgb::from(Color32::from(self.color));
+                    widgets::palette::add_swatch_button(ui, &mut self.palette, rgb);
+                    if let Some(color) = widgets::palette::nearest_swatch_ui(ui, &self.palette, rgb)
+                    {
+                        self.load_color(color);
+                    }
+                });
+
+                ui.add_space(5.0);
+                ui.separator();
+                ui.add_space(5.0);
+
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("OkHSV");
+                    ui.spacing_mut().slider_width = 100.0;
+                    widgets::okhsv::color_picker_2d_alpha_with_resolution(
+                        ui,
+                        &mut self.okhsv,
+                        widgets::DEFAULT_MESH_RESOLUTION,
+                    );
+                    widgets::palette::add_swatch_button(ui, &mut self.palette, self.okhsv.color);
+                    if let Some(color) =
+                        widgets::palette::nearest_swatch_ui(ui, &self.palette, self.okhsv.color)
+                    {
+                        self.load_color(color);
+                    }
+                });
+
+                ui.add_space(5.0);
+                ui.separator();
+                ui.add_space(5.0);
+
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("OkHSL");
+                    ui.spacing_mut().slider_width = 100.0;
+                    widgets::okhsl::color_picker_circle_alpha_with_resolution(
+                        ui,
+                        &mut self.okhsl,
+                        widgets::DEFAULT_MESH_RESOLUTION,
+                    );
+                    widgets::palette::add_swatch_button(ui, &mut self.palette, self.okhsl.color);
+                    if let Some(color) =
+                        widgets::palette::nearest_swatch_ui(ui, &self.palette, self.okhsl.color)
+                    {
+                        self.load_color(color);
+                    }
+                });
+
+                ui.add_space(5.0);
+                ui.separator();
+                ui.add_space(5.0);
+
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("OkLCh");
+                    ui.spacing_mut().slider_width = 100.0;
+                    widgets::oklch::color_picker_2d_with_resolution(
+                        ui,
+                        &mut self.colour_lch,
+                        widgets::DEFAULT_MESH_RESOLUTION,
+                    );
+                    widgets::palette::add_swatch_button(ui, &mut self.palette, self.colour_lch);
+                    if let Some(color) =
+                        widgets::palette::nearest_swatch_ui(ui, &self.palette, self.colour_lch)
+                    {
+                        self.load_color(color);
+                    }
+                });
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use eframe::{App, Storage};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    #[test]
+    fn migrate_version_1() {
+        let mut storage = MemoryStorage::default();
+        storage.set_string(
+            eframe::APP_KEY,
+            "(version: 1, colour: (red: 1.0, green: 0.5, blue: 0.0), \
+             colour_too: (red: 0.0, green: 0.5, blue: 1.0), \
+             palette: [(name: \"kept\", color: (red: 0.1, green: 0.2, blue: 0.3))])"
+                .to_owned(),
+        );
+
+        let state = OkPicker::restore(&storage).expect("version 1 should load");
+        assert_eq!(state.okhsv.color.green, 0.5);
+        assert_eq!(state.okhsv.alpha, 1.0);
+        assert_eq!(state.okhsl.color.blue, 1.0);
+        assert_eq!(state.palette[0].name, "kept");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = MemoryStorage::default();
+        let mut state = OkPicker::default();
+        state.okhsv.alpha = 0.25;
+        state.save(&mut storage);
+
+        let restored = OkPicker::restore(&storage).expect("the state should load");
+        assert_eq!(restored.version, STATE_VERSION);
+        assert_eq!(restored.okhsv.alpha, 0.25);
+    }
+}
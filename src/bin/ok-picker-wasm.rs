@@ -2,6 +2,35 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+//! Web entry point of Ok Picker.
+//!
+//! Build it with [Trunk](https://trunkrs.dev) from the root of the repository,
+//! see the README for the details.
+
+/// Id of the canvas the app is drawn in, see `index.html`.
+#[cfg(target_arch = "wasm32")]
+const CANVAS_ID: &str = "ok_picker_canvas";
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use ok_picker::app::OkPicker;
+
+    // Log panics and traces to the browser console
+    console_error_panic_hook::set_once();
+    tracing_wasm::set_as_global_default();
+
+    eframe::start_web(
+        CANVAS_ID,
+        eframe::WebOptions::default(),
+        Box::new(|cc| Box::new(OkPicker::new(cc))),
+    )
+    .expect("failed to start the app in the browser");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    println!("Hello, world!");
+    eprintln!(
+        "ok-picker-wasm only runs in a browser, build it for wasm32-unknown-unknown (see the README)"
+    );
+    std::process::exit(1);
 }
@@ -0,0 +1,763 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Command line interface of the native binary
+//!
+//! Without arguments, the binary opens the GUI. The `convert` subcommand
+//! converts colors between [`Notation`]s without opening any window:
+//!
+//! ```text
+//! ok-picker convert '#6666b3' --to okhsl,oklch,hex
+//! cat colors.txt | ok-picker convert --to hex --json
+//! ```
+//!
+//! The `import` subcommand lists the colors of a palette file:
+//!
+//! ```text
+//! ok-picker import brand.ase --to hex,oklch
+//! ```
+//!
+//! The `extract` subcommand lists the dominant colors of an image:
+//!
+//! ```text
+//! ok-picker extract wallpaper.png -k 8
+//! ```
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    ser::{SerializeMap, SerializeSeq},
+    Serialize, Serializer,
+};
+
+use crate::{
+    colors::{format::Notation, Alpha, Srgb},
+    palette::{
+        extract::{dominant_colors, DEFAULT_COUNT},
+        import::{import, import_detect, ImportFormat},
+    },
+};
+
+pub const USAGE: &str = "\
+Usage:
+    ok-picker                       Open the color picker
+    ok-picker convert [OPTIONS] [COLOR...]
+                                    Convert colors between notations
+    ok-picker import [OPTIONS] <FILE>
+                                    List the colors of a palette file
+    ok-picker extract [OPTIONS] <IMAGE>
+                                    List the dominant colors of an image
+    ok-picker help                  Show this message
+
+Convert options:
+    -t, --to <NOTATION,...>  Notations to convert to, among hex, hexa, rgb,
+                             rgba, okhsv, okhsl, oklab, oklch, lab, lch and
+                             display-p3 (default: all of them)
+    --json                   Output a JSON array instead of text
+
+Colors are read from the arguments, or from the standard input (one per line)
+when there are none or when one of them is `-`. Text output has one line per
+color, with the converted values separated by tabs, or empty when the color
+doesn't parse.
+
+Import options:
+    -f, --format <FORMAT>    Format of the file, among gpl, ase and json
+                             (default: guessed from the file)
+    -t, --to <NOTATION,...>  Notations to show the colors in (default: hex)
+    --json                   Output a JSON array instead of text
+
+The file is read from the standard input when it is `-`. Text output has one
+line per color, with the values separated by tabs and followed by the name.
+
+Extract options:
+    -k, --colors <K>         Number of colors to extract (default: 8)
+    -t, --to <NOTATION,...>  Notations to show the colors in (default: hex)
+    --json                   Output a JSON array instead of text
+
+The image is a PNG or JPEG file, read from the standard input when it is `-`.
+The colors are sorted by the share of the image they cover. Text output has
+one line per color, with the values separated by tabs and followed by the
+coverage.";
+
+/// What the binary has been asked to do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Gui,
+    Help,
+    Convert(Convert),
+    Import(Import),
+    Extract(Extract),
+}
+
+/// Invalid command line arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the command line arguments, without the program name.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        None => Ok(Command::Gui),
+        Some("help" | "-h" | "--help") => Ok(Command::Help),
+        Some("convert") => Convert::from_args(args).map(Command::Convert),
+        Some("import") => Import::from_args(args).map(Command::Import),
+        Some("extract") => Extract::from_args(args).map(Command::Extract),
+        Some(other) => Err(CliError(format!("unknown command {other:?}"))),
+    }
+}
+
+/// The `convert` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Convert {
+    /// The colors given as arguments, `-` standing for the standard input
+    pub colors: Vec<String>,
+    pub to: Vec<Notation>,
+    pub json: bool,
+}
+
+impl Convert {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let mut colors = Vec::new();
+        let mut to = None;
+        let mut json = false;
+        let mut only_colors = false;
+
+        while let Some(arg) = args.next() {
+            if only_colors {
+                colors.push(arg);
+                continue;
+            }
+            match arg.as_str() {
+                "--" => only_colors = true,
+                "--json" => json = true,
+                "-t" | "--to" => {
+                    let list = args
+                        .next()
+                        .ok_or_else(|| CliError(format!("{arg} expects a list of notations")))?;
+                    to = Some(parse_notations(&list)?);
+                }
+                _ => match arg.strip_prefix("--to=") {
+                    Some(list) => to = Some(parse_notations(list)?),
+                    None if arg.starts_with("--") => {
+                        return Err(CliError(format!("unknown option {arg:?}")));
+                    }
+                    None => colors.push(arg),
+                },
+            }
+        }
+
+        if colors.is_empty() {
+            colors.push("-".to_owned());
+        }
+
+        Ok(Self {
+            colors,
+            to: to.unwrap_or_else(|| Notation::ALL.to_vec()),
+            json,
+        })
+    }
+
+    /// Converts all the colors, reading `-` from `input` line by line.
+    ///
+    /// Each color is written as soon as it is read. Colors that fail to
+    /// parse are reported to `errors`, and don't stop the conversion of the
+    /// others: text output has an empty line in their place, so that output
+    /// lines match input lines. Returns `true` if all the colors converted.
+    pub fn run(
+        &self,
+        input: impl BufRead,
+        mut output: impl Write,
+        mut errors: impl Write,
+    ) -> io::Result<bool> {
+        let mut success = true;
+        let mut report = |entry: &Entry| {
+            if let Err(err) = &entry.converted {
+                success = false;
+                writeln!(errors, "error: {:?}: {err}", entry.input)?;
+            }
+            Ok::<_, io::Error>(())
+        };
+
+        if self.json {
+            let mut serializer = serde_json::Serializer::pretty(&mut output);
+            let mut entries = serializer.serialize_seq(None)?;
+            self.for_each_input(input, |input| {
+                if input.is_empty() {
+                    return Ok(());
+                }
+                let entry = self.convert(input);
+                report(&entry)?;
+                entries.serialize_element(&entry)?;
+                Ok(())
+            })?;
+            SerializeSeq::end(entries)?;
+            writeln!(output)?;
+        } else {
+            self.for_each_input(input, |input| {
+                if input.is_empty() {
+                    return writeln!(output);
+                }
+                let entry = self.convert(input);
+                report(&entry)?;
+                match &entry.converted {
+                    Ok(values) => {
+                        let values: Vec<&str> =
+                            values.iter().map(|(_, value)| value.as_str()).collect();
+                        writeln!(output, "{}", values.join("\t"))
+                    }
+                    Err(_) => writeln!(output),
+                }
+            })?;
+        }
+
+        Ok(success)
+    }
+
+    /// Calls `f` with each color as it is read, trimmed, reading `-` from
+    /// `input`.
+    fn for_each_input(
+        &self,
+        input: impl BufRead,
+        mut f: impl FnMut(&str) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut lines = Some(input.lines());
+        for color in &self.colors {
+            if color != "-" {
+                f(color)?;
+                continue;
+            }
+            // The standard input can only be read once
+            if let Some(lines) = lines.take() {
+                for line in lines {
+                    f(line?.trim())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn convert<'a>(&self, input: &'a str) -> Entry<'a> {
+        let converted = input.parse::<Alpha<Srgb>>().map(|color| {
+            self.to
+                .iter()
+                .map(|notation| (*notation, notation.format_alpha(color)))
+                .collect()
+        });
+        Entry {
+            label: "input",
+            input,
+            converted: converted.map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// The `import` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    /// The palette file, `-` standing for the standard input
+    pub file: PathBuf,
+    /// The format of the file, guessed when `None`
+    pub format: Option<ImportFormat>,
+    pub to: Vec<Notation>,
+    pub json: bool,
+}
+
+impl Import {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let mut file = None;
+        let mut format = None;
+        let mut to = None;
+        let mut json = false;
+
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value)),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .map(str::to_owned)
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError(format!("{option} expects a value")))
+            };
+            match option {
+                "--json" => json = true,
+                "-f" | "--format" => {
+                    let name = value()?;
+                    format = Some(
+                        ImportFormat::from_name(&name)
+                            .ok_or_else(|| CliError(format!("unknown format {name:?}")))?,
+                    );
+                }
+                "-t" | "--to" => to = Some(parse_notations(&value()?)?),
+                _ if option.starts_with("--") => {
+                    return Err(CliError(format!("unknown option {arg:?}")));
+                }
+                _ if file.is_none() => file = Some(PathBuf::from(arg)),
+                _ => return Err(CliError(format!("unexpected argument {arg:?}"))),
+            }
+        }
+
+        Ok(Self {
+            file: file.ok_or_else(|| CliError("import expects a palette file".to_owned()))?,
+            format,
+            to: to.unwrap_or_else(|| vec![Notation::Hex]),
+            json,
+        })
+    }
+
+    /// Lists the colors of the palette, reading `-` from `input`.
+    ///
+    /// Returns `false` if the palette could not be imported, after reporting
+    /// why to `errors`.
+    pub fn run(
+        &self,
+        mut input: impl Read,
+        mut output: impl Write,
+        mut errors: impl Write,
+    ) -> io::Result<bool> {
+        let (path, bytes) = if self.file == Path::new("-") {
+            let mut bytes = Vec::new();
+            input.read_to_end(&mut bytes)?;
+            (None, bytes)
+        } else {
+            match std::fs::read(&self.file) {
+                Ok(bytes) => (Some(self.file.as_path()), bytes),
+                Err(err) => {
+                    writeln!(errors, "error: {}: {err}", self.file.display())?;
+                    return Ok(false);
+                }
+            }
+        };
+
+        let imported = match self.format {
+            Some(format) => import(format, &bytes),
+            None => import_detect(path, &bytes),
+        };
+        let palette = match imported {
+            Ok(palette) => palette,
+            Err(err) => {
+                writeln!(errors, "error: {}: {err}", self.file.display())?;
+                return Ok(false);
+            }
+        };
+
+        if self.json {
+            let entries: Vec<Entry> = palette
+                .swatches
+                .iter()
+                .map(|swatch| Entry {
+                    label: "name",
+                    input: swatch.name.as_str(),
+                    converted: Ok(self
+                        .to
+                        .iter()
+                        .map(|notation| (*notation, notation.format(swatch.color)))
+                        .collect()),
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut output, &entries)?;
+            writeln!(output)?;
+        } else {
+            for swatch in &palette.swatches {
+                for notation in &self.to {
+                    write!(output, "{}\t", notation.format(swatch.color))?;
+                }
+                writeln!(output, "{}", swatch.name)?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// The `extract` subcommand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Extract {
+    /// The image file, `-` standing for the standard input
+    pub file: PathBuf,
+    /// Number of colors to extract
+    pub count: usize,
+    pub to: Vec<Notation>,
+    pub json: bool,
+}
+
+impl Extract {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let mut file = None;
+        let mut count = DEFAULT_COUNT;
+        let mut to = None;
+        let mut json = false;
+
+        while let Some(arg) = args.next() {
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => (option, Some(value)),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .map(str::to_owned)
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError(format!("{option} expects a value")))
+            };
+            match option {
+                "--json" => json = true,
+                "-k" | "--colors" => {
+                    let value = value()?;
+                    count = value
+                        .parse()
+                        .ok()
+                        .filter(|&count| count > 0)
+                        .ok_or_else(|| {
+                            CliError(format!("{option} expects a positive number, not {value:?}"))
+                        })?;
+                }
+                "-t" | "--to" => to = Some(parse_notations(&value()?)?),
+                _ if option.starts_with("--") => {
+                    return Err(CliError(format!("unknown option {arg:?}")));
+                }
+                _ if file.is_none() => file = Some(PathBuf::from(arg)),
+                _ => return Err(CliError(format!("unexpected argument {arg:?}"))),
+            }
+        }
+
+        Ok(Self {
+            file: file.ok_or_else(|| CliError("extract expects an image file".to_owned()))?,
+            count,
+            to: to.unwrap_or_else(|| vec![Notation::Hex]),
+            json,
+        })
+    }
+
+    /// Lists the dominant colors of the image, reading `-` from `input`.
+    ///
+    /// Returns `false` if the image could not be read or decoded, after
+    /// reporting why to `errors`.
+    pub fn run(
+        &self,
+        mut input: impl Read,
+        mut output: impl Write,
+        mut errors: impl Write,
+    ) -> io::Result<bool> {
+        let bytes = if self.file == Path::new("-") {
+            let mut bytes = Vec::new();
+            input.read_to_end(&mut bytes)?;
+            bytes
+        } else {
+            match std::fs::read(&self.file) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    writeln!(errors, "error: {}: {err}", self.file.display())?;
+                    return Ok(false);
+                }
+            }
+        };
+
+        let image = match image::load_from_memory(&bytes) {
+            Ok(image) => image.to_rgba8(),
+            Err(err) => {
+                writeln!(errors, "error: {}: {err}", self.file.display())?;
+                return Ok(false);
+            }
+        };
+        let dominants = dominant_colors(image.as_raw(), self.count);
+
+        if self.json {
+            let entries: Vec<Entry<f64>> = dominants
+                .iter()
+                .map(|dominant| Entry {
+                    label: "coverage",
+                    input: &dominant.coverage,
+                    converted: Ok(self
+                        .to
+                        .iter()
+                        .map(|notation| (*notation, notation.format(dominant.color)))
+                        .collect()),
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut output, &entries)?;
+            writeln!(output)?;
+        } else {
+            for dominant in &dominants {
+                for notation in &self.to {
+                    write!(output, "{}\t", notation.format(dominant.color))?;
+                }
+                writeln!(output, "{:.1}%", 100.0 * dominant.coverage)?;
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+fn parse_notations(list: &str) -> Result<Vec<Notation>, CliError> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            Notation::from_name(name).ok_or_else(|| CliError(format!("unknown notation {name:?}")))
+        })
+        .collect()
+}
+
+/// A converted color, serialized as an object with the input (under
+/// `label`), and either the converted values keyed by notation or the error.
+struct Entry<'a, T: ?Sized = str> {
+    label: &'static str,
+    input: &'a T,
+    converted: Result<Vec<(Notation, String)>, String>,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Entry<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry(self.label, self.input)?;
+        match &self.converted {
+            Ok(values) => {
+                for (notation, value) in values {
+                    map.serialize_entry(&notation.name().to_ascii_lowercase(), value)?;
+                }
+            }
+            Err(err) => map.serialize_entry("error", err)?,
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn run(convert: &Convert, input: &str) -> (bool, String, String) {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        let success = convert
+            .run(input.as_bytes(), &mut output, &mut errors)
+            .unwrap();
+        (
+            success,
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(errors).unwrap(),
+        )
+    }
+
+    #[test]
+    fn parse_command_line() {
+        assert_eq!(parse_args(args(&[])), Ok(Command::Gui));
+        assert_eq!(parse_args(args(&["--help"])), Ok(Command::Help));
+        assert!(parse_args(args(&["frobnicate"])).is_err());
+        assert!(parse_args(args(&["convert", "--to", "cmyk"])).is_err());
+        assert!(parse_args(args(&["convert", "--to"])).is_err());
+
+        assert_eq!(
+            parse_args(args(&[
+                "convert",
+                "#6666b3",
+                "--to",
+                "okhsl,OkLCh,hex",
+                "--json"
+            ])),
+            Ok(Command::Convert(Convert {
+                colors: args(&["#6666b3"]),
+                to: vec![Notation::OkHsl, Notation::OkLCh, Notation::Hex],
+                json: true,
+            }))
+        );
+        assert_eq!(
+            parse_args(args(&["convert", "--to=rgb"])),
+            Ok(Command::Convert(Convert {
+                colors: args(&["-"]),
+                to: vec![Notation::Rgb],
+                json: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn convert_text() {
+        let convert = Convert {
+            colors: args(&["#6666b3", "-"]),
+            to: vec![Notation::Hex, Notation::Rgb],
+            json: false,
+        };
+        let (success, output, errors) = run(&convert, "rgb(0, 0, 0)\n\n  #fff  \n");
+        assert!(success);
+        assert_eq!(
+            output,
+            "#6666B3\trgb(102, 102, 179)\n#000000\trgb(0, 0, 0)\n\n#FFFFFF\trgb(255, 255, 255)\n"
+        );
+        assert!(errors.is_empty());
+
+        // A line for each input line, even the ones that fail
+        let (success, output, errors) = run(&convert, "nope\n#fff\n");
+        assert!(!success);
+        assert_eq!(
+            output,
+            "#6666B3\trgb(102, 102, 179)\n\n#FFFFFF\trgb(255, 255, 255)\n"
+        );
+        assert!(errors.contains("\"nope\""));
+    }
+
+    #[test]
+    fn convert_streaming() {
+        /// An input that breaks after its first line.
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+
+        let convert = Convert {
+            colors: args(&["-"]),
+            to: vec![Notation::Hex],
+            json: false,
+        };
+        let input = io::BufReader::new("#fff\n".as_bytes().chain(Broken));
+        let mut output = Vec::new();
+        assert!(convert.run(input, &mut output, io::sink()).is_err());
+        assert_eq!(String::from_utf8(output).unwrap(), "#FFFFFF\n");
+    }
+
+    #[test]
+    fn convert_json() {
+        let convert = Convert {
+            colors: args(&["#6666b3", "nope"]),
+            to: vec![Notation::Hex],
+            json: true,
+        };
+        let (success, output, errors) = run(&convert, "");
+        assert!(!success);
+        assert!(errors.contains("\"nope\""));
+
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json[0]["input"], "#6666b3");
+        assert_eq!(json[0]["hex"], "#6666B3");
+        assert_eq!(json[1]["input"], "nope");
+        assert!(json[1]["error"].is_string());
+    }
+
+    #[test]
+    fn import_palette() {
+        assert!(parse_args(args(&["import"])).is_err());
+        assert!(parse_args(args(&["import", "a.gpl", "b.gpl"])).is_err());
+        assert!(parse_args(args(&["import", "a", "--format", "png"])).is_err());
+
+        let command = parse_args(args(&["import", "-", "--format=gpl", "-t", "hex,rgb"]));
+        let Ok(Command::Import(import)) = command else {
+            panic!("unexpected {command:?}");
+        };
+        assert_eq!(import.format, Some(ImportFormat::Gpl));
+
+        let gpl = "GIMP Palette\nName: Test\n255 0 0\tRed\n0 0 255 Deep Blue\n";
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        assert!(import
+            .run(gpl.as_bytes(), &mut output, &mut errors)
+            .unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "#FF0000\trgb(255, 0, 0)\tRed\n#0000FF\trgb(0, 0, 255)\tDeep Blue\n"
+        );
+
+        let mut output = Vec::new();
+        assert!(!import
+            .run("GIMP Palette\n1 2".as_bytes(), &mut output, &mut errors)
+            .unwrap());
+        assert!(String::from_utf8(errors).unwrap().contains("line 2"));
+
+        let missing = Import {
+            file: PathBuf::from("missing.gpl"),
+            ..import
+        };
+        let mut errors = Vec::new();
+        assert!(!missing.run(io::empty(), io::sink(), &mut errors).unwrap());
+        assert!(String::from_utf8(errors)
+            .unwrap()
+            .starts_with("error: missing.gpl: "));
+    }
+
+    #[test]
+    fn extract_colors() {
+        assert!(parse_args(args(&["extract"])).is_err());
+        assert!(parse_args(args(&["extract", "a.png", "-k", "0"])).is_err());
+        assert!(parse_args(args(&["extract", "a.png", "--colors=many"])).is_err());
+
+        let command = parse_args(args(&["extract", "-", "-k", "2", "--to=hex,rgb"]));
+        let Ok(Command::Extract(extract)) = command else {
+            panic!("unexpected {command:?}");
+        };
+        assert_eq!(extract.count, 2);
+
+        // 3 red pixels for 1 blue one
+        let image = image::RgbaImage::from_fn(2, 2, |x, y| {
+            if x + y == 0 {
+                image::Rgba([0, 0, 255, 255])
+            } else {
+                image::Rgba([255, 0, 0, 255])
+            }
+        });
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image)
+            .write_to(
+                &mut io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        assert!(extract
+            .run(png.as_slice(), &mut output, &mut errors)
+            .unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "#FF0000\trgb(255, 0, 0)\t75.0%\n#0000FF\trgb(0, 0, 255)\t25.0%\n"
+        );
+
+        let mut output = Vec::new();
+        assert!(!extract
+            .run("not an image".as_bytes(), &mut output, &mut errors)
+            .unwrap());
+        assert!(output.is_empty());
+        assert!(String::from_utf8(errors).unwrap().starts_with("error: -"));
+
+        let json = Extract {
+            json: true,
+            ..extract.clone()
+        };
+        let mut output = Vec::new();
+        assert!(json.run(png.as_slice(), &mut output, io::sink()).unwrap());
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["coverage"], 0.75);
+        assert_eq!(json[0]["hex"], "#FF0000");
+
+        let missing = Extract {
+            file: PathBuf::from("missing.png"),
+            ..extract
+        };
+        let mut errors = Vec::new();
+        assert!(!missing.run(io::empty(), io::sink(), &mut errors).unwrap());
+        assert!(String::from_utf8(errors)
+            .unwrap()
+            .starts_with("error: missing.png: "));
+    }
+}
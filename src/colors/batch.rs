@@ -0,0 +1,297 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Conversions of whole slices of colors
+//!
+//! Images and large palettes are converted 4 colors at a time with SIMD
+//! lanes, and with the `rayon` feature the slices are also split between
+//! threads. The results match the `From` implementations up to rounding.
+//!
+//! Byte buffers are interleaved RGB8 or RGBA8, with the same rounding as
+//! [`Color32`](eframe::egui::Color32): colors out of the sRGB gamut have
+//! their channels clamped.
+//!
+//! All the functions panic if the output doesn't have the length of the
+//! input.
+
+use wide::{f64x4, CmpEq, CmpGe};
+
+use super::{
+    conversions::{LIN_SRGB_TO_LMS, LMS_TO_LIN_SRGB, LMS_TO_OKLAB, OKLAB_TO_LMS},
+    Alpha, OkLab, Srgb,
+};
+
+const LANES: usize = 4;
+
+/// Number of colors converted by each rayon task.
+#[cfg(feature = "rayon")]
+const TASK_LEN: usize = 4096;
+
+type Channels = [f64x4; 3];
+
+/// Converts sRGB colors to OkLab.
+pub fn srgb_to_oklab_slice(input: &[Srgb], output: &mut [OkLab]) {
+    convert(
+        input,
+        output,
+        |[rgb]: &[Srgb; 1]| [rgb.red, rgb.green, rgb.blue],
+        srgb_to_oklab,
+        |[lightness, a, b], [lab]: &mut [OkLab; 1]| *lab = OkLab { lightness, a, b },
+    );
+}
+
+/// Converts OkLab colors to sRGB, without any gamut mapping.
+pub fn oklab_to_srgb_slice(input: &[OkLab], output: &mut [Srgb]) {
+    convert(
+        input,
+        output,
+        |[lab]: &[OkLab; 1]| [lab.lightness, lab.a, lab.b],
+        oklab_to_srgb,
+        |[red, green, blue], [rgb]: &mut [Srgb; 1]| *rgb = Srgb { red, green, blue },
+    );
+}
+
+/// Converts an interleaved RGB8 buffer to OkLab.
+pub fn rgb8_to_oklab_slice(bytes: &[u8], output: &mut [OkLab]) {
+    convert(
+        bytes,
+        output,
+        |pixel: &[u8; 3]| pixel.map(channel_from_u8),
+        srgb_to_oklab,
+        |[lightness, a, b], [lab]: &mut [OkLab; 1]| *lab = OkLab { lightness, a, b },
+    );
+}
+
+/// Converts an interleaved RGBA8 buffer to OkLab, with the alpha.
+pub fn rgba8_to_oklab_slice(bytes: &[u8], output: &mut [Alpha<OkLab>]) {
+    convert(
+        bytes,
+        output,
+        |&[red, green, blue, _]: &[u8; 4]| [red, green, blue].map(channel_from_u8),
+        srgb_to_oklab,
+        |[lightness, a, b], [lab]: &mut [Alpha<OkLab>; 1]| lab.color = OkLab { lightness, a, b },
+    );
+    for (color, pixel) in output.iter_mut().zip(bytes.chunks_exact(4)) {
+        color.alpha = channel_from_u8(pixel[3]);
+    }
+}
+
+/// Converts OkLab colors to an interleaved RGB8 buffer.
+pub fn oklab_to_rgb8_slice(input: &[OkLab], bytes: &mut [u8]) {
+    convert(
+        input,
+        bytes,
+        |[lab]: &[OkLab; 1]| [lab.lightness, lab.a, lab.b],
+        oklab_to_srgb,
+        |rgb, pixel: &mut [u8; 3]| *pixel = rgb.map(channel_to_u8),
+    );
+}
+
+/// Converts OkLab colors with alpha to an interleaved RGBA8 buffer.
+pub fn oklab_to_rgba8_slice(input: &[Alpha<OkLab>], bytes: &mut [u8]) {
+    convert(
+        input,
+        bytes,
+        |[lab]: &[Alpha<OkLab>; 1]| [lab.color.lightness, lab.color.a, lab.color.b],
+        oklab_to_srgb,
+        |[red, green, blue], pixel: &mut [u8; 4]| {
+            let [red, green, blue] = [red, green, blue].map(channel_to_u8);
+            pixel[..3].copy_from_slice(&[red, green, blue]);
+        },
+    );
+    for (pixel, color) in bytes.chunks_exact_mut(4).zip(input) {
+        pixel[3] = channel_to_u8(color.alpha);
+    }
+}
+
+fn channel_from_u8(value: u8) -> f64 {
+    f64::from(value) / 255.0
+}
+
+fn channel_to_u8(value: f64) -> u8 {
+    (value * 256.0).floor() as u8
+}
+
+/// Runs `kernel` over `input` 4 colors at a time, through the `load` and
+/// `store` accessors of the channels.
+///
+/// Each color spans `IN` items of `input` and `OUT` items of `output`, so
+/// byte buffers are read and written in place, one pixel at a time.
+fn convert<I: Sync, O: Send, const IN: usize, const OUT: usize>(
+    input: &[I],
+    output: &mut [O],
+    load: impl Fn(&[I; IN]) -> [f64; 3] + Sync,
+    kernel: fn(Channels) -> Channels,
+    store: impl Fn([f64; 3], &mut [O; OUT]) + Sync,
+) {
+    assert!(
+        input.len() % IN == 0,
+        "{} input items aren't {IN} channel pixels",
+        input.len()
+    );
+    assert!(
+        output.len() % OUT == 0,
+        "{} output items aren't {OUT} channel pixels",
+        output.len()
+    );
+    assert_eq!(
+        input.len() / IN,
+        output.len() / OUT,
+        "the output length differs from the input"
+    );
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        input
+            .par_chunks(TASK_LEN * IN)
+            .zip(output.par_chunks_mut(TASK_LEN * OUT))
+            .for_each(|(input, output)| convert_lanes(input, output, &load, kernel, &store));
+    }
+    #[cfg(not(feature = "rayon"))]
+    convert_lanes(input, output, &load, kernel, &store);
+}
+
+fn convert_lanes<I, O, const IN: usize, const OUT: usize>(
+    input: &[I],
+    output: &mut [O],
+    load: &impl Fn(&[I; IN]) -> [f64; 3],
+    kernel: fn(Channels) -> Channels,
+    store: &impl Fn([f64; 3], &mut [O; OUT]),
+) {
+    for (input, output) in input.chunks(LANES * IN).zip(output.chunks_mut(LANES * OUT)) {
+        // The missing colors of the last chunk stay black
+        let mut lanes = [[0.0; LANES]; 3];
+        for (lane, color) in input.chunks_exact(IN).enumerate() {
+            let [first, second, third] = load(color.try_into().expect("chunks have IN items"));
+            lanes[0][lane] = first;
+            lanes[1][lane] = second;
+            lanes[2][lane] = third;
+        }
+
+        let [first, second, third] = kernel(lanes.map(f64x4::new)).map(f64x4::to_array);
+        for (lane, color) in output.chunks_exact_mut(OUT).enumerate() {
+            store(
+                [first[lane], second[lane], third[lane]],
+                color.try_into().expect("chunks have OUT items"),
+            );
+        }
+    }
+}
+
+fn multiply(matrix: &[[f64; 3]; 3], [x, y, z]: Channels) -> Channels {
+    matrix.map(|[first, second, third]| {
+        x.mul_add(
+            f64x4::splat(first),
+            y.mul_add(f64x4::splat(second), z * f64x4::splat(third)),
+        )
+    })
+}
+
+fn srgb_decode(value: f64x4) -> f64x4 {
+    value
+        .cmp_ge(f64x4::splat(0.040_45))
+        .blend(((value + 0.055) / 1.055).powf(2.4), value / 12.92)
+}
+
+fn srgb_encode(value: f64x4) -> f64x4 {
+    value.cmp_ge(f64x4::splat(0.003_130_8)).blend(
+        value
+            .powf(1.0 / 2.4)
+            .mul_add(f64x4::splat(1.055), f64x4::splat(-0.055)),
+        value * 12.92,
+    )
+}
+
+/// Cube root, refined by a Newton step since `powf` is less precise than
+/// [`f64::cbrt`].
+fn cbrt(value: f64x4) -> f64x4 {
+    let root = value.abs().powf(1.0 / 3.0).copysign(value);
+    let refined = root - (root * root * root - value) / (3.0 * root * root);
+    root.cmp_eq(f64x4::ZERO).blend(root, refined)
+}
+
+fn srgb_to_oklab(rgb: Channels) -> Channels {
+    let lms = multiply(&LIN_SRGB_TO_LMS, rgb.map(srgb_decode));
+    multiply(&LMS_TO_OKLAB, lms.map(cbrt))
+}
+
+fn oklab_to_srgb(lab: Channels) -> Channels {
+    let lms = multiply(&OKLAB_TO_LMS, lab).map(|value| value * value * value);
+    multiply(&LMS_TO_LIN_SRGB, lms).map(srgb_encode)
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+
+    use super::*;
+
+    /// 255 pixels, to hit a partial chunk, with all the byte values.
+    fn bytes(channels: usize) -> Vec<u8> {
+        (0..255 * channels)
+            .map(|index| (index * 37 % 256) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn slices() {
+        let colors: Vec<Srgb> = bytes(3)
+            .chunks(3)
+            .map(|pixel| Srgb::from(Color32::from_rgb(pixel[0], pixel[1], pixel[2])))
+            .collect();
+
+        let mut labs = vec![OkLab::default(); colors.len()];
+        srgb_to_oklab_slice(&colors, &mut labs);
+        for (color, lab) in colors.iter().zip(&labs) {
+            let expected = OkLab::from(*color);
+            assert!(
+                (lab.lightness - expected.lightness).abs() < 1e-12
+                    && (lab.a - expected.a).abs() < 1e-12
+                    && (lab.b - expected.b).abs() < 1e-12,
+                "{lab:?} isn't {expected:?}"
+            );
+        }
+
+        let mut back = vec![Srgb::default(); labs.len()];
+        oklab_to_srgb_slice(&labs, &mut back);
+        for (lab, rgb) in labs.iter().zip(&back) {
+            let expected = Srgb::from(*lab);
+            assert!(
+                (rgb.red - expected.red).abs() < 1e-12
+                    && (rgb.green - expected.green).abs() < 1e-12
+                    && (rgb.blue - expected.blue).abs() < 1e-12,
+                "{rgb:?} isn't {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn byte_buffers() {
+        let rgb = bytes(3);
+        let mut labs = vec![OkLab::default(); rgb.len() / 3];
+        rgb8_to_oklab_slice(&rgb, &mut labs);
+        let mut back = vec![0; rgb.len()];
+        oklab_to_rgb8_slice(&labs, &mut back);
+        assert_eq!(back, rgb);
+
+        let rgba = bytes(4);
+        let mut labs = vec![Alpha::opaque(OkLab::default()); rgba.len() / 4];
+        rgba8_to_oklab_slice(&rgba, &mut labs);
+        for (pixel, lab) in rgba.chunks(4).zip(&labs) {
+            let expected = Color32::from_rgba_unmultiplied(pixel[0], pixel[1], pixel[2], pixel[3]);
+            assert_eq!(Color32::from(lab.convert::<Srgb>()), expected);
+        }
+        let mut back = vec![0; rgba.len()];
+        oklab_to_rgba8_slice(&labs, &mut back);
+        assert_eq!(back, rgba);
+    }
+
+    #[test]
+    #[should_panic(expected = "the output length differs from the input")]
+    fn length_mismatch() {
+        srgb_to_oklab_slice(&[Srgb::default()], &mut []);
+    }
+}
@@ -0,0 +1,305 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! CIE color spaces
+//!
+//! [`Xyz`] is the hub between the CIE spaces and the other ones: it is a
+//! linear transform of [`LinSrgb`], and [`Lab`] is computed from it after a
+//! Bradford chromatic adaptation to D50.
+//!
+//! The constants come from
+//! [CSS Color 4](https://www.w3.org/TR/css-color-4/#color-conversion-code),
+//! so the values match what browsers compute.
+
+use super::{rgb::RgbSpace, LCh, Lab, LinSrgb, OkHsl, OkHsv, OkLCh, OkLab, Srgb, Xyz};
+
+/// The D65 white point, used by sRGB and OkLab
+pub const D65: Xyz = Xyz {
+    x: 0.3127 / 0.3290,
+    y: 1.0,
+    z: (1.0 - 0.3127 - 0.3290) / 0.3290,
+};
+
+/// The D50 white point, used by CIELAB and in print
+pub const D50: Xyz = Xyz {
+    x: 0.3457 / 0.3585,
+    y: 1.0,
+    z: (1.0 - 0.3457 - 0.3585) / 0.3585,
+};
+
+/// Bradford chromatic adaptation from D65 to D50
+const D65_TO_D50: [[f64; 3]; 3] = [
+    [
+        1.047_929_820_840_548_8,
+        0.022_946_793_341_019_088,
+        -0.050_192_229_543_135_57,
+    ],
+    [
+        0.029_627_815_688_159_344,
+        0.990_434_484_573_249,
+        -0.017_073_825_029_385_14,
+    ],
+    [
+        -0.009_243_058_152_591_178,
+        0.015_055_144_896_577_895,
+        0.751_874_289_958_000_8,
+    ],
+];
+
+/// Bradford chromatic adaptation from D50 to D65
+const D50_TO_D65: [[f64; 3]; 3] = [
+    [
+        0.955_473_421_488_075,
+        -0.023_098_454_948_764_71,
+        0.063_259_243_200_570_72,
+    ],
+    [
+        -0.028_369_709_333_863_7,
+        1.009_995_398_081_304_6,
+        0.021_041_441_191_917_323,
+    ],
+    [
+        0.012_314_014_864_481_998,
+        -0.020_507_649_298_898_964,
+        1.330_365_926_242_124,
+    ],
+];
+
+/// CIELAB constants, as exact fractions
+const EPSILON: f64 = 216.0 / 24389.0;
+const KAPPA: f64 = 24389.0 / 27.0;
+
+pub(super) const fn multiply(matrix: &[[f64; 3]; 3], vector: [f64; 3]) -> [f64; 3] {
+    let [x, y, z] = vector;
+    let [first, second, third] = matrix;
+    [
+        first[0] * x + first[1] * y + first[2] * z,
+        second[0] * x + second[1] * y + second[2] * z,
+        third[0] * x + third[1] * y + third[2] * z,
+    ]
+}
+
+impl Xyz {
+    fn to_array(self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    fn from_array([x, y, z]: [f64; 3]) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Adapts the color to the D50 white point, with the Bradford transform.
+    ///
+    /// The result holds values relative to D50, which is what print specs
+    /// usually give.
+    pub fn to_d50(self) -> Self {
+        Self::from_array(multiply(&D65_TO_D50, self.to_array()))
+    }
+
+    /// Adapts values relative to the D50 white point back to D65, with the
+    /// Bradford transform.
+    pub fn from_d50(d50: Self) -> Self {
+        Self::from_array(multiply(&D50_TO_D65, d50.to_array()))
+    }
+}
+
+impl From<LinSrgb> for Xyz {
+    fn from(rgb: LinSrgb) -> Self {
+        RgbSpace::SRGB.linear_to_xyz([rgb.red, rgb.green, rgb.blue])
+    }
+}
+
+impl From<Xyz> for LinSrgb {
+    fn from(xyz: Xyz) -> Self {
+        let [red, green, blue] = RgbSpace::SRGB.xyz_to_linear(xyz);
+        Self { red, green, blue }
+    }
+}
+
+impl From<Xyz> for Lab {
+    fn from(xyz: Xyz) -> Self {
+        let xyz = xyz.to_d50().to_array();
+        let white = D50.to_array();
+
+        let [fx, fy, fz] = [0, 1, 2].map(|i| {
+            let value = xyz[i] / white[i];
+            if value > EPSILON {
+                value.cbrt()
+            } else {
+                (KAPPA * value + 16.0) / 116.0
+            }
+        });
+
+        Self {
+            lightness: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+impl From<Lab> for Xyz {
+    fn from(lab: Lab) -> Self {
+        let fy = (lab.lightness + 16.0) / 116.0;
+        let fx = lab.a / 500.0 + fy;
+        let fz = fy - lab.b / 200.0;
+
+        let x = if fx.powi(3) > EPSILON {
+            fx.powi(3)
+        } else {
+            (116.0 * fx - 16.0) / KAPPA
+        };
+        let y = if lab.lightness > KAPPA * EPSILON {
+            fy.powi(3)
+        } else {
+            lab.lightness / KAPPA
+        };
+        let z = if fz.powi(3) > EPSILON {
+            fz.powi(3)
+        } else {
+            (116.0 * fz - 16.0) / KAPPA
+        };
+
+        Self::from_d50(Self {
+            x: x * D50.x,
+            y: y * D50.y,
+            z: z * D50.z,
+        })
+    }
+}
+
+impl From<Lab> for LCh {
+    fn from(lab: Lab) -> Self {
+        Self {
+            lightness: lab.lightness,
+            chroma: lab.a.hypot(lab.b),
+            hue: lab.b.atan2(lab.a),
+        }
+    }
+}
+
+impl From<LCh> for Lab {
+    fn from(lch: LCh) -> Self {
+        Self {
+            lightness: lch.lightness,
+            a: lch.chroma * lch.hue.cos(),
+            b: lch.chroma * lch.hue.sin(),
+        }
+    }
+}
+
+convert_via!(LinSrgb: Srgb => Xyz, Xyz => Srgb, OkLab => Xyz, Xyz => OkLab);
+convert_via!(OkLab: OkLCh => Xyz, Xyz => OkLCh);
+convert_via!(Srgb: OkHsv => Xyz, Xyz => OkHsv, OkHsl => Xyz, Xyz => OkHsl);
+
+convert_via!(Xyz:
+    LinSrgb => Lab, Lab => LinSrgb,
+    Srgb => Lab, Lab => Srgb,
+    OkLab => Lab, Lab => OkLab,
+    OkLCh => Lab, Lab => OkLCh,
+    OkHsv => Lab, Lab => OkHsv,
+    OkHsl => Lab, Lab => OkHsl,
+);
+
+convert_via!(Lab:
+    Xyz => LCh, LCh => Xyz,
+    LinSrgb => LCh, LCh => LinSrgb,
+    Srgb => LCh, LCh => Srgb,
+    OkLab => LCh, LCh => OkLab,
+    OkLCh => LCh, LCh => OkLCh,
+    OkHsv => LCh, LCh => OkHsv,
+    OkHsl => LCh, LCh => OkHsl,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(left: [f64; 3], right: [f64; 3], tolerance: f64) {
+        assert!(
+            left.iter()
+                .zip(right)
+                .all(|(left, right)| (left - right).abs() < tolerance),
+            "{left:?} and {right:?} are too different"
+        );
+    }
+
+    #[test]
+    fn xyz() {
+        let white = Srgb {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        };
+        assert_close(Xyz::from(white).to_array(), D65.to_array(), 1e-6);
+        assert_close(D65.to_d50().to_array(), D50.to_array(), 1e-6);
+        assert_close(Xyz::from_d50(D50).to_array(), D65.to_array(), 1e-6);
+
+        let color = Xyz {
+            x: 0.2,
+            y: 0.3,
+            z: 0.4,
+        };
+        assert_close(
+            Xyz::from_d50(color.to_d50()).to_array(),
+            color.to_array(),
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn lab() {
+        let white = Lab::from(Srgb {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        });
+        assert_close([white.lightness, white.a, white.b], [100.0, 0.0, 0.0], 1e-3);
+
+        // sRGB red, as computed by CSS Color 4 implementations
+        let red = Srgb {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        let lab = Lab::from(red);
+        assert_close([lab.lightness, lab.a, lab.b], [54.29, 80.80, 69.89], 0.01);
+        let lch = LCh::from(red);
+        assert_close(
+            [lch.lightness, lch.chroma, lch.hue.to_degrees()],
+            [54.29, 106.84, 40.85],
+            0.01,
+        );
+
+        let color = Srgb {
+            red: 0.4,
+            green: 0.4,
+            blue: 0.7,
+        };
+        let back = Srgb::from(LCh::from(color));
+        assert_close(
+            [back.red, back.green, back.blue],
+            [color.red, color.green, color.blue],
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let color = Srgb {
+            red: 0.2,
+            green: 0.6,
+            blue: 0.3,
+        };
+        let expected = [color.red, color.green, color.blue];
+        let check = |rgb: Srgb| assert_close([rgb.red, rgb.green, rgb.blue], expected, 1e-6);
+
+        check(Srgb::from(OkLab::from(Lab::from(OkLab::from(color)))));
+        let lch = OkLCh::from(OkLab::from(color));
+        check(Srgb::from(OkLab::from(OkLCh::from(Xyz::from(lch)))));
+        check(Srgb::from(OkHsv::from(LCh::from(OkHsv::from(color)))));
+        check(Srgb::from(OkHsl::from(Lab::from(OkHsl::from(color)))));
+        check(Srgb::from(LinSrgb::from(LCh::from(LinSrgb::from(color)))));
+    }
+}
@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Contrast between colors
+//!
+//! Implements the [WCAG 2.1](https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio)
+//! contrast ratio, and the [APCA](https://github.com/Myndex/apca-w3) lightness
+//! contrast (version 0.0.98G-4g).
+
+use std::fmt;
+
+use super::{LinSrgb, Srgb};
+
+/// Relative luminance of a color, as defined by WCAG 2.1.
+pub fn relative_luminance(rgb: LinSrgb) -> f64 {
+    0.2126 * rgb.red + 0.7152 * rgb.green + 0.0722 * rgb.blue
+}
+
+/// WCAG 2.1 contrast ratio between two colors, from 1 to 21.
+///
+/// The ratio is symmetric, so the order of the colors doesn't matter.
+pub fn wcag_contrast_ratio(first: Srgb, second: Srgb) -> f64 {
+    let first = relative_luminance(first.into());
+    let second = relative_luminance(second.into());
+    (first.max(second) + 0.05) / (first.min(second) + 0.05)
+}
+
+/// Conformance levels of a WCAG 2.1 contrast ratio, for text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WcagLevel {
+    /// Below 3:1
+    Fail,
+    /// At least 3:1, enough for large text only
+    AaLarge,
+    /// At least 4.5:1, enough for AA, and for AAA with large text
+    Aa,
+    /// At least 7:1
+    Aaa,
+}
+
+impl WcagLevel {
+    pub fn from_ratio(ratio: f64) -> Self {
+        if ratio >= 7.0 {
+            Self::Aaa
+        } else if ratio >= 4.5 {
+            Self::Aa
+        } else if ratio >= 3.0 {
+            Self::AaLarge
+        } else {
+            Self::Fail
+        }
+    }
+}
+
+impl fmt::Display for WcagLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fail => "Fail",
+            Self::AaLarge => "AA Large",
+            Self::Aa => "AA",
+            Self::Aaa => "AAA",
+        })
+    }
+}
+
+/// APCA lightness contrast (Lc) of `text` over `background`.
+///
+/// The result is roughly between -108 and 106. It is positive for dark text on
+/// light backgrounds, and negative for light text on dark backgrounds; only
+/// its absolute value matters to assess readability (Lc 75 is the minimum for
+/// body text, Lc 60 for content text, and Lc 45 for large headlines).
+pub fn apca_contrast(text: Srgb, background: Srgb) -> f64 {
+    const NORM_BG: f64 = 0.56;
+    const NORM_TXT: f64 = 0.57;
+    const REV_TXT: f64 = 0.62;
+    const REV_BG: f64 = 0.65;
+    const BLACK_THRESHOLD: f64 = 0.022;
+    const BLACK_CLAMP: f64 = 1.414;
+    const SCALE: f64 = 1.14;
+    const LOW_OFFSET: f64 = 0.027;
+    const DELTA_Y_MIN: f64 = 0.0005;
+    const LOW_CLIP: f64 = 0.1;
+
+    /// APCA uses a simple power curve instead of the piecewise sRGB one
+    fn screen_luminance(rgb: Srgb) -> f64 {
+        let y = 0.212_672_9 * rgb.red.max(0.0).powf(2.4)
+            + 0.715_152_2 * rgb.green.max(0.0).powf(2.4)
+            + 0.072_175_0 * rgb.blue.max(0.0).powf(2.4);
+        if y > BLACK_THRESHOLD {
+            y
+        } else {
+            y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+        }
+    }
+
+    let text_y = screen_luminance(text);
+    let background_y = screen_luminance(background);
+
+    if (background_y - text_y).abs() < DELTA_Y_MIN {
+        return 0.0;
+    }
+
+    let lc = if background_y > text_y {
+        // Dark text on a light background
+        let sapc = (background_y.powf(NORM_BG) - text_y.powf(NORM_TXT)) * SCALE;
+        if sapc < LOW_CLIP {
+            0.0
+        } else {
+            sapc - LOW_OFFSET
+        }
+    } else {
+        // Light text on a dark background
+        let sapc = (background_y.powf(REV_BG) - text_y.powf(REV_TXT)) * SCALE;
+        if sapc > -LOW_CLIP {
+            0.0
+        } else {
+            sapc + LOW_OFFSET
+        }
+    };
+
+    lc * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: u8) -> Srgb {
+        let value = f64::from(value) / 255.0;
+        Srgb {
+            red: value,
+            green: value,
+            blue: value,
+        }
+    }
+
+    #[test]
+    fn wcag() {
+        let ratio = wcag_contrast_ratio(gray(0), gray(255));
+        assert!((ratio - 21.0).abs() < 1e-6, "black on white is {ratio}");
+        assert_eq!(WcagLevel::from_ratio(ratio), WcagLevel::Aaa);
+
+        let ratio = wcag_contrast_ratio(gray(255), gray(255));
+        assert!((ratio - 1.0).abs() < 1e-6, "white on white is {ratio}");
+        assert_eq!(WcagLevel::from_ratio(ratio), WcagLevel::Fail);
+
+        // #777 is the classic "almost AA" gray on white
+        let ratio = wcag_contrast_ratio(gray(0x77), gray(255));
+        assert!((ratio - 4.48).abs() < 0.01, "#777 on white is {ratio}");
+        assert_eq!(WcagLevel::from_ratio(ratio), WcagLevel::AaLarge);
+        assert_eq!(ratio, wcag_contrast_ratio(gray(255), gray(0x77)));
+    }
+
+    #[test]
+    fn apca() {
+        let lc = apca_contrast(gray(0), gray(255));
+        assert!((lc - 106.04).abs() < 0.01, "black on white is {lc}");
+
+        let lc = apca_contrast(gray(255), gray(0));
+        assert!((lc + 107.88).abs() < 0.01, "white on black is {lc}");
+
+        let lc = apca_contrast(gray(0x88), gray(255));
+        assert!((lc - 63.06).abs() < 0.01, "#888 on white is {lc}");
+
+        let lc = apca_contrast(gray(255), gray(0x88));
+        assert!((lc + 68.54).abs() < 0.01, "white on #888 is {lc}");
+
+        assert_eq!(apca_contrast(gray(0x88), gray(0x88)), 0.0);
+    }
+}
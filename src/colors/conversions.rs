@@ -7,46 +7,50 @@
 //!
 //! Yes, it could be shaders. It could.
 
-use super::{LinSrgb, OkHsl, OkHsv, OkLCh, OkLab, Srgb};
+use super::{cie::multiply, rgb::RgbSpace, LinSrgb, OkHsl, OkHsv, OkLCh, OkLab, Srgb};
 use crate::colors::ACCEPTABLE_ERROR;
 
-use once_cell::sync::Lazy;
-
-/// The matrices were updated 2021-01-25
-static M1_LIN_SRGB_TO_OKLAB: Lazy<ndarray::Array2<f64>> = Lazy::new(|| {
-    ndarray::arr2(&[
-        [0.412_221_470_8, 0.536_332_536_3, 0.051_445_992_9],
-        [0.211_903_498_2, 0.680_699_545_1, 0.107_396_956_6],
-        [0.088_302_461_9, 0.281_718_837_6, 0.629_978_700_5],
-    ])
-});
-
-/// The matrices were updated 2021-01-25
-static M2_LIN_SRGB_TO_OKLAB: Lazy<ndarray::Array2<f64>> = Lazy::new(|| {
-    ndarray::arr2(&[
-        [0.210_454_255_3, 0.793_617_785_0, -0.004_072_046_8],
-        [1.977_998_495_1, -2.428_592_205_0, 0.450_593_709_9],
-        [0.025_904_037_1, 0.782_771_766_2, -0.808_675_766_0],
-    ])
-});
-
-/// The matrices were updated 2021-01-25
-static M1_OKLAB_TO_LIN_SRGB: Lazy<ndarray::Array2<f64>> = Lazy::new(|| {
-    ndarray::arr2(&[
-        [1.0, 0.396_337_777_4, 0.215_803_757_3],
-        [1.0, -0.105_561_345_8, -0.063_854_172_8],
-        [1.0, -0.089_484_177_5, -1.291_485_548_0],
-    ])
-});
-
-/// The matrices were updated 2021-01-25
-static M2_OKLAB_TO_LIN_SRGB: Lazy<ndarray::Array2<f64>> = Lazy::new(|| {
-    ndarray::arr2(&[
-        [4.076_741_662_1, -3.307_711_591_3, 0.230_969_929_2],
-        [-1.268_438_004_6, 2.609_757_401_1, -0.341_319_396_5],
-        [-0.004_196_086_3, -0.703_418_614_7, 1.707_614_701_0],
-    ])
-});
+/// Linear sRGB to cone responses (the matrices were updated 2021-01-25)
+pub(crate) const LIN_SRGB_TO_LMS: [[f64; 3]; 3] = [
+    [0.412_221_470_8, 0.536_332_536_3, 0.051_445_992_9],
+    [0.211_903_498_2, 0.680_699_545_1, 0.107_396_956_6],
+    [0.088_302_461_9, 0.281_718_837_6, 0.629_978_700_5],
+];
+
+/// Cube roots of the cone responses to OkLab (the matrices were updated
+/// 2021-01-25)
+pub(crate) const LMS_TO_OKLAB: [[f64; 3]; 3] = [
+    [0.210_454_255_3, 0.793_617_785_0, -0.004_072_046_8],
+    [1.977_998_495_1, -2.428_592_205_0, 0.450_593_709_9],
+    [0.025_904_037_1, 0.782_771_766_2, -0.808_675_766_0],
+];
+
+/// OkLab to the cube roots of the cone responses (the matrices were updated
+/// 2021-01-25)
+pub(crate) const OKLAB_TO_LMS: [[f64; 3]; 3] = [
+    [1.0, 0.396_337_777_4, 0.215_803_757_3],
+    [1.0, -0.105_561_345_8, -0.063_854_172_8],
+    [1.0, -0.089_484_177_5, -1.291_485_548_0],
+];
+
+/// Cone responses to linear sRGB (the matrices were updated 2021-01-25)
+pub(crate) const LMS_TO_LIN_SRGB: [[f64; 3]; 3] = [
+    [4.076_741_662_1, -3.307_711_591_3, 0.230_969_929_2],
+    [-1.268_438_004_6, 2.609_757_401_1, -0.341_319_396_5],
+    [-0.004_196_086_3, -0.703_418_614_7, 1.707_614_701_0],
+];
+
+impl OkLab {
+    /// Converts the color to linear sRGB.
+    ///
+    /// Unlike the `From` implementation, this can be used in constants. The
+    /// other way needs a cube root, which can't be evaluated at compile time.
+    pub const fn to_lin_srgb(self) -> LinSrgb {
+        let [l, m, s] = multiply(&OKLAB_TO_LMS, [self.lightness, self.a, self.b]);
+        let [red, green, blue] = multiply(&LMS_TO_LIN_SRGB, [l * l * l, m * m * m, s * s * s]);
+        LinSrgb { red, green, blue }
+    }
+}
 
 impl From<LinSrgb> for Srgb {
     fn from(linear: LinSrgb) -> Self {
@@ -84,42 +88,6 @@ impl From<Srgb> for LinSrgb {
     }
 }
 
-impl From<&ndarray::Array1<f64>> for LinSrgb {
-    fn from(col: &ndarray::Array1<f64>) -> Self {
-        let slice = col.as_slice().unwrap();
-        debug_assert_eq!(slice.len(), 3);
-        Self {
-            red: slice[0],
-            green: slice[1],
-            blue: slice[2],
-        }
-    }
-}
-
-impl From<LinSrgb> for ndarray::Array1<f64> {
-    fn from(col: LinSrgb) -> Self {
-        ndarray::arr1(&[col.red, col.green, col.blue])
-    }
-}
-
-impl From<&ndarray::Array1<f64>> for OkLab {
-    fn from(col: &ndarray::Array1<f64>) -> Self {
-        let slice = col.as_slice().unwrap();
-        debug_assert_eq!(slice.len(), 3);
-        Self {
-            lightness: slice[0],
-            a: slice[1],
-            b: slice[2],
-        }
-    }
-}
-
-impl From<OkLab> for ndarray::Array1<f64> {
-    fn from(col: OkLab) -> Self {
-        ndarray::arr1(&[col.lightness, col.a, col.b])
-    }
-}
-
 impl From<OkLab> for OkLCh {
     fn from(lab: OkLab) -> Self {
         Self {
@@ -142,15 +110,12 @@ impl From<OkLCh> for OkLab {
 
 impl From<LinSrgb> for OkLab {
     fn from(lin: LinSrgb) -> Self {
-        use ndarray::Array1;
-
         debug_assert!(lin.is_normal(), "lin is not normal {lin:?}");
 
-        let lin_vec = Array1::<f64>::from(lin);
-        let lms = M1_LIN_SRGB_TO_OKLAB.dot(&lin_vec).to_vec();
-        let lms_ = ndarray::arr1(&[lms[0].cbrt(), lms[1].cbrt(), lms[2].cbrt()]);
+        let lms = multiply(&LIN_SRGB_TO_LMS, [lin.red, lin.green, lin.blue]);
+        let [lightness, a, b] = multiply(&LMS_TO_OKLAB, lms.map(f64::cbrt));
 
-        let col: Self = (&M2_LIN_SRGB_TO_OKLAB.dot(&lms_)).into();
+        let col = Self { lightness, a, b };
         debug_assert!(col.is_normal(), "col is not normal: {col:?}");
         col
     }
@@ -158,27 +123,40 @@ impl From<LinSrgb> for OkLab {
 
 impl From<OkLab> for LinSrgb {
     fn from(lab: OkLab) -> Self {
-        use ndarray::Array1;
-
         debug_assert!(lab.is_normal(), "lab is not normal {lab:?}");
 
-        let lab_vec = Array1::<f64>::from(lab);
-        let lms_ = M1_OKLAB_TO_LIN_SRGB.dot(&lab_vec).to_vec();
-        let lms = ndarray::arr1(&[lms_[0].powi(3), lms_[1].powi(3), lms_[2].powi(3)]);
-
-        let col: Self = (&M2_OKLAB_TO_LIN_SRGB.dot(&lms)).into();
+        let col = lab.to_lin_srgb();
         debug_assert!(col.is_normal(), "col is not normal: {col:?}");
         col
     }
 }
 
+impl From<OkLab> for Srgb {
+    fn from(lab: OkLab) -> Self {
+        Self::from(LinSrgb::from(lab))
+    }
+}
+
+impl From<Srgb> for OkLab {
+    fn from(rgb: Srgb) -> Self {
+        Self::from(LinSrgb::from(rgb))
+    }
+}
+
 impl From<Srgb> for OkHsv {
     fn from(gammad_rgb: Srgb) -> Self {
         debug_assert!(
             gammad_rgb.is_normal(),
             "gammad_rgb isn't normal {gammad_rgb:?}"
         );
-        let lab = OkLab::from(LinSrgb::from(gammad_rgb));
+        Self::from_oklab_in(OkLab::from(LinSrgb::from(gammad_rgb)), &RgbSpace::SRGB)
+    }
+}
+
+impl OkHsv {
+    /// Computes OkHSV relative to the gamut of `space` instead of sRGB, so a
+    /// saturation and a value of 1 reach the most vivid colors of `space`.
+    pub fn from_oklab_in(lab: OkLab, space: &RgbSpace) -> Self {
         debug_assert!(lab.is_normal(), "lab isn't normal {lab:?}");
 
         let chroma = (lab.a.powi(2) + lab.b.powi(2)).sqrt();
@@ -197,7 +175,7 @@ impl From<Srgb> for OkHsv {
         };
 
         let hue = saturated_b.atan2(saturated_a);
-        let cusp = find_cusp(saturated_a, saturated_b);
+        let cusp = find_cusp(space, saturated_a, saturated_b);
         let st_max = ST::from_cusp(cusp);
 
         const S0: f64 = 0.5;
@@ -220,17 +198,12 @@ impl From<Srgb> for OkHsv {
             c_v
         };
 
-        let rgb_scale = LinSrgb::from(OkLab {
+        let [red, green, blue] = space.oklab_to_linear(OkLab {
             lightness: l_vt,
             a: saturated_a * c_vt,
             b: saturated_b * c_vt,
         });
-        let scale_l = (1.0
-            / f64::max(
-                f64::max(rgb_scale.red, rgb_scale.green),
-                f64::max(rgb_scale.blue, 0.0),
-            ))
-        .cbrt();
+        let scale_l = (1.0 / f64::max(f64::max(red, green), f64::max(blue, 0.0))).cbrt();
         let scaled_lightness = lab.lightness / scale_l;
         /* Code from the original source, that is unused here
          *
@@ -250,11 +223,19 @@ impl From<Srgb> for OkHsv {
 
 impl From<OkHsv> for Srgb {
     fn from(hsv: OkHsv) -> Self {
+        LinSrgb::from(hsv.to_oklab_in(&RgbSpace::SRGB)).into()
+    }
+}
+
+impl OkHsv {
+    /// Inverse of [`OkHsv::from_oklab_in`].
+    pub fn to_oklab_in(self, space: &RgbSpace) -> OkLab {
+        let hsv = self;
         debug_assert!(hsv.is_normal(), "hsv isn't normal {hsv:?}");
 
         let a_ = (hsv.hue).cos();
         let b_ = (hsv.hue).sin();
-        let cusp = find_cusp(a_, b_);
+        let cusp = find_cusp(space, a_, b_);
         let st_max = ST::from_cusp(cusp);
 
         const S0: f64 = 0.5;
@@ -285,21 +266,18 @@ impl From<OkHsv> for Srgb {
         let c = if l.is_normal() { c * l_new / l } else { c };
         let l = l_new;
 
-        let rgb_scale = LinSrgb::from(OkLab {
+        let rgb_scale = space.oklab_to_linear(OkLab {
             lightness: l_vt,
             a: a_ * c_vt,
             b: b_ * c_vt,
         });
         debug_assert!(
-            rgb_scale.is_normal(),
+            rgb_scale.iter().all(|channel| channel.is_finite()),
             "rgb_scale is not normal {rgb_scale:?}"
         );
-        let scale_l = (1.0
-            / f64::max(
-                f64::max(rgb_scale.red, rgb_scale.green),
-                f64::max(rgb_scale.blue, std::f64::MIN_POSITIVE),
-            ))
-        .cbrt();
+        let [red, green, blue] = rgb_scale;
+        let scale_l =
+            (1.0 / f64::max(f64::max(red, green), f64::max(blue, f64::MIN_POSITIVE))).cbrt();
         debug_assert!(scale_l.is_finite(), "scale_l is not normal {scale_l:?}");
         let l = l * scale_l;
         let c = c * scale_l;
@@ -314,7 +292,7 @@ impl From<OkHsv> for Srgb {
             "resulting_lab is not normal {resulting_lab:?}"
         );
 
-        LinSrgb::from(resulting_lab).into()
+        resulting_lab
     }
 }
 
@@ -336,11 +314,28 @@ impl From<OkHsl> for Srgb {
             };
         }
 
+        Self::from(LinSrgb::from(hsl.to_oklab_in(&RgbSpace::SRGB)))
+    }
+}
+
+impl OkHsl {
+    /// Inverse of [`OkHsl::from_oklab_in`].
+    pub fn to_oklab_in(self, space: &RgbSpace) -> OkLab {
+        let hsl = self;
+
+        if hsl.lightness == 1.0 || hsl.lightness == 0.0 {
+            return OkLab {
+                lightness: hsl.lightness,
+                a: 0.0,
+                b: 0.0,
+            };
+        }
+
         let a = hsl.hue.cos();
         let b = hsl.hue.sin();
         let l = inverse_toe(hsl.lightness);
 
-        let Cs { c_0, c_mid, c_max } = Cs::from(OkLab { lightness: l, a, b });
+        let Cs { c_0, c_mid, c_max } = Cs::new(OkLab { lightness: l, a, b }, space);
 
         let mid = 0.8;
         let mid_inv = 1.25_f64;
@@ -360,18 +355,24 @@ impl From<OkHsl> for Srgb {
             k_0 + t * k_1 / (1.0 - k_2 * t)
         };
 
-        Self::from(LinSrgb::from(OkLab {
+        OkLab {
             lightness: l,
             a: c * a,
             b: c * b,
-        }))
+        }
     }
 }
 
 impl From<Srgb> for OkHsl {
     fn from(rgb: Srgb) -> Self {
-        let lab = OkLab::from(LinSrgb::from(rgb));
+        Self::from_oklab_in(OkLab::from(LinSrgb::from(rgb)), &RgbSpace::SRGB)
+    }
+}
 
+impl OkHsl {
+    /// Computes OkHSL relative to the gamut of `space` instead of sRGB, so a
+    /// saturation of 1 reaches the most vivid colors of `space`.
+    pub fn from_oklab_in(lab: OkLab, space: &RgbSpace) -> Self {
         let chroma = (lab.a.powi(2) + lab.b.powi(2)).sqrt();
         let a_ = if chroma.is_normal() {
             lab.a / chroma
@@ -387,11 +388,14 @@ impl From<Srgb> for OkHsl {
         let lightness = lab.lightness;
         let hue = b_.atan2(a_);
 
-        let Cs { c_0, c_mid, c_max } = Cs::from(OkLab {
-            lightness,
-            a: a_,
-            b: b_,
-        });
+        let Cs { c_0, c_mid, c_max } = Cs::new(
+            OkLab {
+                lightness,
+                a: a_,
+                b: b_,
+            },
+            space,
+        );
         // Inverse of the interpolation in Srgb::from::<OkHsl>()
         let mid = 0.8;
         let mid_inv = 1.25_f64;
@@ -430,7 +434,7 @@ impl From<Srgb> for OkHsl {
 }
 
 #[derive(Clone, Copy, Debug)]
-struct LC {
+pub(crate) struct LC {
     pub lightness: f64,
     pub chroma: f64,
 }
@@ -446,6 +450,9 @@ struct ST {
     pub t: f64,
 }
 
+/// Ratio of [`ST::mid`] to the sRGB cusp, averaged over the hues.
+const MID_TO_CUSP: f64 = 0.9;
+
 impl ST {
     fn from_cusp(cusp: LC) -> Self {
         let l = cusp.lightness;
@@ -460,6 +467,8 @@ impl ST {
     /// Returns a smooth approximation of the location of the cusp
     /// This polynomial was created by an optimization process
     /// It has been designed so that S_mid < S_max and T_mid < T_max
+    ///
+    /// The fit is made for the sRGB gamut, see [`ST::mid_in`] for the others.
     fn mid(a: f64, b: f64) -> Self {
         Self {
             s: 0.115_169_93
@@ -481,6 +490,22 @@ impl ST {
                                 + a * (0.002_992_15 - 0.453_995_68 * b - 0.146_618_72 * a)))),
         }
     }
+
+    /// Same as [`ST::mid`] in the gamut of `space`, whose cusp is `st_max`.
+    ///
+    /// The polynomial fit only follows the sRGB cusp, so spaces without a
+    /// [`SaturationFit`](super::rgb::SaturationFit) scale their own cusp
+    /// instead. This is less smooth across hues, but follows the gamut.
+    fn mid_in(space: &RgbSpace, a: f64, b: f64, st_max: Self) -> Self {
+        if space.saturation_fit.is_some() {
+            Self::mid(a, b)
+        } else {
+            Self {
+                s: MID_TO_CUSP * st_max.s,
+                t: MID_TO_CUSP * st_max.t,
+            }
+        }
+    }
 }
 
 /// toe function for L_r
@@ -499,31 +524,37 @@ fn inverse_toe(val: f64) -> f64 {
     (val * val + K1 * val) / (K3 * (val + K2))
 }
 
-fn find_cusp(a: f64, b: f64) -> LC {
+pub(crate) fn find_cusp(space: &RgbSpace, a: f64, b: f64) -> LC {
     debug_assert!(
         (1.0 - a.powi(2) - b.powi(2)).abs() < ACCEPTABLE_ERROR,
         "Precondition failed: ({a:?}, {b:?}) isn't on unit circle (norm is {})",
         a.powi(2) + b.powi(2)
     );
-    let s_cusp = compute_max_saturation(a, b);
+    let s_cusp = compute_max_saturation(space, a, b);
 
-    let max_rgb = LinSrgb::from(OkLab {
+    let [red, green, blue] = space.oklab_to_linear(OkLab {
         lightness: 1.0,
         a: s_cusp * a,
         b: s_cusp * b,
     });
-    debug_assert!(max_rgb.is_normal(), "max_rgb is not normal: {max_rgb:?}");
-    let lightness = (1.0 / f64::max(max_rgb.red, f64::max(max_rgb.green, max_rgb.blue))).cbrt();
+    debug_assert!(
+        red.is_finite() && green.is_finite() && blue.is_finite(),
+        "max_rgb is not normal: {:?}",
+        [red, green, blue]
+    );
+    let lightness = (1.0 / f64::max(red, f64::max(green, blue))).cbrt();
     LC {
         lightness,
         chroma: lightness * s_cusp,
     }
 }
-/// Finds the maximum saturation possible for a given hue that fits in sRGB
+
+/// Finds the maximum saturation possible for a given hue that fits in the
+/// gamut of `space`
 ///
 /// Saturation here is defined as S = C/L
 /// a and b must be normalized so a^2 + b^2 == 1
-fn compute_max_saturation(a: f64, b: f64) -> f64 {
+fn compute_max_saturation(space: &RgbSpace, a: f64, b: f64) -> f64 {
     debug_assert!(
         (1.0 - a.powi(2) - b.powi(2)).abs() < ACCEPTABLE_ERROR,
         "Precondition failed: ({a:?}, {b:?}) isn't on unit circle (norm is {})",
@@ -531,44 +562,23 @@ fn compute_max_saturation(a: f64, b: f64) -> f64 {
     );
     // Max saturation will be when one of r, g or b goes below zero.
 
+    let Some(fit) = space.saturation_fit else {
+        return search_max_saturation(space, a, b);
+    };
+
     // Select different coefficients depending on which component goes below zero first
-    let (k0, k1, k2, k3, k4, wl, wm, ws) = if 1.0 < a.mul_add(-1.881_703_28, -0.809_364_93 * b) {
+    let channel = if 1.0 < a.mul_add(fit.red[0], fit.red[1] * b) {
         // red component
-        (
-            1.190_862_77,
-            1.765_767_28,
-            0.596_626_41,
-            0.755_151_97,
-            0.567_712_45,
-            M2_OKLAB_TO_LIN_SRGB[[0, 0]],
-            M2_OKLAB_TO_LIN_SRGB[[0, 1]],
-            M2_OKLAB_TO_LIN_SRGB[[0, 2]],
-        )
-    } else if 1.0 < a.mul_add(1.814_441_04, -1.194_452_76 * b) {
+        0
+    } else if 1.0 < a.mul_add(fit.green[0], fit.green[1] * b) {
         // green component
-        (
-            0.739_565_15,
-            -0.459_544_04,
-            0.082_854_27,
-            0.125_410_70,
-            0.145_032_04,
-            M2_OKLAB_TO_LIN_SRGB[[1, 0]],
-            M2_OKLAB_TO_LIN_SRGB[[1, 1]],
-            M2_OKLAB_TO_LIN_SRGB[[1, 2]],
-        )
+        1
     } else {
         // blue component
-        (
-            1.357_336_52,
-            -0.009_157_99,
-            -1.151_302_10,
-            -0.505_596_06,
-            0.006_921_67,
-            M2_OKLAB_TO_LIN_SRGB[[2, 0]],
-            M2_OKLAB_TO_LIN_SRGB[[2, 1]],
-            M2_OKLAB_TO_LIN_SRGB[[2, 2]],
-        )
+        2
     };
+    let [k0, k1, k2, k3, k4] = fit.coefficients[channel];
+    let [wl, wm, ws] = space.lms_to_linear[channel];
 
     // Approximate max saturation using a polynomial:
     let mut sat = k0 + k1 * a + k2 * b + k3 * a * a + k4 * a * b;
@@ -578,9 +588,9 @@ fn compute_max_saturation(a: f64, b: f64) -> f64 {
     // this should be sufficient for most applications, otherwise do two/three steps
     for _ in 0..4 {
         let (k_l, k_m, k_s) = (
-            M1_OKLAB_TO_LIN_SRGB[[0, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[0, 2]] * b,
-            M1_OKLAB_TO_LIN_SRGB[[1, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[1, 2]] * b,
-            M1_OKLAB_TO_LIN_SRGB[[2, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[2, 2]] * b,
+            OKLAB_TO_LMS[0][1] * a + OKLAB_TO_LMS[0][2] * b,
+            OKLAB_TO_LMS[1][1] * a + OKLAB_TO_LMS[1][2] * b,
+            OKLAB_TO_LMS[2][1] * a + OKLAB_TO_LMS[2][2] * b,
         );
 
         let (l_, m_, s_) = (1.0 + sat * k_l, 1.0 + sat * k_m, 1.0 + sat * k_s);
@@ -606,6 +616,41 @@ fn compute_max_saturation(a: f64, b: f64) -> f64 {
     sat
 }
 
+/// Finds the maximum saturation for spaces without a polynomial fit.
+///
+/// White (S = 0) is inside every gamut, so this walks away from it until a
+/// channel goes below zero, then bisects the last step.
+fn search_max_saturation(space: &RgbSpace, a: f64, b: f64) -> f64 {
+    const STEP: f64 = 0.05;
+    const MAX_SATURATION: f64 = 100.0;
+
+    let in_gamut = |sat: f64| {
+        space
+            .oklab_to_linear(OkLab {
+                lightness: 1.0,
+                a: sat * a,
+                b: sat * b,
+            })
+            .into_iter()
+            .all(|channel| channel >= 0.0)
+    };
+
+    let mut low = 0.0;
+    while low < MAX_SATURATION && in_gamut(low + STEP) {
+        low += STEP;
+    }
+    let mut high = low + STEP;
+    for _ in 0..48 {
+        let mid = 0.5 * (low + high);
+        if in_gamut(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
 #[derive(Clone, Copy, Debug)]
 struct Cs {
     c_0: f64,
@@ -613,18 +658,19 @@ struct Cs {
     c_max: f64,
 }
 
-impl From<OkLab> for Cs {
-    fn from(lab: OkLab) -> Self {
+impl Cs {
+    fn new(lab: OkLab, space: &RgbSpace) -> Self {
         debug_assert!(lab.is_normal(), "lab isn't normal: {lab:?}");
-        let cusp = find_cusp(lab.a, lab.b);
-        let c_max = find_gamut_intersection(lab.a, lab.b, lab.lightness, 1.0, lab.lightness, cusp);
+        let cusp = find_cusp(space, lab.a, lab.b);
+        let c_max =
+            find_gamut_intersection(space, lab.a, lab.b, lab.lightness, 1.0, lab.lightness, cusp);
         let st_max = ST::from_cusp(cusp);
         let k = c_max
             / (lab.lightness * st_max.s)
                 .min((1.0 - lab.lightness) * st_max.t)
-                .max(std::f64::MIN_POSITIVE);
+                .max(f64::MIN_POSITIVE);
         debug_assert!(k.is_finite(), "k is not normal: {k:?}");
-        let st_mid = ST::mid(lab.a, lab.b);
+        let st_mid = ST::mid_in(space, lab.a, lab.b, st_max);
 
         debug_assert!(
             st_mid.s.is_normal() && st_mid.t.is_normal(),
@@ -660,11 +706,30 @@ impl From<OkLab> for Cs {
     }
 }
 
+/// Returns the maximum chroma that fits in the gamut of `space` for the given
+/// OkLab lightness and hue (in radians).
+pub(crate) fn max_chroma(space: &RgbSpace, lightness: f64, hue: f64) -> f64 {
+    if lightness <= 0.0 || lightness >= 1.0 {
+        return 0.0;
+    }
+    let (a, b) = (hue.cos(), hue.sin());
+    let cusp = find_cusp(space, a, b);
+    find_gamut_intersection(space, a, b, lightness, 1.0, lightness, cusp).max(0.0)
+}
+
 /// Finds intersection of the line defined by
 /// L = L0 * (1 - t) + t * L1;
 /// C = t * C1;
 /// a and b must be normalized so a^2 + b^2 == 1
-fn find_gamut_intersection(a: f64, b: f64, l1: f64, c1: f64, l0: f64, cusp: LC) -> f64 {
+pub(crate) fn find_gamut_intersection(
+    space: &RgbSpace,
+    a: f64,
+    b: f64,
+    l1: f64,
+    c1: f64,
+    l0: f64,
+    cusp: LC,
+) -> f64 {
     // Find the intersection for upper and lower half seprately
     if (l1 - l0) * cusp.chroma <= (cusp.lightness - l0) * c1 {
         // Lower half
@@ -679,9 +744,9 @@ fn find_gamut_intersection(a: f64, b: f64, l1: f64, c1: f64, l0: f64, cusp: LC)
 
         let d_l = l1 - l0;
         let d_c = c1;
-        let k_l = M1_OKLAB_TO_LIN_SRGB[[0, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[0, 2]] * b;
-        let k_m = M1_OKLAB_TO_LIN_SRGB[[1, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[1, 2]] * b;
-        let k_s = M1_OKLAB_TO_LIN_SRGB[[2, 1]] * a + M1_OKLAB_TO_LIN_SRGB[[2, 2]] * b;
+        let k_l = OKLAB_TO_LMS[0][1] * a + OKLAB_TO_LMS[0][2] * b;
+        let k_m = OKLAB_TO_LMS[1][1] * a + OKLAB_TO_LMS[1][2] * b;
+        let k_s = OKLAB_TO_LMS[2][1] * a + OKLAB_TO_LMS[2][2] * b;
 
         let l_dt = d_l + d_c * k_l;
         let m_dt = d_l + d_c * k_m;
@@ -708,44 +773,44 @@ fn find_gamut_intersection(a: f64, b: f64, l1: f64, c1: f64, l0: f64, cusp: LC)
             let mdt2 = 6.0 * m_dt * m_dt * m_;
             let sdt2 = 6.0 * s_dt * s_dt * s_;
 
-            let r = M2_OKLAB_TO_LIN_SRGB[[0, 0]] * l_c
-                + M2_OKLAB_TO_LIN_SRGB[[0, 1]] * m_c
-                + M2_OKLAB_TO_LIN_SRGB[[0, 2]] * s_c
+            let r = space.lms_to_linear[0][0] * l_c
+                + space.lms_to_linear[0][1] * m_c
+                + space.lms_to_linear[0][2] * s_c
                 - 1.0;
-            let r1 = M2_OKLAB_TO_LIN_SRGB[[0, 0]] * ldt
-                + M2_OKLAB_TO_LIN_SRGB[[0, 1]] * mdt
-                + M2_OKLAB_TO_LIN_SRGB[[0, 2]] * sdt;
-            let r2 = M2_OKLAB_TO_LIN_SRGB[[0, 0]] * ldt2
-                + M2_OKLAB_TO_LIN_SRGB[[0, 1]] * mdt2
-                + M2_OKLAB_TO_LIN_SRGB[[0, 2]] * sdt2;
+            let r1 = space.lms_to_linear[0][0] * ldt
+                + space.lms_to_linear[0][1] * mdt
+                + space.lms_to_linear[0][2] * sdt;
+            let r2 = space.lms_to_linear[0][0] * ldt2
+                + space.lms_to_linear[0][1] * mdt2
+                + space.lms_to_linear[0][2] * sdt2;
 
             let u_r = r1 / (r1 * r1 - 0.5 * r * r2);
             let t_r = if u_r > 0.0 { Some(-r * u_r) } else { None };
 
-            let g = M2_OKLAB_TO_LIN_SRGB[[1, 0]] * l_c
-                + M2_OKLAB_TO_LIN_SRGB[[1, 1]] * m_c
-                + M2_OKLAB_TO_LIN_SRGB[[1, 2]] * s_c
+            let g = space.lms_to_linear[1][0] * l_c
+                + space.lms_to_linear[1][1] * m_c
+                + space.lms_to_linear[1][2] * s_c
                 - 1.0;
-            let g1 = M2_OKLAB_TO_LIN_SRGB[[1, 0]] * ldt
-                + M2_OKLAB_TO_LIN_SRGB[[1, 1]] * mdt
-                + M2_OKLAB_TO_LIN_SRGB[[1, 2]] * sdt;
-            let g2 = M2_OKLAB_TO_LIN_SRGB[[1, 0]] * ldt2
-                + M2_OKLAB_TO_LIN_SRGB[[1, 1]] * mdt2
-                + M2_OKLAB_TO_LIN_SRGB[[1, 2]] * sdt2;
+            let g1 = space.lms_to_linear[1][0] * ldt
+                + space.lms_to_linear[1][1] * mdt
+                + space.lms_to_linear[1][2] * sdt;
+            let g2 = space.lms_to_linear[1][0] * ldt2
+                + space.lms_to_linear[1][1] * mdt2
+                + space.lms_to_linear[1][2] * sdt2;
 
             let u_g = g1 / (g1 * g1 - 0.5 * g * g2);
             let t_g = if u_g > 0.0 { Some(-g * u_g) } else { None };
 
-            let b = M2_OKLAB_TO_LIN_SRGB[[2, 0]] * l_c
-                + M2_OKLAB_TO_LIN_SRGB[[2, 1]] * m_c
-                + M2_OKLAB_TO_LIN_SRGB[[2, 2]] * s_c
+            let b = space.lms_to_linear[2][0] * l_c
+                + space.lms_to_linear[2][1] * m_c
+                + space.lms_to_linear[2][2] * s_c
                 - 1.0;
-            let b1 = M2_OKLAB_TO_LIN_SRGB[[2, 0]] * ldt
-                + M2_OKLAB_TO_LIN_SRGB[[2, 1]] * mdt
-                + M2_OKLAB_TO_LIN_SRGB[[2, 2]] * sdt;
-            let b2 = M2_OKLAB_TO_LIN_SRGB[[2, 0]] * ldt2
-                + M2_OKLAB_TO_LIN_SRGB[[2, 1]] * mdt2
-                + M2_OKLAB_TO_LIN_SRGB[[2, 2]] * sdt2;
+            let b1 = space.lms_to_linear[2][0] * ldt
+                + space.lms_to_linear[2][1] * mdt
+                + space.lms_to_linear[2][2] * sdt;
+            let b2 = space.lms_to_linear[2][0] * ldt2
+                + space.lms_to_linear[2][1] * mdt2
+                + space.lms_to_linear[2][2] * sdt2;
 
             let u_b = b1 / (b1 * b1 - 0.5 * b * b2);
             let t_b = if u_b > 0.0 { Some(-b * u_b) } else { None };
@@ -787,7 +852,7 @@ mod tests {
                         "return_col is not normal\n{init_col:?} became\n{return_col:?}"
                     );
                     // Comparing with f32 epsilon to allow some leeway
-                    if init_col.red.abs() > std::f32::EPSILON as f64 {
+                    if init_col.red.abs() > f32::EPSILON as f64 {
                         let error = (init_col.red - return_col.red).abs() / init_col.red.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -800,7 +865,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -808,7 +873,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.green.abs() > std::f32::EPSILON as f64 {
+                    if init_col.green.abs() > f32::EPSILON as f64 {
                         let error =
                             (init_col.green - return_col.green).abs() / init_col.green.abs();
                         assert!(
@@ -822,7 +887,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -830,7 +895,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.blue.abs() > std::f32::EPSILON as f64 {
+                    if init_col.blue.abs() > f32::EPSILON as f64 {
                         let error = (init_col.blue - return_col.blue).abs() / init_col.blue.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -843,7 +908,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -874,7 +939,7 @@ mod tests {
                         "return_col is not normal\n{init_col:?} became\n{return_col:?}"
                     );
                     // Comparing with f32 epsilon to allow some leeway
-                    if init_col.red.abs() > std::f32::EPSILON as f64 {
+                    if init_col.red.abs() > f32::EPSILON as f64 {
                         let error = (init_col.red - return_col.red).abs() / init_col.red.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -887,7 +952,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -895,7 +960,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.green.abs() > std::f32::EPSILON as f64 {
+                    if init_col.green.abs() > f32::EPSILON as f64 {
                         let error =
                             (init_col.green - return_col.green).abs() / init_col.green.abs();
                         assert!(
@@ -909,7 +974,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -917,7 +982,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.blue.abs() > std::f32::EPSILON as f64 {
+                    if init_col.blue.abs() > f32::EPSILON as f64 {
                         let error = (init_col.blue - return_col.blue).abs() / init_col.blue.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -930,7 +995,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -961,7 +1026,7 @@ mod tests {
                         "return_col is not normal\n{init_col:?} became\n{return_col:?}"
                     );
                     // Comparing with f32 epsilon to allow some leeway
-                    if init_col.red.abs() > std::f32::EPSILON as f64 {
+                    if init_col.red.abs() > f32::EPSILON as f64 {
                         let error = (init_col.red - return_col.red).abs() / init_col.red.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -974,7 +1039,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -982,7 +1047,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.green.abs() > std::f32::EPSILON as f64 {
+                    if init_col.green.abs() > f32::EPSILON as f64 {
                         let error =
                             (init_col.green - return_col.green).abs() / init_col.green.abs();
                         assert!(
@@ -996,7 +1061,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -1004,7 +1069,7 @@ mod tests {
                         );
                     }
 
-                    if init_col.blue.abs() > std::f32::EPSILON as f64 {
+                    if init_col.blue.abs() > f32::EPSILON as f64 {
                         let error = (init_col.blue - return_col.blue).abs() / init_col.blue.abs();
                         assert!(
                             error < ACCEPTABLE_ERROR,
@@ -1017,7 +1082,7 @@ mod tests {
                                 .red
                                 .max(init_col.green)
                                 .max(init_col.blue)
-                                .max(std::f32::EPSILON as f64))
+                                .max(f32::EPSILON as f64))
                         .abs();
                         assert!(
                             ratio <= ACCEPTABLE_ERROR,
@@ -1028,4 +1093,51 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn max_saturation_search() {
+        // The search used for wide gamuts agrees with the fit for sRGB
+        for step in 0..STEPS {
+            let hue = 2.0 * std::f64::consts::PI * f64::from(step) / f64::from(STEPS);
+            let (a, b) = (hue.cos(), hue.sin());
+            let fitted = compute_max_saturation(&RgbSpace::SRGB, a, b);
+            let searched = search_max_saturation(&RgbSpace::SRGB, a, b);
+            assert!(
+                (fitted - searched).abs() <= ACCEPTABLE_ERROR * fitted,
+                "hue {hue}: fitted {fitted}, searched {searched}"
+            );
+            assert!(searched < search_max_saturation(&RgbSpace::REC2020, a, b));
+        }
+    }
+
+    #[test]
+    fn wide_gamut_mid() {
+        for space in [RgbSpace::DISPLAY_P3, RgbSpace::REC2020, RgbSpace::ADOBE_RGB] {
+            for step in 0..STEPS {
+                let hue = 2.0 * std::f64::consts::PI * f64::from(step) / f64::from(STEPS);
+                let (a, b) = (hue.cos(), hue.sin());
+                let st_max = ST::from_cusp(find_cusp(&space, a, b));
+                let st_mid = ST::mid_in(&space, a, b, st_max);
+                assert!(st_mid.s < st_max.s && st_mid.t < st_max.t, "hue {hue}");
+
+                for lightness in [0.2, 0.5, 0.8] {
+                    let lab = OkLab { lightness, a, b };
+                    let Cs { c_0, c_mid, c_max } = Cs::new(lab, &space);
+                    assert!(0.0 < c_0 && c_mid < c_max, "{}: {lab:?}", space.name);
+
+                    let hsl = OkHsl {
+                        hue,
+                        saturation: 0.9,
+                        lightness: toe(lightness),
+                    };
+                    let back = OkHsl::from_oklab_in(hsl.to_oklab_in(&space), &space);
+                    assert!(
+                        (back.saturation - hsl.saturation).abs() < ACCEPTABLE_ERROR,
+                        "{}: {hsl:?} came back as {back:?}",
+                        space.name
+                    );
+                }
+            }
+        }
+    }
 }
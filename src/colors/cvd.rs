@@ -0,0 +1,530 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Simulation of color vision deficiencies
+//!
+//! Implements the dichromacy simulations of
+//! [Brettel, Viénot and Mollon (1997)](https://doi.org/10.1364/JOSAA.14.002647)
+//! and [Viénot, Brettel and Mollon (1999)](https://doi.org/10.1002/(SICI)1520-6378(199908)24:4%3C243::AID-COL5%3E3.0.CO;2-3),
+//! with the linear sRGB matrices of
+//! [DaltonLens](https://daltonlens.org/opensource-cvd-simulation/), and the
+//! anomalous trichromacy model of
+//! [Machado, Oliveira and Fernandes (2009)](https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html).
+//!
+//! All the simulations are linear (or piecewise linear) maps of [`LinSrgb`]
+//! that keep the neutral colors unchanged.
+
+use serde::{Deserialize, Serialize};
+
+use super::{cie::multiply, LinSrgb, Srgb};
+
+/// The cones affected by a deficiency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Deficiency {
+    /// Long wavelength cones, red-green confusion
+    Protan,
+    /// Medium wavelength cones, red-green confusion, the most common one
+    #[default]
+    Deutan,
+    /// Short wavelength cones, blue-yellow confusion
+    Tritan,
+}
+
+impl Deficiency {
+    pub const ALL: [Self; 3] = [Self::Protan, Self::Deutan, Self::Tritan];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Protan => "Protan",
+            Self::Deutan => "Deutan",
+            Self::Tritan => "Tritan",
+        }
+    }
+
+    /// Name of the full deficiency, when the cones are missing.
+    pub fn anopia(self) -> &'static str {
+        match self {
+            Self::Protan => "protanopia",
+            Self::Deutan => "deuteranopia",
+            Self::Tritan => "tritanopia",
+        }
+    }
+
+    /// Name of the partial deficiency, when the cones are shifted.
+    pub fn anomaly(self) -> &'static str {
+        match self {
+            Self::Protan => "protanomaly",
+            Self::Deutan => "deuteranomaly",
+            Self::Tritan => "tritanomaly",
+        }
+    }
+}
+
+/// Model used to simulate a deficiency.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Method {
+    /// Projection on two half-planes, accurate for all the deficiencies.
+    #[default]
+    Brettel,
+    /// Projection on a single plane, only accurate for protans and deutans.
+    Vienot,
+    /// Shift of the cone sensitivities, made for partial deficiencies.
+    Machado,
+}
+
+impl Method {
+    pub const ALL: [Self; 3] = [Self::Brettel, Self::Vienot, Self::Machado];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Brettel => "Brettel 1997",
+            Self::Vienot => "Viénot 1999",
+            Self::Machado => "Machado 2009",
+        }
+    }
+}
+
+/// A simulated deficiency.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Simulation {
+    pub deficiency: Deficiency,
+    pub method: Method,
+    /// From 0 (normal vision) to 1 (dichromacy)
+    pub severity: f64,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            deficiency: Deficiency::default(),
+            method: Method::default(),
+            severity: 1.0,
+        }
+    }
+}
+
+impl Simulation {
+    /// Name of the simulated deficiency, depending on the severity.
+    pub fn name(&self) -> &'static str {
+        if self.severity >= 1.0 {
+            self.deficiency.anopia()
+        } else {
+            self.deficiency.anomaly()
+        }
+    }
+
+    /// Simulates how `rgb` looks with the deficiency.
+    ///
+    /// The result can be slightly out of the sRGB gamut.
+    pub fn simulate(&self, rgb: LinSrgb) -> LinSrgb {
+        let severity = self.severity.clamp(0.0, 1.0);
+        let input = [rgb.red, rgb.green, rgb.blue];
+        let output = match self.method {
+            Method::Brettel => {
+                let brettel = match self.deficiency {
+                    Deficiency::Protan => &BRETTEL_PROTAN,
+                    Deficiency::Deutan => &BRETTEL_DEUTAN,
+                    Deficiency::Tritan => &BRETTEL_TRITAN,
+                };
+                let [x, y, z] = brettel.normal;
+                let side = x * input[0] + y * input[1] + z * input[2];
+                let matrix = if side >= 0.0 {
+                    &brettel.first
+                } else {
+                    &brettel.second
+                };
+                mix(input, multiply(matrix, input), severity)
+            }
+            Method::Vienot => {
+                let matrix = match self.deficiency {
+                    Deficiency::Protan => &VIENOT_PROTAN,
+                    Deficiency::Deutan => &VIENOT_DEUTAN,
+                    Deficiency::Tritan => &VIENOT_TRITAN,
+                };
+                mix(input, multiply(matrix, input), severity)
+            }
+            Method::Machado => {
+                let table = match self.deficiency {
+                    Deficiency::Protan => &MACHADO_PROTAN,
+                    Deficiency::Deutan => &MACHADO_DEUTAN,
+                    Deficiency::Tritan => &MACHADO_TRITAN,
+                };
+                // The table has a matrix every 0.1 of severity, from 0.1
+                let step = severity * 10.0;
+                let index = (step.floor() as usize).min(9);
+                let lower = match index {
+                    0 => input,
+                    _ => multiply(&table[index - 1], input),
+                };
+                let upper = multiply(&table[index], input);
+                mix(lower, upper, step - index as f64)
+            }
+        };
+
+        let [red, green, blue] = output;
+        LinSrgb { red, green, blue }
+    }
+
+    /// Simulates how `rgb` looks with the deficiency, clamped to the gamut.
+    pub fn simulate_srgb(&self, rgb: Srgb) -> Srgb {
+        let mut simulated = self.simulate(rgb.into());
+        simulated.clamp();
+        simulated.into()
+    }
+}
+
+fn mix(from: [f64; 3], to: [f64; 3], t: f64) -> [f64; 3] {
+    [0, 1, 2].map(|index| from[index] + t * (to[index] - from[index]))
+}
+
+/// The two projections of a Brettel simulation, and the normal of the plane
+/// separating the colors each one applies to.
+struct Brettel {
+    first: [[f64; 3]; 3],
+    second: [[f64; 3]; 3],
+    normal: [f64; 3],
+}
+
+const BRETTEL_PROTAN: Brettel = Brettel {
+    first: [
+        [0.14980, 1.19548, -0.34528],
+        [0.10764, 0.84864, 0.04372],
+        [0.00384, -0.00540, 1.00156],
+    ],
+    second: [
+        [0.14570, 1.16172, -0.30742],
+        [0.10816, 0.85291, 0.03892],
+        [0.00386, -0.00524, 1.00139],
+    ],
+    normal: [0.00048, 0.00393, -0.00441],
+};
+
+const BRETTEL_DEUTAN: Brettel = Brettel {
+    first: [
+        [0.36477, 0.86381, -0.22858],
+        [0.26294, 0.64245, 0.09462],
+        [-0.02006, 0.02728, 0.99278],
+    ],
+    second: [
+        [0.37298, 0.88166, -0.25464],
+        [0.25954, 0.63506, 0.10540],
+        [-0.01980, 0.02784, 0.99196],
+    ],
+    normal: [-0.00281, -0.00611, 0.00892],
+};
+
+const BRETTEL_TRITAN: Brettel = Brettel {
+    first: [
+        [1.01277, 0.13548, -0.14826],
+        [-0.01243, 0.86812, 0.14431],
+        [0.07589, 0.80500, 0.11911],
+    ],
+    second: [
+        [0.93678, 0.18979, -0.12657],
+        [0.06154, 0.81526, 0.12320],
+        [-0.37562, 1.12767, 0.24796],
+    ],
+    normal: [0.03901, -0.02788, -0.01113],
+};
+
+const VIENOT_PROTAN: [[f64; 3]; 3] = [
+    [0.11238, 0.88762, 0.00000],
+    [0.11238, 0.88762, 0.00000],
+    [0.00401, -0.00401, 1.00000],
+];
+
+const VIENOT_DEUTAN: [[f64; 3]; 3] = [
+    [0.29275, 0.70725, 0.00000],
+    [0.29275, 0.70725, 0.00000],
+    [-0.02234, 0.02234, 1.00000],
+];
+
+const VIENOT_TRITAN: [[f64; 3]; 3] = [
+    [1.00000, 0.14461, -0.14461],
+    [0.00000, 0.85924, 0.14076],
+    [0.00000, 0.85924, 0.14076],
+];
+
+/// Machado matrices for severities 0.1, 0.2, …, 1.0.
+const MACHADO_PROTAN: [[[f64; 3]; 3]; 10] = [
+    [
+        [0.856167, 0.182038, -0.038205],
+        [0.029342, 0.955115, 0.015544],
+        [-0.002880, -0.001563, 1.004443],
+    ],
+    [
+        [0.734766, 0.334872, -0.069637],
+        [0.051840, 0.919198, 0.028963],
+        [-0.004928, -0.004209, 1.009137],
+    ],
+    [
+        [0.630323, 0.465641, -0.095964],
+        [0.069181, 0.890046, 0.040773],
+        [-0.006308, -0.007724, 1.014032],
+    ],
+    [
+        [0.539009, 0.579343, -0.118352],
+        [0.082546, 0.866121, 0.051332],
+        [-0.007136, -0.011959, 1.019095],
+    ],
+    [
+        [0.458064, 0.679578, -0.137642],
+        [0.092785, 0.846313, 0.060902],
+        [-0.007494, -0.016807, 1.024301],
+    ],
+    [
+        [0.385450, 0.769005, -0.154455],
+        [0.100526, 0.829802, 0.069673],
+        [-0.007442, -0.022190, 1.029632],
+    ],
+    [
+        [0.319627, 0.849633, -0.169261],
+        [0.106241, 0.815969, 0.077790],
+        [-0.007025, -0.028051, 1.035076],
+    ],
+    [
+        [0.259411, 0.923008, -0.182420],
+        [0.110296, 0.804340, 0.085364],
+        [-0.006276, -0.034346, 1.040622],
+    ],
+    [
+        [0.203876, 0.990338, -0.194214],
+        [0.112975, 0.794542, 0.092483],
+        [-0.005222, -0.041043, 1.046265],
+    ],
+    [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+    ],
+];
+
+const MACHADO_DEUTAN: [[[f64; 3]; 3]; 10] = [
+    [
+        [0.866435, 0.177704, -0.044139],
+        [0.049567, 0.939063, 0.011370],
+        [-0.003453, 0.007233, 0.996220],
+    ],
+    [
+        [0.760729, 0.319078, -0.079807],
+        [0.090568, 0.889315, 0.020117],
+        [-0.006027, 0.013325, 0.992702],
+    ],
+    [
+        [0.675425, 0.433850, -0.109275],
+        [0.125303, 0.847755, 0.026942],
+        [-0.007950, 0.018572, 0.989378],
+    ],
+    [
+        [0.605511, 0.528560, -0.134071],
+        [0.155318, 0.812366, 0.032316],
+        [-0.009376, 0.023176, 0.986200],
+    ],
+    [
+        [0.547494, 0.607765, -0.155259],
+        [0.181692, 0.781742, 0.036566],
+        [-0.010410, 0.027275, 0.983136],
+    ],
+    [
+        [0.498864, 0.674741, -0.173604],
+        [0.205199, 0.754872, 0.039929],
+        [-0.011131, 0.030969, 0.980162],
+    ],
+    [
+        [0.457771, 0.731899, -0.189670],
+        [0.226409, 0.731012, 0.042579],
+        [-0.011595, 0.034333, 0.977261],
+    ],
+    [
+        [0.422823, 0.781057, -0.203881],
+        [0.245752, 0.709602, 0.044646],
+        [-0.011843, 0.037423, 0.974421],
+    ],
+    [
+        [0.392952, 0.823610, -0.216562],
+        [0.263559, 0.690210, 0.046232],
+        [-0.011910, 0.040281, 0.971630],
+    ],
+    [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+    ],
+];
+
+const MACHADO_TRITAN: [[[f64; 3]; 3]; 10] = [
+    [
+        [0.926670, 0.092514, -0.019184],
+        [0.021191, 0.964503, 0.014306],
+        [0.008437, 0.054813, 0.936750],
+    ],
+    [
+        [0.895720, 0.133330, -0.029050],
+        [0.029997, 0.945400, 0.024603],
+        [0.013027, 0.104707, 0.882266],
+    ],
+    [
+        [0.905871, 0.127791, -0.033662],
+        [0.026856, 0.941251, 0.031893],
+        [0.013410, 0.148296, 0.838294],
+    ],
+    [
+        [0.948035, 0.089490, -0.037526],
+        [0.014364, 0.946792, 0.038844],
+        [0.010853, 0.193991, 0.795156],
+    ],
+    [
+        [1.017277, 0.027029, -0.044306],
+        [-0.006113, 0.958479, 0.047634],
+        [0.006379, 0.248708, 0.744913],
+    ],
+    [
+        [1.104996, -0.046633, -0.058363],
+        [-0.032137, 0.971635, 0.060503],
+        [0.001336, 0.317922, 0.680742],
+    ],
+    [
+        [1.193214, -0.109812, -0.083402],
+        [-0.058496, 0.979410, 0.079086],
+        [-0.002346, 0.403492, 0.598854],
+    ],
+    [
+        [1.257728, -0.139648, -0.118081],
+        [-0.078003, 0.975409, 0.102594],
+        [-0.003316, 0.501214, 0.502102],
+    ],
+    [
+        [1.278864, -0.125333, -0.153531],
+        [-0.084748, 0.957674, 0.127074],
+        [-0.000989, 0.601151, 0.399838],
+    ],
+    [
+        [1.255528, -0.076749, -0.178779],
+        [-0.078411, 0.930809, 0.147602],
+        [0.004733, 0.691367, 0.303900],
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::difference::delta_e_ok;
+
+    fn all() -> impl Iterator<Item = Simulation> {
+        Method::ALL.into_iter().flat_map(|method| {
+            Deficiency::ALL
+                .into_iter()
+                .map(move |deficiency| Simulation {
+                    deficiency,
+                    method,
+                    severity: 1.0,
+                })
+        })
+    }
+
+    #[test]
+    fn neutral_colors() {
+        for simulation in all() {
+            for gray in [0.0, 0.18, 0.5, 1.0] {
+                let rgb = LinSrgb {
+                    red: gray,
+                    green: gray,
+                    blue: gray,
+                };
+                let simulated = simulation.simulate(rgb);
+                for channel in [simulated.red, simulated.green, simulated.blue] {
+                    assert!(
+                        (channel - gray).abs() < 1e-4,
+                        "{simulation:?} {simulated:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn severity() {
+        let color = LinSrgb {
+            red: 0.8,
+            green: 0.1,
+            blue: 0.3,
+        };
+        for simulation in all() {
+            let normal = Simulation {
+                severity: 0.0,
+                ..simulation
+            };
+            assert_eq!(normal.simulate(color), color, "{simulation:?}");
+
+            // Partial severities move continuously towards the dichromacy
+            let full = simulation.simulate(color);
+            let mut previous = color;
+            for step in 1..=20 {
+                let partial = Simulation {
+                    severity: f64::from(step) / 20.0,
+                    ..simulation
+                }
+                .simulate(color);
+                let jump = (partial.red - previous.red).abs()
+                    + (partial.green - previous.green).abs()
+                    + (partial.blue - previous.blue).abs();
+                assert!(jump < 0.1, "{simulation:?} at step {step}");
+                previous = partial;
+            }
+            assert_eq!(previous, full);
+        }
+    }
+
+    #[test]
+    fn confusions() {
+        let red = Srgb {
+            red: 0.8,
+            green: 0.2,
+            blue: 0.2,
+        };
+        let green = Srgb {
+            red: 0.45,
+            green: 0.45,
+            blue: 0.1,
+        };
+        let difference = |simulation: Simulation| {
+            delta_e_ok(
+                simulation.simulate_srgb(red).into(),
+                simulation.simulate_srgb(green).into(),
+            )
+        };
+        assert!(delta_e_ok(red.into(), green.into()) > 0.1);
+
+        // The red/olive pair is the classic red-green confusion, and stays
+        // distinct for tritans
+        for method in [Method::Brettel, Method::Vienot] {
+            let simulation = |deficiency| Simulation {
+                deficiency,
+                method,
+                severity: 1.0,
+            };
+            assert!(
+                difference(simulation(Deficiency::Deutan)) < 0.06,
+                "{method:?}"
+            );
+            assert!(
+                difference(simulation(Deficiency::Tritan)) > 0.1,
+                "{method:?}"
+            );
+        }
+
+        // Viénot projects protans on a single plane, which is idempotent
+        let simulation = Simulation {
+            deficiency: Deficiency::Protan,
+            method: Method::Vienot,
+            severity: 1.0,
+        };
+        let once = simulation.simulate(red.into());
+        let twice = simulation.simulate(once);
+        assert!((once.red - twice.red).abs() < 1e-4 && (once.blue - twice.blue).abs() < 1e-4);
+        // Protans can't tell red from green: both channels match
+        assert!((once.red - once.green).abs() < 1e-9);
+    }
+}
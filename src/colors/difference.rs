@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Perceptual difference between colors
+//!
+//! Implements ΔE_OK, the Euclidean distance in OkLab used by
+//! [CSS Color 4](https://www.w3.org/TR/css-color-4/#color-difference-OK),
+//! and the CIE76 and [CIEDE2000](https://hajim.rochester.edu/ece/sites/gsharma/ciede2000/)
+//! distances in CIELAB.
+
+use std::f64::consts::PI;
+
+use super::{Lab, OkLab};
+
+/// ΔE_OK between two colors.
+///
+/// Around 0.02 is the just noticeable difference, and black and white are 1
+/// apart.
+pub fn delta_e_ok(first: OkLab, second: OkLab) -> f64 {
+    ((first.lightness - second.lightness).powi(2)
+        + (first.a - second.a).powi(2)
+        + (first.b - second.b).powi(2))
+    .sqrt()
+}
+
+/// CIE76 ΔE*ab between two colors, the Euclidean distance in CIELAB.
+pub fn cie76(first: Lab, second: Lab) -> f64 {
+    ((first.lightness - second.lightness).powi(2)
+        + (first.a - second.a).powi(2)
+        + (first.b - second.b).powi(2))
+    .sqrt()
+}
+
+/// CIEDE2000 ΔE₀₀ between two colors, with all the weighting factors set to 1.
+pub fn ciede2000(first: Lab, second: Lab) -> f64 {
+    let chroma = |lab: Lab| lab.a.hypot(lab.b);
+    let mean_chroma = (chroma(first) + chroma(second)) / 2.0;
+    let g = 0.5 * (1.0 - (mean_chroma.powi(7) / (mean_chroma.powi(7) + 25f64.powi(7))).sqrt());
+
+    // Chroma and hue (in degrees, from 0 to 360) after stretching the a axis
+    let chroma_hue = |lab: Lab| {
+        let a = (1.0 + g) * lab.a;
+        let hue = if a == 0.0 && lab.b == 0.0 {
+            0.0
+        } else {
+            lab.b.atan2(a).to_degrees().rem_euclid(360.0)
+        };
+        (a.hypot(lab.b), hue)
+    };
+    let (c1, h1) = chroma_hue(first);
+    let (c2, h2) = chroma_hue(second);
+
+    let delta_l = second.lightness - first.lightness;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if (h2 - h1).abs() <= 180.0 {
+        h2 - h1
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else {
+        h2 - h1 + 360.0
+    };
+    let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+    let mean_l = (first.lightness + second.lightness) / 2.0;
+    let mean_c = (c1 + c2) / 2.0;
+    let mean_h = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (mean_h - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * mean_h).to_radians().cos()
+        + 0.32 * (3.0 * mean_h + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * mean_h - 63.0).to_radians().cos();
+    let delta_theta = 30.0 * (-((mean_h - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (mean_c.powi(7) / (mean_c.powi(7) + 25f64.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (mean_l - 50.0).powi(2) / (20.0 + (mean_l - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * mean_c;
+    let s_h = 1.0 + 0.015 * mean_c * t;
+    let r_t = -(2.0 * delta_theta * PI / 180.0).sin() * r_c;
+
+    let l = delta_l / s_l;
+    let c = delta_c / s_c;
+    let h = delta_h / s_h;
+    (l * l + c * c + h * h + r_t * c * h).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Srgb;
+
+    #[test]
+    fn ciede2000_reference() {
+        // From the test data of Sharma, Wu and Dalal
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            ((50.0, 2.49, -0.001), (50.0, -2.49, 0.0009), 7.1792),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+            ((2.0776, 0.0795, -1.135), (0.9033, -0.0636, -0.5514), 0.9082),
+        ];
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let first = Lab {
+                lightness: l1,
+                a: a1,
+                b: b1,
+            };
+            let second = Lab {
+                lightness: l2,
+                a: a2,
+                b: b2,
+            };
+            let delta = ciede2000(first, second);
+            assert!(
+                (delta - expected).abs() < 1e-4,
+                "{first:?} {second:?}: {delta}"
+            );
+            assert!((ciede2000(second, first) - delta).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn euclidean() {
+        let black = Srgb {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        let white = Srgb {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        };
+        assert!((delta_e_ok(black.into(), white.into()) - 1.0).abs() < 1e-3);
+        assert!((cie76(black.into(), white.into()) - 100.0).abs() < 1e-3);
+        assert_eq!(delta_e_ok(white.into(), white.into()), 0.0);
+    }
+}
@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Formatting colors in textual notations
+//!
+//! Every notation here can be read back with [`super::parse`].
+//!
+//! Only `#RRGGBBAA` and `rgba()` always show the alpha. The other notations
+//! drop it, except the Ok, CIE and `color()` ones that add it when the color
+//! is translucent.
+
+use std::fmt;
+
+use super::{rgb::DisplayP3, Alpha, LCh, Lab, OkHsl, OkHsv, OkLCh, OkLab, Srgb};
+
+/// A textual notation for colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// `#RRGGBB`
+    Hex,
+    /// `#RRGGBBAA`
+    HexAlpha,
+    /// `rgb(r, g, b)`, with 0-255 channels
+    Rgb,
+    /// `rgba(r, g, b, a)`, with 0-255 channels and a 0-1 alpha
+    Rgba,
+    /// `okhsv(h, s, v)`, with the hue in radians
+    OkHsv,
+    /// `okhsl(h, s, l)`, with the hue in radians
+    OkHsl,
+    /// `oklab(L a b)`, as in CSS Color 4
+    OkLab,
+    /// `oklch(L C H)`, as in CSS Color 4 (so the hue is in degrees)
+    OkLCh,
+    /// `lab(L a b)`, CIELAB relative to D50 as in CSS Color 4
+    Lab,
+    /// `lch(L C H)`, CIELCh relative to D50 as in CSS Color 4 (so the hue is
+    /// in degrees)
+    LCh,
+    /// `color(display-p3 r g b)`, with 0-1 channels
+    DisplayP3,
+}
+
+impl Notation {
+    pub const ALL: [Self; 11] = [
+        Self::Hex,
+        Self::HexAlpha,
+        Self::Rgb,
+        Self::Rgba,
+        Self::OkHsv,
+        Self::OkHsl,
+        Self::OkLab,
+        Self::OkLCh,
+        Self::Lab,
+        Self::LCh,
+        Self::DisplayP3,
+    ];
+
+    /// Human readable name of the notation.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "Hex",
+            Self::HexAlpha => "HexA",
+            Self::Rgb => "RGB",
+            Self::Rgba => "RGBA",
+            Self::OkHsv => "OkHSV",
+            Self::OkHsl => "OkHSL",
+            Self::OkLab => "OkLab",
+            Self::OkLCh => "OkLCh",
+            Self::Lab => "Lab",
+            Self::LCh => "LCh",
+            Self::DisplayP3 => "Display-P3",
+        }
+    }
+
+    /// Finds a notation from its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|notation| notation.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Formats the color in this notation.
+    pub fn format(self, color: Srgb) -> String {
+        self.format_alpha(Alpha::opaque(color))
+    }
+
+    /// Formats the translucent color in this notation.
+    pub fn format_alpha(self, color: Alpha<Srgb>) -> String {
+        let Alpha { color, alpha } = color;
+        // Separator of the alpha in the other notations, if it is shown at all
+        let alpha_suffix = |separator: &str| {
+            if alpha < 1.0 {
+                format!("{separator}{alpha:.4}")
+            } else {
+                String::new()
+            }
+        };
+
+        match self {
+            Self::Hex => {
+                let (r, g, b) = rgb_u8(color);
+                format!("#{:02X}{:02X}{:02X}", r, g, b)
+            }
+            Self::HexAlpha => {
+                let (r, g, b) = rgb_u8(color);
+                let a = (256.0 * alpha).floor() as u8;
+                format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+            }
+            Self::Rgb => {
+                let (r, g, b) = rgb_u8(color);
+                format!("rgb({}, {}, {})", r, g, b)
+            }
+            Self::Rgba => {
+                let (r, g, b) = rgb_u8(color);
+                format!("rgba({}, {}, {}, {:.3})", r, g, b, alpha)
+            }
+            Self::OkHsv => {
+                let hsv = OkHsv::from(color);
+                format!(
+                    "okhsv({:.4}, {:.4}, {:.4}{})",
+                    hsv.hue,
+                    hsv.saturation,
+                    hsv.value,
+                    alpha_suffix(", ")
+                )
+            }
+            Self::OkHsl => {
+                let hsl = OkHsl::from(color);
+                format!(
+                    "okhsl({:.4}, {:.4}, {:.4}{})",
+                    hsl.hue,
+                    hsl.saturation,
+                    hsl.lightness,
+                    alpha_suffix(", ")
+                )
+            }
+            Self::OkLab => {
+                let lab = OkLab::from(color);
+                format!(
+                    "oklab({:.4} {:.4} {:.4}{})",
+                    lab.lightness,
+                    lab.a,
+                    lab.b,
+                    alpha_suffix(" / ")
+                )
+            }
+            Self::OkLCh => {
+                let lch = OkLCh::from(OkLab::from(color));
+                format!(
+                    "oklch({:.4} {:.4} {:.2}{})",
+                    lch.lightness,
+                    lch.chroma,
+                    lch.hue.to_degrees().rem_euclid(360.0),
+                    alpha_suffix(" / ")
+                )
+            }
+            Self::Lab => {
+                let lab = Lab::from(color);
+                format!(
+                    "lab({:.2} {:.2} {:.2}{})",
+                    lab.lightness,
+                    lab.a,
+                    lab.b,
+                    alpha_suffix(" / ")
+                )
+            }
+            Self::LCh => {
+                let lch = LCh::from(color);
+                format!(
+                    "lch({:.2} {:.2} {:.2}{})",
+                    lch.lightness,
+                    lch.chroma,
+                    lch.hue.to_degrees().rem_euclid(360.0),
+                    alpha_suffix(" / ")
+                )
+            }
+            Self::DisplayP3 => {
+                let p3 = DisplayP3::from(color);
+                format!(
+                    "color(display-p3 {:.4} {:.4} {:.4}{})",
+                    p3.red,
+                    p3.green,
+                    p3.blue,
+                    alpha_suffix(" / ")
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The 8-bit channels of the color, as shown in the widgets.
+pub(crate) fn rgb_u8(color: Srgb) -> (u8, u8, u8) {
+    (
+        (256.0 * color.red).floor() as u8,
+        (256.0 * color.green).floor() as u8,
+        (256.0 * color.blue).floor() as u8,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let color = Srgb {
+            red: 0.4,
+            green: 0.4,
+            blue: 0.7,
+        };
+        assert_eq!(Notation::Hex.format(color), "#6666B3");
+        assert_eq!(Notation::Rgb.format(color), "rgb(102, 102, 179)");
+        assert_eq!(Notation::HexAlpha.format(color), "#6666B3FF");
+        assert_eq!(Notation::Rgba.format(color), "rgba(102, 102, 179, 1.000)");
+        assert_eq!(Notation::Lab.format(color), "lab(45.90 15.29 -41.32)");
+        assert_eq!(
+            Notation::DisplayP3.format(Srgb {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+            }),
+            "color(display-p3 0.9175 0.2003 0.1386)"
+        );
+
+        for notation in Notation::ALL {
+            let text = notation.format(color);
+            let parsed: Srgb = text
+                .parse()
+                .unwrap_or_else(|err| panic!("{text:?} failed to parse: {err}"));
+            assert!(
+                (parsed.red - color.red).abs() < 0.005
+                    && (parsed.green - color.green).abs() < 0.005
+                    && (parsed.blue - color.blue).abs() < 0.005,
+                "{text:?} gave back {parsed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_alpha() {
+        let color = Alpha::new(
+            Srgb {
+                red: 0.4,
+                green: 0.4,
+                blue: 0.7,
+            },
+            0.5,
+        );
+        assert_eq!(Notation::HexAlpha.format_alpha(color), "#6666B380");
+        assert_eq!(
+            Notation::Rgba.format_alpha(color),
+            "rgba(102, 102, 179, 0.500)"
+        );
+        assert_eq!(Notation::Hex.format_alpha(color), "#6666B3");
+
+        for notation in Notation::ALL {
+            let text = notation.format_alpha(color);
+            let parsed: Alpha<Srgb> = text
+                .parse()
+                .unwrap_or_else(|err| panic!("{text:?} failed to parse: {err}"));
+            let expected_alpha = match notation {
+                Notation::Hex | Notation::Rgb => 1.0,
+                _ => color.alpha,
+            };
+            assert!(
+                (parsed.alpha - expected_alpha).abs() < 0.005,
+                "{text:?} gave back {parsed:?}"
+            );
+        }
+    }
+}
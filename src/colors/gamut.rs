@@ -0,0 +1,294 @@
+// SPDX-FileCopyrightText: 2022 Björn Ottosson
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: MIT
+
+//! Gamut mapping
+//!
+//! Brings OkLab/OkLCh colors that don't fit in sRGB back into the gamut, either
+//! with the clipping strategies from
+//! [Björn Ottosson](https://bottosson.github.io/posts/gamutclipping/), or with
+//! the chroma reduction algorithm from
+//! [CSS Color 4](https://www.w3.org/TR/css-color-4/#binsearch).
+//!
+//! All the mapping functions return a [`LinSrgb`] color that is in gamut.
+
+use super::{
+    conversions::{find_cusp, find_gamut_intersection},
+    rgb::RgbSpace,
+    LinSrgb, OkLCh, OkLab,
+};
+
+/// Tolerance for the gamut checks, to absorb the numerical noise of conversions.
+const IN_GAMUT_TOLERANCE: f64 = 1e-6;
+
+/// Chroma under which a color is considered achromatic, and only its
+/// lightness gets clipped.
+const ACHROMATIC_CHROMA: f64 = 1e-5;
+
+/// Strategies to clip a color to the sRGB gamut.
+///
+/// All strategies keep the hue, and move the color in a straight line
+/// towards a point on the lightness axis until it hits the gamut boundary.
+/// They differ in the choice of that point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClipStrategy {
+    /// Keep the chroma as much as possible, only clamping the lightness to [0, 1].
+    PreserveChroma,
+    /// Project towards L = 0.5.
+    ProjectToMid,
+    /// Project towards the lightness of the cusp for the hue.
+    ProjectToLCusp,
+    /// Project towards a point that moves from L = 0.5 to the color's own
+    /// lightness as chroma decreases. A higher `alpha` keeps the lightness
+    /// more often, 0.05 is a good default.
+    AdaptiveL0Mid { alpha: f64 },
+    /// Same as [`ClipStrategy::AdaptiveL0Mid`], but around the lightness of
+    /// the cusp instead of L = 0.5.
+    AdaptiveL0LCusp { alpha: f64 },
+}
+
+impl Default for ClipStrategy {
+    fn default() -> Self {
+        Self::AdaptiveL0Mid { alpha: 0.05 }
+    }
+}
+
+/// Returns `true` if the color is in the sRGB gamut.
+pub fn is_in_gamut(rgb: LinSrgb) -> bool {
+    [rgb.red, rgb.green, rgb.blue]
+        .into_iter()
+        .all(|channel| (-IN_GAMUT_TOLERANCE..=1.0 + IN_GAMUT_TOLERANCE).contains(&channel))
+}
+
+/// Clips the color to the sRGB gamut with the given strategy.
+///
+/// Colors already in gamut are returned unchanged.
+pub fn clip(lab: OkLab, strategy: ClipStrategy) -> LinSrgb {
+    let rgb = LinSrgb::from(lab);
+    if is_in_gamut(rgb) {
+        return rgb;
+    }
+
+    let lightness = lab.lightness;
+    let chroma = (lab.a.powi(2) + lab.b.powi(2)).sqrt();
+    if chroma < ACHROMATIC_CHROMA {
+        let gray = lightness.clamp(0.0, 1.0).powi(3);
+        return LinSrgb {
+            red: gray,
+            green: gray,
+            blue: gray,
+        };
+    }
+
+    let a_ = lab.a / chroma;
+    let b_ = lab.b / chroma;
+    let cusp = find_cusp(&RgbSpace::SRGB, a_, b_);
+
+    let l0 = match strategy {
+        ClipStrategy::PreserveChroma => lightness.clamp(0.0, 1.0),
+        ClipStrategy::ProjectToMid => 0.5,
+        ClipStrategy::ProjectToLCusp => cusp.lightness,
+        ClipStrategy::AdaptiveL0Mid { alpha } => {
+            let l_d = lightness - 0.5;
+            let e_1 = 0.5 + l_d.abs() + alpha * chroma;
+            0.5 * (1.0 + l_d.signum() * (e_1 - (e_1.powi(2) - 2.0 * l_d.abs()).sqrt()))
+        }
+        ClipStrategy::AdaptiveL0LCusp { alpha } => {
+            let l_d = lightness - cusp.lightness;
+            let k = 2.0
+                * if l_d > 0.0 {
+                    1.0 - cusp.lightness
+                } else {
+                    cusp.lightness
+                };
+            let e_1 = 0.5 * k + l_d.abs() + alpha * chroma / k;
+            cusp.lightness
+                + 0.5 * (l_d.signum() * (e_1 - (e_1.powi(2) - 2.0 * k * l_d.abs()).sqrt()))
+        }
+    };
+
+    let t = find_gamut_intersection(&RgbSpace::SRGB, a_, b_, lightness, chroma, l0, cusp);
+    let clipped_lightness = l0 * (1.0 - t) + t * lightness;
+    let clipped_chroma = t * chroma;
+
+    clamp(LinSrgb::from(OkLab {
+        lightness: clipped_lightness,
+        a: clipped_chroma * a_,
+        b: clipped_chroma * b_,
+    }))
+}
+
+/// Maps the color to the sRGB gamut by reducing its chroma, following the
+/// CSS Color 4 algorithm.
+///
+/// Lightness and hue are kept, and chroma is reduced until clamping the
+/// result to sRGB gives a color that isn't noticeably different (ΔE_OK < 0.02).
+pub fn css_gamut_map(lch: OkLCh) -> LinSrgb {
+    /// Just noticeable difference in OkLab
+    const JND: f64 = 0.02;
+    const EPSILON: f64 = 0.0001;
+
+    if lch.lightness >= 1.0 {
+        return LinSrgb {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        };
+    }
+    if lch.lightness <= 0.0 {
+        return LinSrgb::default();
+    }
+
+    let origin = LinSrgb::from(OkLab::from(lch));
+    if is_in_gamut(origin) {
+        return clamp(origin);
+    }
+
+    let delta_e = |clipped: LinSrgb, current: OkLCh| -> f64 {
+        let clipped = OkLab::from(clipped);
+        let current = OkLab::from(current);
+        ((clipped.lightness - current.lightness).powi(2)
+            + (clipped.a - current.a).powi(2)
+            + (clipped.b - current.b).powi(2))
+        .sqrt()
+    };
+
+    let mut current = lch;
+    let mut clipped = clamp(origin);
+    if delta_e(clipped, current) < JND {
+        return clipped;
+    }
+
+    let mut min = 0.0;
+    let mut max = lch.chroma;
+    let mut min_in_gamut = true;
+
+    while max - min > EPSILON {
+        let chroma = (min + max) / 2.0;
+        current.chroma = chroma;
+        let rgb = LinSrgb::from(OkLab::from(current));
+
+        if min_in_gamut && is_in_gamut(rgb) {
+            min = chroma;
+            continue;
+        }
+
+        clipped = clamp(rgb);
+        let error = delta_e(clipped, current);
+        if error < JND {
+            if JND - error < EPSILON {
+                return clipped;
+            }
+            min_in_gamut = false;
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+
+    clipped
+}
+
+fn clamp(mut rgb: LinSrgb) -> LinSrgb {
+    rgb.clamp();
+    rgb
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    const STEPS: i32 = 16;
+
+    /// A grid of OkLCh colors, most of them out of gamut
+    fn lch_grid() -> impl Iterator<Item = OkLCh> {
+        (1..STEPS).flat_map(|l_step| {
+            (0..=STEPS).flat_map(move |c_step| {
+                (0..STEPS).map(move |h_step| OkLCh {
+                    lightness: l_step as f64 / STEPS as f64,
+                    chroma: 0.5 * c_step as f64 / STEPS as f64,
+                    hue: 2.0 * PI * h_step as f64 / STEPS as f64 - PI,
+                })
+            })
+        })
+    }
+
+    fn hue_of(rgb: LinSrgb) -> f64 {
+        OkLCh::from(OkLab::from(rgb)).hue
+    }
+
+    fn assert_same_hue(left: f64, right: f64) {
+        let diff = (left - right).rem_euclid(2.0 * PI);
+        let diff = diff.min(2.0 * PI - diff);
+        assert!(diff < 1e-3, "Hues {left} and {right} are too different");
+    }
+
+    #[test]
+    fn in_gamut_colors_are_unchanged() {
+        let lab = OkLab::from(LinSrgb {
+            red: 0.2,
+            green: 0.5,
+            blue: 0.7,
+        });
+        let strategies = [
+            ClipStrategy::PreserveChroma,
+            ClipStrategy::ProjectToMid,
+            ClipStrategy::ProjectToLCusp,
+            ClipStrategy::AdaptiveL0Mid { alpha: 0.05 },
+            ClipStrategy::AdaptiveL0LCusp { alpha: 0.05 },
+        ];
+        for strategy in strategies {
+            let rgb = clip(lab, strategy);
+            assert!((rgb.red - 0.2).abs() < 1e-6, "{strategy:?} moved {rgb:?}");
+            assert!((rgb.green - 0.5).abs() < 1e-6, "{strategy:?} moved {rgb:?}");
+            assert!((rgb.blue - 0.7).abs() < 1e-6, "{strategy:?} moved {rgb:?}");
+        }
+
+        let rgb = css_gamut_map(OkLCh::from(lab));
+        assert!((rgb.blue - 0.7).abs() < 1e-6, "CSS mapping moved {rgb:?}");
+    }
+
+    #[test]
+    fn clip_strategies() {
+        let strategies = [
+            ClipStrategy::PreserveChroma,
+            ClipStrategy::ProjectToMid,
+            ClipStrategy::ProjectToLCusp,
+            ClipStrategy::AdaptiveL0Mid { alpha: 0.05 },
+            ClipStrategy::AdaptiveL0LCusp { alpha: 0.05 },
+        ];
+        for lch in lch_grid() {
+            for strategy in strategies {
+                let rgb = clip(OkLab::from(lch), strategy);
+                assert!(
+                    is_in_gamut(rgb),
+                    "{lch:?} is out of gamut with {strategy:?}: {rgb:?}"
+                );
+                if lch.chroma > 0.0 && OkLCh::from(OkLab::from(rgb)).chroma > 1e-3 {
+                    assert_same_hue(lch.hue, hue_of(rgb));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn css_gamut_mapping() {
+        for lch in lch_grid() {
+            let rgb = css_gamut_map(lch);
+            assert!(is_in_gamut(rgb), "{lch:?} is out of gamut: {rgb:?}");
+
+            let mapped = OkLCh::from(OkLab::from(rgb));
+            assert!(
+                (mapped.lightness - lch.lightness).abs() < 0.02,
+                "{lch:?} changed lightness too much: {mapped:?}"
+            );
+            assert!(
+                mapped.chroma <= lch.chroma + 0.02,
+                "{lch:?} gained chroma: {mapped:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,390 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Interpolation between colors
+//!
+//! Colors are mixed in one of the [`MixSpace`]s. Hues of the polar spaces
+//! follow the [CSS Color 4](https://www.w3.org/TR/css-color-4/#hue-interpolation)
+//! hue interpolation methods, and a hue is ignored when the color is gray,
+//! so that mixing with white or black doesn't drift through other hues.
+//!
+//! A [`Gradient`] chains several such mixes between its stops.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    gamut::{self, ClipStrategy},
+    LinSrgb, OkHsl, OkLCh, OkLab, Srgb,
+};
+
+/// Chroma (or OkHSL saturation) under which a hue is meaningless.
+const ACHROMATIC: f64 = 1e-4;
+
+/// The color space in which colors are mixed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MixSpace {
+    #[default]
+    OkLab,
+    OkLCh,
+    OkHsl,
+    LinearSrgb,
+}
+
+impl MixSpace {
+    pub const ALL: [Self; 4] = [Self::OkLab, Self::OkLCh, Self::OkHsl, Self::LinearSrgb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OkLab => "OkLab",
+            Self::OkLCh => "OkLCh",
+            Self::OkHsl => "OkHSL",
+            Self::LinearSrgb => "Linear sRGB",
+        }
+    }
+
+    /// Whether the space has a hue, so that the [`HueInterpolation`] matters.
+    pub fn is_polar(self) -> bool {
+        matches!(self, Self::OkLCh | Self::OkHsl)
+    }
+}
+
+/// Which way around the hue circle to go, as in CSS Color 4.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HueInterpolation {
+    /// Take the shorter arc between the hues
+    #[default]
+    Shorter,
+    /// Take the longer arc between the hues
+    Longer,
+    /// Always go counterclockwise, with increasing hue angles
+    Increasing,
+    /// Always go clockwise, with decreasing hue angles
+    Decreasing,
+}
+
+impl HueInterpolation {
+    pub const ALL: [Self; 4] = [
+        Self::Shorter,
+        Self::Longer,
+        Self::Increasing,
+        Self::Decreasing,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Shorter => "Shorter",
+            Self::Longer => "Longer",
+            Self::Increasing => "Increasing",
+            Self::Decreasing => "Decreasing",
+        }
+    }
+
+    /// Interpolates between two hues in radians, returning a hue in (-π, π].
+    pub fn lerp(self, from: f64, to: f64, t: f64) -> f64 {
+        let mut from = from.rem_euclid(2.0 * PI);
+        let mut to = to.rem_euclid(2.0 * PI);
+        let delta = to - from;
+        match self {
+            Self::Shorter if delta > PI => from += 2.0 * PI,
+            Self::Shorter if delta < -PI => to += 2.0 * PI,
+            Self::Longer if 0.0 < delta && delta < PI => from += 2.0 * PI,
+            Self::Longer if -PI < delta && delta <= 0.0 => to += 2.0 * PI,
+            Self::Increasing if to < from => to += 2.0 * PI,
+            Self::Decreasing if from < to => from += 2.0 * PI,
+            _ => {}
+        }
+        let hue = lerp(from, to, t).rem_euclid(2.0 * PI);
+        if hue > PI {
+            hue - 2.0 * PI
+        } else {
+            hue
+        }
+    }
+}
+
+/// Easing of the progression between two stops.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly, quadratic
+    EaseIn,
+    /// Ends slowly, quadratic
+    EaseOut,
+    /// Starts and ends slowly, with smoothstep
+    EaseInOut,
+}
+
+impl Easing {
+    pub const ALL: [Self; 4] = [Self::Linear, Self::EaseIn, Self::EaseOut, Self::EaseInOut];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::EaseIn => "Ease in",
+            Self::EaseOut => "Ease out",
+            Self::EaseInOut => "Ease in-out",
+        }
+    }
+
+    /// Maps a progression in [0, 1] to the eased progression, also in [0, 1].
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+/// Hues of a pair of colors, where a gray color takes the hue of the other.
+fn hues(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let ((from_hue, from_chroma), (to_hue, to_chroma)) = (from, to);
+    match (from_chroma < ACHROMATIC, to_chroma < ACHROMATIC) {
+        (true, false) => (to_hue, to_hue),
+        (false, true) => (from_hue, from_hue),
+        _ => (from_hue, to_hue),
+    }
+}
+
+/// Mixes two colors, `t` going from 0 (`from`) to 1 (`to`).
+///
+/// Mixing in OkLab or OkLCh can leave the sRGB gamut, even between colors
+/// that are in it, so the result is clipped back into it.
+pub fn mix(from: Srgb, to: Srgb, t: f64, space: MixSpace, hue: HueInterpolation) -> Srgb {
+    match space {
+        MixSpace::OkLab => {
+            let (from, to) = (OkLab::from(from), OkLab::from(to));
+            clip(OkLab {
+                lightness: lerp(from.lightness, to.lightness, t),
+                a: lerp(from.a, to.a, t),
+                b: lerp(from.b, to.b, t),
+            })
+        }
+        MixSpace::OkLCh => {
+            let (from, to) = (OkLCh::from(OkLab::from(from)), OkLCh::from(OkLab::from(to)));
+            let (from_hue, to_hue) = hues((from.hue, from.chroma), (to.hue, to.chroma));
+            clip(OkLab::from(OkLCh {
+                lightness: lerp(from.lightness, to.lightness, t),
+                chroma: lerp(from.chroma, to.chroma, t),
+                hue: hue.lerp(from_hue, to_hue, t),
+            }))
+        }
+        MixSpace::OkHsl => {
+            let (from, to) = (OkHsl::from(from), OkHsl::from(to));
+            let (from_hue, to_hue) = hues((from.hue, from.saturation), (to.hue, to.saturation));
+            Srgb::from(OkHsl {
+                hue: hue.lerp(from_hue, to_hue, t),
+                saturation: lerp(from.saturation, to.saturation, t),
+                lightness: lerp(from.lightness, to.lightness, t),
+            })
+        }
+        MixSpace::LinearSrgb => {
+            let (from, to) = (LinSrgb::from(from), LinSrgb::from(to));
+            Srgb::from(LinSrgb {
+                red: lerp(from.red, to.red, t),
+                green: lerp(from.green, to.green, t),
+                blue: lerp(from.blue, to.blue, t),
+            })
+        }
+    }
+}
+
+fn clip(lab: OkLab) -> Srgb {
+    Srgb::from(gamut::clip(lab, ClipStrategy::default()))
+}
+
+/// A color at a position of a [`Gradient`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stop {
+    /// Position in the gradient, from 0 to 1
+    pub position: f64,
+    pub color: Srgb,
+}
+
+impl Stop {
+    pub fn new(position: f64, color: Srgb) -> Self {
+        Self { position, color }
+    }
+}
+
+/// A gradient going through several color stops.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gradient {
+    /// The stops, that [`Gradient::at`] expects sorted by position
+    pub stops: Vec<Stop>,
+    pub space: MixSpace,
+    pub hue: HueInterpolation,
+    /// Easing between each pair of consecutive stops
+    pub easing: Easing,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::new(vec![
+            Stop::new(
+                0.0,
+                Srgb {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 0.5,
+                },
+            ),
+            Stop::new(
+                1.0,
+                Srgb {
+                    red: 1.0,
+                    green: 0.85,
+                    blue: 0.3,
+                },
+            ),
+        ])
+    }
+}
+
+impl Gradient {
+    /// Creates a gradient mixed in OkLab, with the stops sorted by position.
+    pub fn new(stops: Vec<Stop>) -> Self {
+        let mut gradient = Self {
+            stops,
+            space: MixSpace::default(),
+            hue: HueInterpolation::default(),
+            easing: Easing::default(),
+        };
+        gradient.sort();
+        gradient
+    }
+
+    /// Sorts the stops by position, to call after moving them around.
+    pub fn sort(&mut self) {
+        self.stops
+            .sort_by(|first, second| first.position.total_cmp(&second.position));
+    }
+
+    /// The color at `position`, from 0 to 1.
+    ///
+    /// Before the first stop and after the last one, the color of that stop
+    /// is used. A gradient without stops is black.
+    pub fn at(&self, position: f64) -> Srgb {
+        let Some(first) = self.stops.first() else {
+            return Srgb {
+                red: 0.0,
+                green: 0.0,
+                blue: 0.0,
+            };
+        };
+        if position <= first.position {
+            return first.color;
+        }
+
+        for pair in self.stops.windows(2) {
+            let [from, to] = [pair[0], pair[1]];
+            if position <= to.position {
+                let length = to.position - from.position;
+                if length <= 0.0 {
+                    return to.color;
+                }
+                let t = self.easing.apply((position - from.position) / length);
+                return mix(from.color, to.color, t, self.space, self.hue);
+            }
+        }
+
+        self.stops.last().map_or(first.color, |last| last.color)
+    }
+
+    /// Samples `count` evenly spaced colors, from position 0 to 1 included.
+    pub fn sample(&self, count: usize) -> Vec<Srgb> {
+        match count {
+            0 => Vec::new(),
+            1 => vec![self.at(0.0)],
+            _ => (0..count)
+                .map(|index| self.at(index as f64 / (count - 1) as f64))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(left: Srgb, right: Srgb) {
+        assert!(
+            (left.red - right.red).abs() < 1e-6
+                && (left.green - right.green).abs() < 1e-6
+                && (left.blue - right.blue).abs() < 1e-6,
+            "{left:?} and {right:?} are too different"
+        );
+    }
+
+    #[test]
+    fn hue_interpolation() {
+        let (from, to) = (170_f64.to_radians(), (-170_f64).to_radians());
+        let middle = |method: HueInterpolation| method.lerp(from, to, 0.5).to_degrees();
+
+        assert!((middle(HueInterpolation::Shorter) - 180.0).abs() < 1e-9);
+        assert!(middle(HueInterpolation::Longer).abs() < 1e-9);
+        assert!((middle(HueInterpolation::Increasing) - 180.0).abs() < 1e-9);
+        assert!(middle(HueInterpolation::Decreasing).abs() < 1e-9);
+
+        // The ends are kept
+        let end = HueInterpolation::Longer.lerp(from, to, 1.0);
+        assert!((end - to).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gradient() {
+        let black = Srgb {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        let white = Srgb {
+            red: 1.0,
+            green: 1.0,
+            blue: 1.0,
+        };
+        let red = Srgb {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        let mut gradient = Gradient::new(vec![
+            Stop::new(1.0, white),
+            Stop::new(0.0, black),
+            Stop::new(0.5, red),
+        ]);
+
+        for space in MixSpace::ALL {
+            gradient.space = space;
+            let samples = gradient.sample(5);
+            assert_eq!(samples.len(), 5);
+            assert_close(samples[0], black);
+            assert_close(samples[2], red);
+            assert_close(samples[4], white);
+            assert_close(gradient.at(-1.0), black);
+            assert_close(gradient.at(2.0), white);
+        }
+
+        // Mixing with white in OkLCh keeps the hue of red
+        gradient.space = MixSpace::OkLCh;
+        let pink = OkLCh::from(OkLab::from(gradient.at(0.75)));
+        let red = OkLCh::from(OkLab::from(red));
+        assert!((pink.hue - red.hue).abs() < 1e-3, "{pink:?}");
+
+        gradient.easing = Easing::EaseIn;
+        let eased = OkLab::from(gradient.at(0.75)).lightness;
+        gradient.easing = Easing::Linear;
+        assert!(eased < OkLab::from(gradient.at(0.75)).lightness);
+    }
+}
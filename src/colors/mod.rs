@@ -3,8 +3,32 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use eframe::egui::{Color32, Rgba};
+use serde::{Deserialize, Serialize};
 
+/// Implements `From<$from> for $to` by converting to `$via` first.
+macro_rules! convert_via {
+    ($via:ty: $($from:ty => $to:ty),+ $(,)?) => {
+        $(
+            impl From<$from> for $to {
+                fn from(color: $from) -> Self {
+                    Self::from(<$via>::from(color))
+                }
+            }
+        )+
+    };
+}
+
+pub mod batch;
+pub mod cie;
+pub mod contrast;
 pub mod conversions;
+pub mod cvd;
+pub mod difference;
+pub mod format;
+pub mod gamut;
+pub mod interpolate;
+pub mod parse;
+pub mod rgb;
 
 /// The controlling factor for accepting numerical errors in debug builds
 /// preconditions/assertions, and in tests.
@@ -13,7 +37,7 @@ pub mod conversions;
 /// relative errors to `(100.0 * ACCEPTABLE_ERROR)%`
 const ACCEPTABLE_ERROR: f64 = 0.0001;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Srgb {
     pub red: f64,
     pub green: f64,
@@ -36,7 +60,7 @@ impl Default for Srgb {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct LinSrgb {
     pub red: f64,
     pub green: f64,
@@ -51,7 +75,8 @@ impl LinSrgb {
     /// Gamma clipping through dumb clamping.
     ///
     /// This method should only be used for colors _really_ close to be in
-    /// gamut, e.g. to fix numerical noise after conversion cycles.
+    /// gamut, e.g. to fix numerical noise after conversion cycles. Use
+    /// [`gamut`] to map colors that are actually out of gamut.
     pub fn clamp(&mut self) {
         self.red = self.red.clamp(0.0, 1.0);
         self.green = self.green.clamp(0.0, 1.0);
@@ -59,7 +84,7 @@ impl LinSrgb {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct OkLab {
     pub lightness: f64,
     pub a: f64,
@@ -74,7 +99,8 @@ impl OkLab {
     /// Gamma clipping through dumb clamping.
     ///
     /// This method should only be used for colors _really_ close to be in
-    /// gamut, e.g. to fix numerical noise after conversion cycles.
+    /// gamut, e.g. to fix numerical noise after conversion cycles. Use
+    /// [`gamut`] to map colors that are actually out of gamut.
     pub fn clamp(&mut self) {
         self.a = self.a.clamp(-1.0, 1.0);
         self.b = self.b.clamp(-1.0, 1.0);
@@ -82,7 +108,7 @@ impl OkLab {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct OkLCh {
     pub lightness: f64,
     pub chroma: f64,
@@ -90,11 +116,44 @@ pub struct OkLCh {
 }
 
 impl OkLCh {
+    #[allow(dead_code)]
     fn is_normal(&self) -> bool {
         self.lightness.is_finite() && self.chroma.is_finite() && self.hue.is_finite()
     }
 }
-#[derive(Clone, Copy, Debug, PartialEq)]
+
+/// CIE 1931 XYZ tristimulus values, relative to the D65 white point of sRGB.
+///
+/// `y` is the relative luminance, so the white point has `y = 1`. See
+/// [`Xyz::to_d50`] for values relative to D50.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Xyz {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// CIELAB, relative to the D50 white point as in CSS Color 4.
+///
+/// The lightness goes from 0 to 100.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Lab {
+    pub lightness: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// CIELCh, the polar form of [`Lab`].
+///
+/// The hue is in radians, like [`OkLCh`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct LCh {
+    pub lightness: f64,
+    pub chroma: f64,
+    pub hue: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct OkHsl {
     pub hue: f64,
     pub saturation: f64,
@@ -102,6 +161,7 @@ pub struct OkHsl {
 }
 
 impl OkHsl {
+    #[allow(dead_code)]
     fn is_normal(&self) -> bool {
         self.lightness.is_finite() && self.saturation.is_finite() && self.hue.is_finite()
     }
@@ -116,7 +176,7 @@ impl Default for OkHsl {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct OkHsv {
     pub hue: f64,
     pub saturation: f64,
@@ -139,6 +199,64 @@ impl Default for OkHsv {
     }
 }
 
+/// A color with an alpha channel, from 0 (transparent) to 1 (opaque).
+///
+/// The color is never premultiplied by the alpha.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Alpha<C> {
+    pub color: C,
+    pub alpha: f64,
+}
+
+impl<C> Alpha<C> {
+    pub fn new(color: C, alpha: f64) -> Self {
+        Self { color, alpha }
+    }
+
+    pub fn opaque(color: C) -> Self {
+        Self::new(color, 1.0)
+    }
+
+    /// Converts the color to another space, keeping the alpha.
+    pub fn convert<D: From<C>>(self) -> Alpha<D> {
+        Alpha::new(D::from(self.color), self.alpha)
+    }
+}
+
+impl<C: Default> Default for Alpha<C> {
+    fn default() -> Self {
+        Self::opaque(C::default())
+    }
+}
+
+impl From<Color32> for Srgb {
+    fn from(color: Color32) -> Self {
+        Self {
+            red: f64::from(color.r()) / 255.0,
+            green: f64::from(color.g()) / 255.0,
+            blue: f64::from(color.b()) / 255.0,
+        }
+    }
+}
+
+impl From<Srgb> for Color32 {
+    fn from(rgb: Srgb) -> Self {
+        Self::from_rgb(
+            (rgb.red * 256.0).floor() as u8,
+            (rgb.green * 256.0).floor() as u8,
+            (rgb.blue * 256.0).floor() as u8,
+        )
+    }
+}
+
+impl From<Srgb> for Rgba {
+    fn from(rgb: Srgb) -> Self {
+        // Rgba holds linear values
+        let lin = LinSrgb::from(rgb);
+        Self::from_rgb(lin.red as f32, lin.green as f32, lin.blue as f32)
+    }
+}
+
 impl From<OkHsv> for Color32 {
     fn from(hsv: OkHsv) -> Self {
         let rgb = Srgb::from(hsv);
@@ -152,8 +270,7 @@ impl From<OkHsv> for Color32 {
 
 impl From<OkHsv> for Rgba {
     fn from(hsv: OkHsv) -> Self {
-        let rgb = Srgb::from(hsv);
-        Self::from_rgb(rgb.red as f32, rgb.green as f32, rgb.blue as f32)
+        Self::from(Srgb::from(hsv))
     }
 }
 
@@ -170,7 +287,77 @@ impl From<OkHsl> for Color32 {
 
 impl From<OkHsl> for Rgba {
     fn from(hsl: OkHsl) -> Self {
-        let rgb = Srgb::from(hsl);
-        Self::from_rgb(rgb.red as f32, rgb.green as f32, rgb.blue as f32)
+        Self::from(Srgb::from(hsl))
+    }
+}
+
+impl<C> From<Alpha<C>> for Color32
+where
+    Srgb: From<C>,
+{
+    fn from(color: Alpha<C>) -> Self {
+        let rgb = Srgb::from(color.color);
+        Self::from_rgba_unmultiplied(
+            (rgb.red * 256.0).floor() as u8,
+            (rgb.green * 256.0).floor() as u8,
+            (rgb.blue * 256.0).floor() as u8,
+            (color.alpha * 256.0).floor() as u8,
+        )
+    }
+}
+
+impl<C> From<Alpha<C>> for Rgba
+where
+    Srgb: From<C>,
+{
+    fn from(color: Alpha<C>) -> Self {
+        let lin = LinSrgb::from(Srgb::from(color.color));
+        Self::from_rgba_unmultiplied(
+            lin.red as f32,
+            lin.green as f32,
+            lin.blue as f32,
+            color.alpha as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn egui_colors() {
+        for value in (0..=255).step_by(15) {
+            let color = Color32::from_rgb(value, 255 - value, value / 2);
+            let rgb = Srgb::from(color);
+            for converted in [
+                Color32::from(Rgba::from(rgb)),
+                Color32::from(Rgba::from(OkHsv::from(rgb))),
+                Color32::from(Rgba::from(OkHsl::from(rgb))),
+            ] {
+                for (converted, expected) in converted.to_array().into_iter().zip(color.to_array())
+                {
+                    assert!(
+                        converted.abs_diff(expected) <= 1,
+                        "{converted:?} instead of {color:?}"
+                    );
+                }
+            }
+            assert_eq!(Color32::from(rgb), color);
+        }
+
+        let translucent = Rgba::from(Alpha::new(
+            Srgb {
+                red: 0.5,
+                green: 0.5,
+                blue: 0.5,
+            },
+            0.5,
+        ));
+        assert!((translucent.a() - 0.5).abs() < 1e-6);
+        let unmultiplied = Color32::from(translucent).to_srgba_unmultiplied();
+        for channel in unmultiplied {
+            assert!(channel.abs_diff(128) <= 1, "{unmultiplied:?}");
+        }
     }
 }
@@ -0,0 +1,1012 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Parsing colors from their textual notations
+//!
+//! The accepted notations are:
+//! - hexadecimal: `#RGB`, `#RGBA`, `#RRGGBB`, `#RRGGBBAA`
+//! - `rgb()`/`rgba()`, with 0-255 numbers or percentages
+//! - `okhsv()`/`okhsl()`, with the hue in radians by default, as the
+//!   widgets print it
+//! - `oklab()`/`oklch()`, following CSS Color 4 (so the hue of `oklch()` is
+//!   in degrees by default)
+//! - `lab()`/`lch()`, CIELAB and CIELCh relative to D50 as in CSS Color 4
+//! - `color()`, with the `srgb`, `display-p3`, `rec2020` and `a98-rgb` spaces
+//!
+//! Functional notations accept both the legacy comma-separated syntax and the
+//! modern space-separated syntax with an optional `/ alpha`. Any hue can be
+//! given an explicit `deg`, `rad`, `grad` or `turn` unit.
+//!
+//! Parsing into a color type drops the alpha, parse into an [`Alpha`] to
+//! keep it.
+
+use std::{f64::consts::PI, fmt, ops::Range, str::FromStr};
+
+use super::{
+    gamut,
+    rgb::{AdobeRgb, DisplayP3, Rec2020, RgbSpace},
+    Alpha, LCh, Lab, LinSrgb, OkHsl, OkHsv, OkLCh, OkLab, Srgb,
+};
+
+/// Reference chroma for percentages in `oklab()` and `oklch()` (100% = 0.4)
+const OK_CHROMA_PERCENT_REFERENCE: f64 = 0.4;
+
+/// Reference for percentages in the a and b axes of `lab()` (100% = 125)
+const LAB_PERCENT_REFERENCE: f64 = 125.0;
+
+/// Reference chroma for percentages in `lch()` (100% = 150)
+const LCH_CHROMA_PERCENT_REFERENCE: f64 = 150.0;
+
+/// Error returned when a color string cannot be parsed.
+///
+/// The span is a byte range in the original input that points at the
+/// offending part of the string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseColorError {
+    pub kind: ParseColorErrorKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseColorErrorKind {
+    /// The input is empty (or only whitespace)
+    Empty,
+    /// The input is neither a hex color nor a known function
+    UnknownNotation,
+    /// A hex color with a number of digits that isn't 3, 4, 6 or 8
+    InvalidHexLength,
+    /// A character that isn't a hexadecimal digit in a hex color
+    InvalidHexDigit,
+    /// A component that isn't a number, a percentage, an angle or `none`
+    InvalidComponent,
+    /// A component with a unit that doesn't make sense at its position
+    InvalidUnit,
+    /// The function doesn't have the expected number of components
+    WrongComponentCount { expected: usize, found: usize },
+    /// Commas and spaces are mixed up between components
+    MixedSeparators,
+    /// The function is missing its closing parenthesis
+    Unterminated,
+    /// There is input left after a complete color
+    TrailingCharacters,
+}
+
+impl fmt::Display for ParseColorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty color"),
+            Self::UnknownNotation => write!(f, "unknown color notation"),
+            Self::InvalidHexLength => write!(f, "hex colors must have 3, 4, 6 or 8 digits"),
+            Self::InvalidHexDigit => write!(f, "invalid hexadecimal digit"),
+            Self::InvalidComponent => write!(f, "invalid color component"),
+            Self::InvalidUnit => write!(f, "unexpected unit for this component"),
+            Self::WrongComponentCount { expected, found } => {
+                write!(f, "expected {expected} components, found {found}")
+            }
+            Self::MixedSeparators => write!(f, "commas and spaces cannot be mixed"),
+            Self::Unterminated => write!(f, "missing closing parenthesis"),
+            Self::TrailingCharacters => write!(f, "unexpected characters after the color"),
+        }
+    }
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (at {}..{})",
+            self.kind, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl ParseColorError {
+    fn new(kind: ParseColorErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+}
+
+/// A color parsed from any supported notation, in the space it was written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParsedColor {
+    Srgb(Srgb),
+    OkHsv(OkHsv),
+    OkHsl(OkHsl),
+    OkLab(OkLab),
+    OkLCh(OkLCh),
+    Lab(Lab),
+    LCh(LCh),
+    DisplayP3(DisplayP3),
+    Rec2020(Rec2020),
+    AdobeRgb(AdobeRgb),
+}
+
+impl FromStr for ParsedColor {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse().map(|(color, _alpha)| color)
+    }
+}
+
+/// Colors out of the sRGB gamut are brought back into it with
+/// [`gamut::css_gamut_map`].
+impl From<ParsedColor> for Srgb {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::Srgb(rgb) => rgb,
+            ParsedColor::OkHsv(hsv) => Self::from(hsv),
+            ParsedColor::OkHsl(hsl) => Self::from(hsl),
+            other => Self::from(gamut::css_gamut_map(OkLCh::from(OkLab::from(other)))),
+        }
+    }
+}
+
+impl From<ParsedColor> for OkLab {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::OkLab(lab) => lab,
+            ParsedColor::OkLCh(lch) => Self::from(lch),
+            ParsedColor::Lab(lab) => Self::from(lab),
+            ParsedColor::LCh(lch) => Self::from(lch),
+            ParsedColor::DisplayP3(rgb) => Self::from(rgb),
+            ParsedColor::Rec2020(rgb) => Self::from(rgb),
+            ParsedColor::AdobeRgb(rgb) => Self::from(rgb),
+            other => Self::from(LinSrgb::from(Srgb::from(other))),
+        }
+    }
+}
+
+impl From<ParsedColor> for OkLCh {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::OkLCh(lch) => lch,
+            other => Self::from(OkLab::from(other)),
+        }
+    }
+}
+
+impl From<ParsedColor> for Lab {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::Lab(lab) => lab,
+            ParsedColor::LCh(lch) => Self::from(lch),
+            other => Self::from(OkLab::from(other)),
+        }
+    }
+}
+
+impl From<ParsedColor> for LCh {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::LCh(lch) => lch,
+            other => Self::from(Lab::from(other)),
+        }
+    }
+}
+
+impl From<ParsedColor> for OkHsv {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::OkHsv(hsv) => hsv,
+            other => Self::from(Srgb::from(other)),
+        }
+    }
+}
+
+impl From<ParsedColor> for OkHsl {
+    fn from(parsed: ParsedColor) -> Self {
+        match parsed {
+            ParsedColor::OkHsl(hsl) => hsl,
+            other => Self::from(Srgb::from(other)),
+        }
+    }
+}
+
+impl FromStr for Srgb {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl<C: From<ParsedColor>> FromStr for Alpha<C> {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s)
+            .parse()
+            .map(|(color, alpha)| Self::new(C::from(color), alpha))
+    }
+}
+
+impl FromStr for OkHsv {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl FromStr for OkHsl {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl FromStr for OkLab {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl FromStr for OkLCh {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl FromStr for Lab {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+impl FromStr for LCh {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<ParsedColor>().map(Self::from)
+    }
+}
+
+/// A single component of a functional notation
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Component {
+    Number(f64),
+    /// The percentage, as written (so `50%` is `50.0`)
+    Percentage(f64),
+    /// An angle with an explicit unit, in radians
+    Angle(f64),
+    /// The CSS `none` keyword, meaning a missing component
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Separator {
+    Comma,
+    Space,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume characters while `pred` holds, returning the span of the consumed input.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> Range<usize> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !pred(c))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        start..self.pos
+    }
+
+    /// Parse the whole input, returning the color and its alpha.
+    fn parse(mut self) -> Result<(ParsedColor, f64), ParseColorError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::Empty,
+                0..self.input.len(),
+            ));
+        }
+
+        let parsed = if self.peek() == Some('#') {
+            self.parse_hex()?
+        } else {
+            self.parse_function()?
+        };
+
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::TrailingCharacters,
+                self.pos..self.input.len(),
+            ));
+        }
+
+        Ok(parsed)
+    }
+
+    fn parse_hex(&mut self) -> Result<(ParsedColor, f64), ParseColorError> {
+        let start = self.pos;
+        self.eat('#');
+        let span = self.take_while(|c| c.is_alphanumeric());
+        let digits = &self.input[span.clone()];
+
+        if let Some((offset, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            let at = span.start + offset;
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::InvalidHexDigit,
+                at..at + c.len_utf8(),
+            ));
+        }
+
+        // All the digits are ASCII at this point, so byte indexing is fine.
+        let channel = |index: usize, width: usize| -> f64 {
+            let digits = &digits[index * width..(index + 1) * width];
+            let value = u8::from_str_radix(digits, 16).expect("digits are hexadecimal");
+            if width == 1 {
+                f64::from(value * 17) / 255.0
+            } else {
+                f64::from(value) / 255.0
+            }
+        };
+
+        let (width, has_alpha) = match digits.len() {
+            3 => (1, false),
+            4 => (1, true),
+            6 => (2, false),
+            8 => (2, true),
+            _ => {
+                return Err(ParseColorError::new(
+                    ParseColorErrorKind::InvalidHexLength,
+                    start..span.end,
+                ))
+            }
+        };
+
+        let rgb = Srgb {
+            red: channel(0, width),
+            green: channel(1, width),
+            blue: channel(2, width),
+        };
+        let alpha = if has_alpha { channel(3, width) } else { 1.0 };
+        Ok((ParsedColor::Srgb(rgb), alpha))
+    }
+
+    fn parse_function(&mut self) -> Result<(ParsedColor, f64), ParseColorError> {
+        let name_span = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let name = self.input[name_span.clone()].to_ascii_lowercase();
+
+        let known = matches!(
+            name.as_str(),
+            "rgb" | "rgba" | "okhsv" | "okhsl" | "oklab" | "oklch" | "lab" | "lch" | "color"
+        );
+        if !known || !self.eat('(') {
+            let end = if name_span.is_empty() {
+                self.input.len()
+            } else {
+                name_span.end
+            };
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::UnknownNotation,
+                name_span.start..end,
+            ));
+        }
+
+        let space = if name == "color" {
+            Some(self.parse_color_space(name_span.start)?)
+        } else {
+            None
+        };
+
+        let args_start = self.pos;
+        let (components, alpha) = self.parse_arguments(name_span.start)?;
+        let args_span = args_start..self.pos - 1;
+
+        if components.len() != 3 {
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::WrongComponentCount {
+                    expected: 3,
+                    found: components.len(),
+                },
+                args_span,
+            ));
+        }
+
+        let alpha = match alpha {
+            Some((component, span)) => alpha_value(component, span)?,
+            None => 1.0,
+        };
+
+        let [(c0, s0), (c1, s1), (c2, s2)]: [(Component, Range<usize>); 3] =
+            components.try_into().expect("length checked above");
+
+        let color = match name.as_str() {
+            "rgb" | "rgba" => ParsedColor::Srgb(Srgb {
+                red: rgb_value(c0, s0)?,
+                green: rgb_value(c1, s1)?,
+                blue: rgb_value(c2, s2)?,
+            }),
+            "okhsv" => ParsedColor::OkHsv(OkHsv {
+                hue: hue_value(c0, s0, HueUnit::Radians)?,
+                saturation: unit_value(c1, s1)?,
+                value: unit_value(c2, s2)?,
+            }),
+            "okhsl" => ParsedColor::OkHsl(OkHsl {
+                hue: hue_value(c0, s0, HueUnit::Radians)?,
+                saturation: unit_value(c1, s1)?,
+                lightness: unit_value(c2, s2)?,
+            }),
+            "oklab" => ParsedColor::OkLab(OkLab {
+                lightness: unit_value(c0, s0)?,
+                a: chroma_value(c1, s1, OK_CHROMA_PERCENT_REFERENCE, true)?,
+                b: chroma_value(c2, s2, OK_CHROMA_PERCENT_REFERENCE, true)?,
+            }),
+            "oklch" => ParsedColor::OkLCh(OkLCh {
+                lightness: unit_value(c0, s0)?,
+                chroma: chroma_value(c1, s1, OK_CHROMA_PERCENT_REFERENCE, false)?,
+                hue: hue_value(c2, s2, HueUnit::Degrees)?,
+            }),
+            "lab" => ParsedColor::Lab(Lab {
+                lightness: lab_lightness_value(c0, s0)?,
+                a: chroma_value(c1, s1, LAB_PERCENT_REFERENCE, true)?,
+                b: chroma_value(c2, s2, LAB_PERCENT_REFERENCE, true)?,
+            }),
+            "lch" => ParsedColor::LCh(LCh {
+                lightness: lab_lightness_value(c0, s0)?,
+                chroma: chroma_value(c1, s1, LCH_CHROMA_PERCENT_REFERENCE, false)?,
+                hue: hue_value(c2, s2, HueUnit::Degrees)?,
+            }),
+            "color" => {
+                let [red, green, blue] = [
+                    unit_value(c0, s0)?,
+                    unit_value(c1, s1)?,
+                    unit_value(c2, s2)?,
+                ];
+                match space.expect("color() always starts with a space") {
+                    RgbSpace::DISPLAY_P3 => ParsedColor::DisplayP3(DisplayP3 { red, green, blue }),
+                    RgbSpace::REC2020 => ParsedColor::Rec2020(Rec2020 { red, green, blue }),
+                    RgbSpace::ADOBE_RGB => ParsedColor::AdobeRgb(AdobeRgb { red, green, blue }),
+                    _ => ParsedColor::Srgb(Srgb { red, green, blue }),
+                }
+            }
+            _ => unreachable!("function names are checked above"),
+        };
+
+        Ok((color, alpha))
+    }
+
+    /// Parse the color space at the start of `color()`, and the whitespace after it.
+    fn parse_color_space(&mut self, function_start: usize) -> Result<RgbSpace, ParseColorError> {
+        self.skip_whitespace();
+        let span = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-');
+        let space = RgbSpace::from_css_name(&self.input[span.clone()]).ok_or_else(|| {
+            let span = if span.is_empty() {
+                function_start..self.pos
+            } else {
+                span
+            };
+            ParseColorError::new(ParseColorErrorKind::UnknownNotation, span)
+        })?;
+        if !self.skip_whitespace() {
+            let end = self.pos + self.peek().map_or(0, char::len_utf8);
+            return Err(ParseColorError::new(
+                ParseColorErrorKind::InvalidComponent,
+                self.pos..end,
+            ));
+        }
+        Ok(space)
+    }
+
+    /// Parse the arguments of a function, up to and including the closing parenthesis.
+    ///
+    /// Returns the color components and the optional alpha component, with their spans.
+    #[allow(clippy::type_complexity)]
+    fn parse_arguments(
+        &mut self,
+        function_start: usize,
+    ) -> Result<
+        (
+            Vec<(Component, Range<usize>)>,
+            Option<(Component, Range<usize>)>,
+        ),
+        ParseColorError,
+    > {
+        let mut components = Vec::new();
+        let mut alpha = None;
+        let mut separator = None;
+
+        self.skip_whitespace();
+        if self.eat(')') {
+            return Ok((components, alpha));
+        }
+
+        loop {
+            let component = self.parse_component()?;
+            let had_space = self.skip_whitespace();
+
+            if self.eat(')') {
+                components.push(component);
+                break;
+            }
+            if self.rest().is_empty() {
+                return Err(ParseColorError::new(
+                    ParseColorErrorKind::Unterminated,
+                    function_start..self.pos,
+                ));
+            }
+
+            let sep_start = self.pos;
+            let found = if self.eat(',') {
+                Separator::Comma
+            } else if self.eat('/') {
+                // Modern syntax: the alpha is the last component
+                if separator == Some(Separator::Comma) {
+                    return Err(ParseColorError::new(
+                        ParseColorErrorKind::MixedSeparators,
+                        sep_start..self.pos,
+                    ));
+                }
+                components.push(component);
+                self.skip_whitespace();
+                alpha = Some(self.parse_component()?);
+                self.skip_whitespace();
+                if !self.eat(')') {
+                    return Err(if self.rest().is_empty() {
+                        ParseColorError::new(
+                            ParseColorErrorKind::Unterminated,
+                            function_start..self.pos,
+                        )
+                    } else {
+                        ParseColorError::new(
+                            ParseColorErrorKind::TrailingCharacters,
+                            self.pos..self.pos + self.peek().map_or(0, char::len_utf8),
+                        )
+                    });
+                }
+                break;
+            } else if had_space {
+                Separator::Space
+            } else {
+                return Err(ParseColorError::new(
+                    ParseColorErrorKind::InvalidComponent,
+                    component.1.start..self.pos + self.peek().map_or(0, char::len_utf8),
+                ));
+            };
+
+            match separator {
+                None => separator = Some(found),
+                Some(previous) if previous != found => {
+                    return Err(ParseColorError::new(
+                        ParseColorErrorKind::MixedSeparators,
+                        sep_start..self.pos.max(sep_start + 1),
+                    ))
+                }
+                Some(_) => {}
+            }
+
+            components.push(component);
+            self.skip_whitespace();
+        }
+
+        // Legacy syntax: the alpha is the fourth comma-separated component
+        if separator == Some(Separator::Comma) && alpha.is_none() && components.len() == 4 {
+            alpha = components.pop();
+        }
+
+        Ok((components, alpha))
+    }
+
+    fn parse_component(&mut self) -> Result<(Component, Range<usize>), ParseColorError> {
+        let number_span = self.take_while(|c| c.is_ascii_digit() || matches!(c, '.' | '+' | '-'));
+        // Exponents need special care to not eat units
+        let number_span = if matches!(self.peek(), Some('e' | 'E'))
+            && self.rest()[1..]
+                .trim_start_matches(['+', '-'])
+                .starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            let exponent = self.take_while(|c| c.is_ascii_digit() || matches!(c, '+' | '-'));
+            number_span.start..exponent.end
+        } else {
+            number_span
+        };
+        let unit_span = self.take_while(|c| c.is_ascii_alphabetic() || c == '%');
+        let span = number_span.start..unit_span.end;
+
+        let invalid = || ParseColorError::new(ParseColorErrorKind::InvalidComponent, span.clone());
+
+        if number_span.is_empty() {
+            return if self.input[unit_span.clone()].eq_ignore_ascii_case("none") {
+                Ok((Component::None, span))
+            } else if span.is_empty() {
+                let end = self.pos + self.peek().map_or(0, char::len_utf8);
+                Err(ParseColorError::new(
+                    ParseColorErrorKind::InvalidComponent,
+                    self.pos..end,
+                ))
+            } else {
+                Err(invalid())
+            };
+        }
+
+        let value: f64 = self.input[number_span].parse().map_err(|_| invalid())?;
+        if !value.is_finite() {
+            return Err(invalid());
+        }
+
+        let unit = self.input[unit_span.clone()].to_ascii_lowercase();
+        let component = match unit.as_str() {
+            "" => Component::Number(value),
+            "%" => Component::Percentage(value),
+            "deg" => Component::Angle(value.to_radians()),
+            "rad" => Component::Angle(value),
+            "grad" => Component::Angle(value * PI / 200.0),
+            "turn" => Component::Angle(value * 2.0 * PI),
+            _ => {
+                return Err(ParseColorError::new(
+                    ParseColorErrorKind::InvalidUnit,
+                    unit_span,
+                ))
+            }
+        };
+
+        Ok((component, span))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum HueUnit {
+    Degrees,
+    Radians,
+}
+
+fn invalid_unit(span: Range<usize>) -> ParseColorError {
+    ParseColorError::new(ParseColorErrorKind::InvalidUnit, span)
+}
+
+/// Wrap a hue in radians to the (-π, π] range used by the pickers.
+fn normalize_hue(hue: f64) -> f64 {
+    let hue = hue.rem_euclid(2.0 * PI);
+    if hue > PI {
+        hue - 2.0 * PI
+    } else {
+        hue
+    }
+}
+
+fn hue_value(
+    component: Component,
+    span: Range<usize>,
+    default_unit: HueUnit,
+) -> Result<f64, ParseColorError> {
+    let radians = match component {
+        Component::Number(value) => match default_unit {
+            HueUnit::Degrees => value.to_radians(),
+            HueUnit::Radians => value,
+        },
+        Component::Angle(value) => value,
+        Component::None => 0.0,
+        Component::Percentage(_) => return Err(invalid_unit(span)),
+    };
+    Ok(normalize_hue(radians))
+}
+
+/// A component in the [0, 1] range, where 100% is 1.
+fn unit_value(component: Component, span: Range<usize>) -> Result<f64, ParseColorError> {
+    match component {
+        Component::Number(value) => Ok(value.clamp(0.0, 1.0)),
+        Component::Percentage(value) => Ok((value / 100.0).clamp(0.0, 1.0)),
+        Component::None => Ok(0.0),
+        Component::Angle(_) => Err(invalid_unit(span)),
+    }
+}
+
+/// An rgb() channel in the [0, 255] range, where 100% is 255.
+fn rgb_value(component: Component, span: Range<usize>) -> Result<f64, ParseColorError> {
+    match component {
+        Component::Number(value) => Ok((value / 255.0).clamp(0.0, 1.0)),
+        Component::Percentage(value) => Ok((value / 100.0).clamp(0.0, 1.0)),
+        Component::None => Ok(0.0),
+        Component::Angle(_) => Err(invalid_unit(span)),
+    }
+}
+
+/// The lightness of `lab()` and `lch()`, in the [0, 100] range.
+fn lab_lightness_value(component: Component, span: Range<usize>) -> Result<f64, ParseColorError> {
+    match component {
+        Component::Number(value) | Component::Percentage(value) => Ok(value.clamp(0.0, 100.0)),
+        Component::None => Ok(0.0),
+        Component::Angle(_) => Err(invalid_unit(span)),
+    }
+}
+
+/// A Lab axis or LCh chroma, where 100% is `percent_reference`.
+fn chroma_value(
+    component: Component,
+    span: Range<usize>,
+    percent_reference: f64,
+    signed: bool,
+) -> Result<f64, ParseColorError> {
+    let value = match component {
+        Component::Number(value) => value,
+        Component::Percentage(value) => value / 100.0 * percent_reference,
+        Component::None => 0.0,
+        Component::Angle(_) => return Err(invalid_unit(span)),
+    };
+    Ok(if signed { value } else { value.max(0.0) })
+}
+
+fn alpha_value(component: Component, span: Range<usize>) -> Result<f64, ParseColorError> {
+    unit_value(component, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::ACCEPTABLE_ERROR;
+
+    fn assert_close(left: f64, right: f64) {
+        assert!(
+            (left - right).abs() < ACCEPTABLE_ERROR,
+            "{left} and {right} are too different"
+        );
+    }
+
+    fn assert_rgb(input: &str, red: f64, green: f64, blue: f64) {
+        let rgb: Srgb = input
+            .parse()
+            .unwrap_or_else(|err| panic!("{input:?} failed to parse: {err}"));
+        assert_close(rgb.red, red);
+        assert_close(rgb.green, green);
+        assert_close(rgb.blue, blue);
+    }
+
+    fn parse_err(input: &str) -> ParseColorError {
+        input
+            .parse::<ParsedColor>()
+            .expect_err("the input should not parse")
+    }
+
+    #[test]
+    fn hex() {
+        assert_rgb("#6666b3", 102.0 / 255.0, 102.0 / 255.0, 179.0 / 255.0);
+        assert_rgb("#6666B3", 102.0 / 255.0, 102.0 / 255.0, 179.0 / 255.0);
+        assert_rgb("#f0a", 1.0, 0.0, 170.0 / 255.0);
+        assert_rgb("  #f0a8  ", 1.0, 0.0, 170.0 / 255.0);
+        assert_rgb("#11223380", 17.0 / 255.0, 34.0 / 255.0, 51.0 / 255.0);
+
+        let (_, alpha) = Parser::new("#11223380").parse().unwrap();
+        assert_close(alpha, 128.0 / 255.0);
+    }
+
+    #[test]
+    fn rgb_functions() {
+        assert_rgb("rgb(255, 0, 51)", 1.0, 0.0, 0.2);
+        assert_rgb("rgb(255 0 51)", 1.0, 0.0, 0.2);
+        assert_rgb("RGB(100%, 50%, 0%)", 1.0, 0.5, 0.0);
+        assert_rgb("rgba(255, 0, 51, 0.5)", 1.0, 0.0, 0.2);
+        assert_rgb("rgb(255 0 51 / 50%)", 1.0, 0.0, 0.2);
+        assert_rgb("rgb(none 0 1e2%)", 0.0, 0.0, 1.0);
+
+        let (_, alpha) = Parser::new("rgba(255, 0, 51, 25%)").parse().unwrap();
+        assert_close(alpha, 0.25);
+
+        let rgba: Alpha<Srgb> = "rgb(255 0 51 / 0.5)".parse().unwrap();
+        assert_close(rgba.color.green, 0.0);
+        assert_close(rgba.alpha, 0.5);
+        let rgba: Alpha<Srgb> = "rgb(255 0 51)".parse().unwrap();
+        assert_close(rgba.alpha, 1.0);
+    }
+
+    #[test]
+    fn ok_functions() {
+        let lab: OkLab = "oklab(40.1% 0.1143 0.045)".parse().unwrap();
+        assert_close(lab.lightness, 0.401);
+        assert_close(lab.a, 0.1143);
+        assert_close(lab.b, 0.045);
+
+        let lab: OkLab = "oklab(50% -25% 100%)".parse().unwrap();
+        assert_close(lab.a, -0.1);
+        assert_close(lab.b, 0.4);
+
+        let lch: OkLCh = "oklch(59.69% 0.156 49.77 / .5)".parse().unwrap();
+        assert_close(lch.lightness, 0.5969);
+        assert_close(lch.chroma, 0.156);
+        assert_close(lch.hue, 49.77_f64.to_radians());
+
+        let lch: OkLCh = "oklch(0.5 0.1 0.5turn)".parse().unwrap();
+        assert_close(lch.hue, PI);
+
+        let hsv: OkHsv = "okhsv(-1.5, 0.5, 0.75)".parse().unwrap();
+        assert_close(hsv.hue, -1.5);
+        assert_close(hsv.saturation, 0.5);
+        assert_close(hsv.value, 0.75);
+
+        let hsl: OkHsl = "okhsl(270deg 100% 50%)".parse().unwrap();
+        assert_close(hsl.hue, -PI / 2.0);
+        assert_close(hsl.saturation, 1.0);
+        assert_close(hsl.lightness, 0.5);
+    }
+
+    #[test]
+    fn cie_functions() {
+        let lab: Lab = "lab(54.29% 80.8 69.89)".parse().unwrap();
+        assert_close(lab.lightness, 54.29);
+        assert_close(lab.a, 80.8);
+        assert_close(lab.b, 69.89);
+
+        let lab: Lab = "lab(150 -100% 50% / 0.5)".parse().unwrap();
+        assert_close(lab.lightness, 100.0);
+        assert_close(lab.a, -125.0);
+        assert_close(lab.b, 62.5);
+
+        let lch: LCh = "lch(54.29 106.84 40.85)".parse().unwrap();
+        assert_close(lch.chroma, 106.84);
+        assert_close(lch.hue, 40.85_f64.to_radians());
+
+        let rgb: Srgb = "lch(54.29 106.84 40.85)".parse().unwrap();
+        assert!((rgb.red - 1.0).abs() < 0.001 && rgb.green.abs() < 0.001 && rgb.blue.abs() < 0.001);
+    }
+
+    #[test]
+    fn color_function() {
+        let parsed: ParsedColor = "color(display-p3 1 0 0)".parse().unwrap();
+        assert_eq!(
+            parsed,
+            ParsedColor::DisplayP3(DisplayP3 {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+            })
+        );
+
+        assert_rgb("color(srgb 40% 0.4 70%)", 0.4, 0.4, 0.7);
+        let p3 = DisplayP3::from(Srgb {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        });
+        let input = format!("color(display-p3 {} {} {})", p3.red, p3.green, p3.blue);
+        assert_rgb(&input, 1.0, 0.0, 0.0);
+        assert_rgb("COLOR(rec2020 1 1 1)", 1.0, 1.0, 1.0);
+
+        let (_, alpha) = Parser::new("color(a98-rgb 0.5 0.5 0.5 / 25%)")
+            .parse()
+            .unwrap();
+        assert_close(alpha, 0.25);
+
+        assert_eq!(parse_err("color(prophoto 1 0 0)").span, 6..14);
+        assert_eq!(parse_err("color(1 0 0)").span, 6..7);
+        assert_eq!(parse_err("color()").span, 0..6);
+    }
+
+    #[test]
+    fn cross_notation() {
+        let rgb = Srgb {
+            red: 0.4,
+            green: 0.4,
+            blue: 0.7,
+        };
+        let lab = OkLab::from(rgb);
+        let lch = OkLCh::from(lab);
+        let input = format!("oklch({} {} {}rad)", lch.lightness, lch.chroma, lch.hue);
+        assert_rgb(&input, rgb.red, rgb.green, rgb.blue);
+
+        let hsl: OkHsl = "#ffffff".parse().unwrap();
+        assert_close(hsl.lightness, 1.0);
+    }
+
+    #[test]
+    fn out_of_gamut() {
+        // As stored by the text field of the pickers
+        let color: Alpha<Srgb> = "oklch(0.7 0.4 150 / 50%)".parse().unwrap();
+        let Alpha { color, alpha } = color;
+        assert_close(alpha, 0.5);
+        for channel in [color.red, color.green, color.blue] {
+            assert!((0.0..=1.0).contains(&channel), "{color:?}");
+        }
+        let lch = OkLCh::from(OkLab::from(color));
+        assert!((lch.lightness - 0.7).abs() < 0.02, "{lch:?}");
+        assert!((lch.hue.to_degrees() - 150.0).abs() < 5.0, "{lch:?}");
+        assert!(lch.chroma < 0.4, "{lch:?}");
+
+        for input in [
+            "lab(50% 150 0)",
+            "color(display-p3 0 1 0)",
+            "oklab(1.2 0 0)",
+        ] {
+            let color: Srgb = input.parse().unwrap();
+            for channel in [color.red, color.green, color.blue] {
+                assert!((0.0..=1.0).contains(&channel), "{input}: {color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_err("");
+        assert_eq!(err.kind, ParseColorErrorKind::Empty);
+
+        let err = parse_err("#12345");
+        assert_eq!(err.kind, ParseColorErrorKind::InvalidHexLength);
+        assert_eq!(err.span, 0..6);
+
+        let err = parse_err("#12g456");
+        assert_eq!(err.kind, ParseColorErrorKind::InvalidHexDigit);
+        assert_eq!(err.span, 3..4);
+
+        let err = parse_err("hsl(1, 2, 3)");
+        assert_eq!(err.kind, ParseColorErrorKind::UnknownNotation);
+        assert_eq!(err.span, 0..3);
+
+        let err = parse_err("rgb(1, 2)");
+        assert_eq!(
+            err.kind,
+            ParseColorErrorKind::WrongComponentCount {
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(err.span, 4..8);
+
+        let err = parse_err("rgb(1, two, 3)");
+        assert_eq!(err.kind, ParseColorErrorKind::InvalidComponent);
+        assert_eq!(err.span, 7..10);
+
+        let err = parse_err("rgb(1, 2 3)");
+        assert_eq!(err.kind, ParseColorErrorKind::MixedSeparators);
+        assert_eq!(err.span, 9..10);
+
+        let err = parse_err("oklch(50% 0.1 20%)");
+        assert_eq!(err.kind, ParseColorErrorKind::InvalidUnit);
+        assert_eq!(err.span, 14..17);
+
+        let err = parse_err("oklab(50% 0.1px 0)");
+        assert_eq!(err.kind, ParseColorErrorKind::InvalidUnit);
+        assert_eq!(err.span, 13..15);
+
+        let err = parse_err("oklab(50% 0.1 0");
+        assert_eq!(err.kind, ParseColorErrorKind::Unterminated);
+
+        let err = parse_err("#123 foo");
+        assert_eq!(err.kind, ParseColorErrorKind::TrailingCharacters);
+        assert_eq!(err.span, 5..8);
+    }
+}
@@ -0,0 +1,502 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! RGB color spaces
+//!
+//! An [`RgbSpace`] describes the primaries and the transfer function of an
+//! RGB color space, all of them with a D65 white point. The gamut code in
+//! [`super::conversions`] can target any of them, so OkHSV and OkHSL can be
+//! computed relative to wide gamuts too.
+//!
+//! [`DisplayP3`], [`Rec2020`] and [`AdobeRgb`] hold gamma encoded colors in
+//! those spaces, like [`Srgb`] does for sRGB. The matrices come from
+//! [CSS Color 4](https://www.w3.org/TR/css-color-4/#color-conversion-code).
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cie::multiply,
+    conversions::{LMS_TO_LIN_SRGB, OKLAB_TO_LMS},
+    Lab, LinSrgb, OkLab, Srgb, Xyz,
+};
+
+/// Transfer functions between linear light and gamma encoded values.
+///
+/// All of them are extended to negative values by symmetry, as in CSS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Transfer {
+    /// The piecewise sRGB curve, also used by Display P3
+    Srgb,
+    /// The piecewise ITU-R BT.2020 curve
+    Rec2020,
+    /// A pure power curve, with the given exponent for decoding
+    Gamma(f64),
+}
+
+/// Constants of the BT.2020 transfer function, at 64 bits precision
+const REC2020_ALPHA: f64 = 1.099_296_826_809_44;
+const REC2020_BETA: f64 = 0.018_053_968_510_807;
+
+impl Transfer {
+    fn decode(self, value: f64) -> f64 {
+        let abs = value.abs();
+        let linear = match self {
+            Self::Srgb if abs <= 0.040_45 => abs / 12.92,
+            Self::Srgb => ((abs + 0.055) / 1.055).powf(2.4),
+            Self::Rec2020 if abs < REC2020_BETA * 4.5 => abs / 4.5,
+            Self::Rec2020 => ((abs + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45),
+            Self::Gamma(gamma) => abs.powf(gamma),
+        };
+        linear.copysign(value)
+    }
+
+    fn encode(self, value: f64) -> f64 {
+        let abs = value.abs();
+        let encoded = match self {
+            Self::Srgb if abs <= 0.003_130_8 => 12.92 * abs,
+            Self::Srgb => abs.powf(1.0 / 2.4).mul_add(1.055, -0.055),
+            Self::Rec2020 if abs < REC2020_BETA => 4.5 * abs,
+            Self::Rec2020 => REC2020_ALPHA * abs.powf(0.45) - (REC2020_ALPHA - 1.0),
+            Self::Gamma(gamma) => abs.powf(1.0 / gamma),
+        };
+        encoded.copysign(value)
+    }
+}
+
+/// Polynomial fit of the maximum saturation of a gamut, from Björn Ottosson.
+///
+/// It gives the starting point of the Halley iterations in
+/// [`super::conversions`]. Spaces without a fit use a slower search instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SaturationFit {
+    /// The red channel clips first when `1 < a * red[0] + b * red[1]`
+    pub red: [f64; 2],
+    /// Otherwise the green channel clips first when `1 < a * green[0] + b * green[1]`
+    pub green: [f64; 2],
+    /// Coefficients `k0` to `k4` of the polynomial, for each channel
+    pub coefficients: [[f64; 5]; 3],
+}
+
+/// An RGB color space, relative to D65.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RgbSpace {
+    /// Human readable name of the space
+    pub name: &'static str,
+    /// Identifier of the space in the CSS `color()` function
+    pub css_name: &'static str,
+    pub(crate) transfer: Transfer,
+    /// Linear RGB to CIE XYZ
+    pub(crate) to_xyz: [[f64; 3]; 3],
+    /// CIE XYZ to linear RGB
+    pub(crate) from_xyz: [[f64; 3]; 3],
+    /// Cone responses of OkLab (before the cube root) to linear RGB
+    pub(crate) lms_to_linear: [[f64; 3]; 3],
+    pub(crate) saturation_fit: Option<SaturationFit>,
+}
+
+const fn multiply_matrices(left: &[[f64; 3]; 3], right: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    let mut row = 0;
+    while row < 3 {
+        let mut column = 0;
+        while column < 3 {
+            product[row][column] = left[row][0] * right[0][column]
+                + left[row][1] * right[1][column]
+                + left[row][2] * right[2][column];
+            column += 1;
+        }
+        row += 1;
+    }
+    product
+}
+
+/// Linear sRGB to CIE XYZ
+const LIN_SRGB_TO_XYZ: [[f64; 3]; 3] = [
+    [
+        0.412_390_799_265_959_34,
+        0.357_584_339_383_878,
+        0.180_480_788_401_834_3,
+    ],
+    [
+        0.212_639_005_871_510_27,
+        0.715_168_678_767_756,
+        0.072_192_315_360_733_71,
+    ],
+    [
+        0.019_330_818_715_591_82,
+        0.119_194_779_794_625_98,
+        0.950_532_152_249_660_7,
+    ],
+];
+
+/// The cone responses of OkLab in XYZ, going through sRGB so that white
+/// stays white in every space.
+const LMS_TO_XYZ: [[f64; 3]; 3] = multiply_matrices(&LIN_SRGB_TO_XYZ, &LMS_TO_LIN_SRGB);
+
+impl RgbSpace {
+    pub const SRGB: Self = Self {
+        name: "sRGB",
+        css_name: "srgb",
+        transfer: Transfer::Srgb,
+        to_xyz: LIN_SRGB_TO_XYZ,
+        from_xyz: [
+            [
+                3.240_969_941_904_522_6,
+                -1.537_383_177_570_094,
+                -0.498_610_760_293_003_4,
+            ],
+            [
+                -0.969_243_636_280_879_6,
+                1.875_967_501_507_720_2,
+                0.041_555_057_407_175_59,
+            ],
+            [
+                0.055_630_079_696_993_66,
+                -0.203_976_958_888_976_52,
+                1.056_971_514_242_878_6,
+            ],
+        ],
+        lms_to_linear: LMS_TO_LIN_SRGB,
+        saturation_fit: Some(SaturationFit {
+            red: [-1.881_703_28, -0.809_364_93],
+            green: [1.814_441_04, -1.194_452_76],
+            coefficients: [
+                [
+                    1.190_862_77,
+                    1.765_767_28,
+                    0.596_626_41,
+                    0.755_151_97,
+                    0.567_712_45,
+                ],
+                [
+                    0.739_565_15,
+                    -0.459_544_04,
+                    0.082_854_27,
+                    0.125_410_70,
+                    0.145_032_04,
+                ],
+                [
+                    1.357_336_52,
+                    -0.009_157_99,
+                    -1.151_302_10,
+                    -0.505_596_06,
+                    0.006_921_67,
+                ],
+            ],
+        }),
+    };
+
+    pub const DISPLAY_P3: Self = Self::new(
+        "Display P3",
+        "display-p3",
+        Transfer::Srgb,
+        [
+            [
+                0.486_570_948_648_216_2,
+                0.265_667_693_169_093_06,
+                0.198_217_285_234_362_5,
+            ],
+            [
+                0.228_974_564_069_748_8,
+                0.691_738_521_836_506_4,
+                0.079_286_914_093_745,
+            ],
+            [0.0, 0.045_113_381_858_902_64, 1.043_944_368_900_976],
+        ],
+        [
+            [
+                2.493_496_911_941_425,
+                -0.931_383_617_919_123_9,
+                -0.402_710_784_450_716_84,
+            ],
+            [
+                -0.829_488_969_561_574_7,
+                1.762_664_060_318_346_3,
+                0.023_624_685_841_943_577,
+            ],
+            [
+                0.035_845_830_243_784_47,
+                -0.076_172_389_268_041_82,
+                0.956_884_524_007_687_2,
+            ],
+        ],
+    );
+
+    pub const REC2020: Self = Self::new(
+        "Rec. 2020",
+        "rec2020",
+        Transfer::Rec2020,
+        [
+            [
+                0.636_958_048_301_291_4,
+                0.144_616_903_586_208_32,
+                0.168_880_975_164_172_1,
+            ],
+            [
+                0.262_700_212_011_267_1,
+                0.677_998_071_518_870_8,
+                0.059_301_716_469_861_96,
+            ],
+            [0.0, 0.028_072_693_049_087_428, 1.060_985_057_710_791],
+        ],
+        [
+            [
+                1.716_651_187_971_268,
+                -0.355_670_783_776_392,
+                -0.253_366_281_373_66,
+            ],
+            [
+                -0.666_684_351_832_489,
+                1.616_481_236_634_939,
+                0.015_768_545_813_911_1,
+            ],
+            [
+                0.017_639_857_445_311,
+                -0.042_770_613_257_809,
+                0.942_103_121_235_474,
+            ],
+        ],
+    );
+
+    pub const ADOBE_RGB: Self = Self::new(
+        "Adobe RGB",
+        "a98-rgb",
+        Transfer::Gamma(563.0 / 256.0),
+        [
+            [
+                0.576_669_042_910_130_5,
+                0.185_558_237_906_546_3,
+                0.188_228_646_234_994_7,
+            ],
+            [
+                0.297_344_975_250_536_05,
+                0.627_363_566_255_466_1,
+                0.075_291_458_493_997_88,
+            ],
+            [
+                0.027_031_361_386_412_34,
+                0.070_688_852_535_827_23,
+                0.991_337_536_837_638_8,
+            ],
+        ],
+        [
+            [
+                2.041_587_903_810_746_5,
+                -0.565_006_974_278_859_6,
+                -0.344_731_350_778_329_56,
+            ],
+            [
+                -0.969_243_636_280_879_5,
+                1.875_967_501_507_720_2,
+                0.041_555_057_407_175_57,
+            ],
+            [
+                0.013_444_280_632_031_142,
+                -0.118_362_392_231_018_38,
+                1.015_174_994_391_205_4,
+            ],
+        ],
+    );
+
+    pub const ALL: [Self; 4] = [Self::SRGB, Self::DISPLAY_P3, Self::REC2020, Self::ADOBE_RGB];
+
+    const fn new(
+        name: &'static str,
+        css_name: &'static str,
+        transfer: Transfer,
+        to_xyz: [[f64; 3]; 3],
+        from_xyz: [[f64; 3]; 3],
+    ) -> Self {
+        Self {
+            name,
+            css_name,
+            transfer,
+            to_xyz,
+            from_xyz,
+            lms_to_linear: multiply_matrices(&from_xyz, &LMS_TO_XYZ),
+            saturation_fit: None,
+        }
+    }
+
+    /// Finds a space from its identifier in CSS `color()`, ignoring case.
+    pub fn from_css_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|space| space.css_name.eq_ignore_ascii_case(name))
+    }
+
+    /// Converts gamma encoded channels to linear light.
+    pub fn decode(&self, encoded: [f64; 3]) -> [f64; 3] {
+        encoded.map(|value| self.transfer.decode(value))
+    }
+
+    /// Converts linear light channels to gamma encoded ones.
+    pub fn encode(&self, linear: [f64; 3]) -> [f64; 3] {
+        linear.map(|value| self.transfer.encode(value))
+    }
+
+    pub fn linear_to_xyz(&self, linear: [f64; 3]) -> Xyz {
+        let [x, y, z] = multiply(&self.to_xyz, linear);
+        Xyz { x, y, z }
+    }
+
+    pub fn xyz_to_linear(&self, xyz: Xyz) -> [f64; 3] {
+        multiply(&self.from_xyz, [xyz.x, xyz.y, xyz.z])
+    }
+
+    /// Converts an OkLab color to linear RGB in this space.
+    pub fn oklab_to_linear(&self, lab: OkLab) -> [f64; 3] {
+        let lms_ = multiply(&OKLAB_TO_LMS, [lab.lightness, lab.a, lab.b]);
+        multiply(&self.lms_to_linear, lms_.map(|value| value.powi(3)))
+    }
+}
+
+/// Defines a gamma encoded RGB color type in `$space`, and its conversions.
+macro_rules! rgb_color {
+    ($(#[$attr:meta])* $name:ident, $space:expr) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+        pub struct $name {
+            pub red: f64,
+            pub green: f64,
+            pub blue: f64,
+        }
+
+        impl $name {
+            pub const SPACE: RgbSpace = $space;
+        }
+
+        impl From<Xyz> for $name {
+            fn from(xyz: Xyz) -> Self {
+                let [red, green, blue] = Self::SPACE.encode(Self::SPACE.xyz_to_linear(xyz));
+                Self { red, green, blue }
+            }
+        }
+
+        impl From<$name> for Xyz {
+            fn from(rgb: $name) -> Self {
+                $name::SPACE.linear_to_xyz($name::SPACE.decode([rgb.red, rgb.green, rgb.blue]))
+            }
+        }
+
+        convert_via!(Xyz:
+            LinSrgb => $name, $name => LinSrgb,
+            Srgb => $name, $name => Srgb,
+            OkLab => $name, $name => OkLab,
+            Lab => $name, $name => Lab,
+        );
+    };
+}
+
+rgb_color!(
+    /// Display P3, the wide gamut of recent screens, with the sRGB transfer
+    /// function.
+    DisplayP3,
+    RgbSpace::DISPLAY_P3
+);
+
+rgb_color!(
+    /// ITU-R BT.2020, the ultra wide gamut of UHD video.
+    Rec2020,
+    RgbSpace::REC2020
+);
+
+rgb_color!(
+    /// Adobe RGB (1998), common in photography and print.
+    AdobeRgb,
+    RgbSpace::ADOBE_RGB
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{cie::D65, OkHsl, OkHsv};
+
+    fn assert_close(left: [f64; 3], right: [f64; 3], tolerance: f64) {
+        assert!(
+            left.iter()
+                .zip(right)
+                .all(|(left, right)| (left - right).abs() < tolerance),
+            "{left:?} and {right:?} are too different"
+        );
+    }
+
+    #[test]
+    fn spaces() {
+        for space in RgbSpace::ALL {
+            let white = space.linear_to_xyz([1.0; 3]);
+            assert_close([white.x, white.y, white.z], [D65.x, D65.y, D65.z], 1e-4);
+
+            let color = [0.2, 0.5, 0.9];
+            let xyz = space.linear_to_xyz(color);
+            assert_close(space.xyz_to_linear(xyz), color, 1e-9);
+            assert_close(space.encode(space.decode(color)), color, 1e-9);
+            assert_close(
+                space.decode(space.encode([-0.5, 0.01, 2.0])),
+                [-0.5, 0.01, 2.0],
+                1e-9,
+            );
+
+            // OkLab white is white in every space
+            let white = space.oklab_to_linear(OkLab {
+                lightness: 1.0,
+                a: 0.0,
+                b: 0.0,
+            });
+            assert_close(white, [1.0; 3], 1e-4);
+        }
+
+        let red = Srgb {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        let p3 = DisplayP3::from(red);
+        assert_close([p3.red, p3.green, p3.blue], [0.9175, 0.2003, 0.1386], 1e-4);
+        let back = Srgb::from(p3);
+        assert_close([back.red, back.green, back.blue], [1.0, 0.0, 0.0], 1e-9);
+    }
+
+    #[test]
+    fn wide_gamut_okhsv() {
+        let p3_red = OkLab::from(DisplayP3 {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        });
+        let hsv = OkHsv::from_oklab_in(p3_red, &RgbSpace::DISPLAY_P3);
+        assert!((hsv.saturation - 1.0).abs() < 1e-3, "{hsv:?}");
+        assert!((hsv.value - 1.0).abs() < 1e-3, "{hsv:?}");
+        let back = hsv.to_oklab_in(&RgbSpace::DISPLAY_P3);
+        assert_close(
+            [back.lightness, back.a, back.b],
+            [p3_red.lightness, p3_red.a, p3_red.b],
+            1e-6,
+        );
+
+        // sRGB red is not the most saturated red in P3
+        let srgb_red = OkLab::from(Srgb {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+        });
+        let hsv = OkHsv::from_oklab_in(srgb_red, &RgbSpace::DISPLAY_P3);
+        assert!(hsv.saturation < 0.99, "{hsv:?}");
+
+        for space in [RgbSpace::REC2020, RgbSpace::ADOBE_RGB] {
+            let color = OkLab {
+                lightness: 0.6,
+                a: 0.1,
+                b: -0.05,
+            };
+            let hsl = OkHsl::from_oklab_in(color, &space);
+            let back = hsl.to_oklab_in(&space);
+            assert_close(
+                [back.lightness, back.a, back.b],
+                [color.lightness, color.a, color.b],
+                1e-6,
+            );
+        }
+    }
+}
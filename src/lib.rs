@@ -2,5 +2,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+pub mod app;
+pub mod cli;
 pub mod colors;
+pub mod palette;
 pub mod widgets;
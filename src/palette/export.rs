@@ -0,0 +1,534 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Exporting palettes to editor and terminal theme formats
+//!
+//! Stylesheet and token formats export every swatch, using its name as an
+//! identifier. Terminal formats only know about a fixed set of colors, so they
+//! export the swatches named like the ones of a generated [`Scheme`]:
+//! `background`, `foreground`, `selection`, `comment`, `accent`, and `color0`
+//! to `color15` (or `black` to `bright_white`). The other swatches are left
+//! out.
+//!
+//! [`Scheme`]: super::generate::Scheme
+
+use std::fmt::{self, Write};
+
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+
+use super::{generate::ANSI_NAMES, Swatch};
+use crate::colors::format::{rgb_u8, Notation};
+
+/// A file format palettes can be exported to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// CSS custom properties, in a `:root` rule
+    #[default]
+    Css,
+    /// SCSS variables
+    Scss,
+    /// Design tokens, following the W3C community group format
+    DesignTokens,
+    /// GIMP palette
+    Gpl,
+    /// Alacritty TOML configuration
+    Alacritty,
+    /// kitty configuration
+    Kitty,
+    /// X resources
+    Xresources,
+    /// Skeleton of an Emacs `deftheme`
+    EmacsTheme,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 8] = [
+        Self::Css,
+        Self::Scss,
+        Self::DesignTokens,
+        Self::Gpl,
+        Self::Alacritty,
+        Self::Kitty,
+        Self::Xresources,
+        Self::EmacsTheme,
+    ];
+
+    /// Human readable name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Css => "CSS custom properties",
+            Self::Scss => "SCSS variables",
+            Self::DesignTokens => "JSON design tokens",
+            Self::Gpl => "GIMP palette",
+            Self::Alacritty => "Alacritty",
+            Self::Kitty => "kitty",
+            Self::Xresources => "Xresources",
+            Self::EmacsTheme => "Emacs theme",
+        }
+    }
+
+    /// Usual extension of the exported files, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Css => "css",
+            Self::Scss => "scss",
+            Self::DesignTokens => "tokens.json",
+            Self::Gpl => "gpl",
+            Self::Alacritty => "toml",
+            Self::Kitty => "conf",
+            Self::Xresources => "Xresources",
+            Self::EmacsTheme => "el",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Serializes the palette called `name` in the given format.
+pub fn export(format: ExportFormat, name: &str, palette: &[Swatch]) -> String {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Css => css(&mut out, name, palette),
+        ExportFormat::Scss => scss(&mut out, name, palette),
+        ExportFormat::DesignTokens => design_tokens(&mut out, name, palette),
+        ExportFormat::Gpl => gpl(&mut out, name, palette),
+        ExportFormat::Alacritty => alacritty(&mut out, name, palette),
+        ExportFormat::Kitty => kitty(&mut out, name, palette),
+        ExportFormat::Xresources => xresources(&mut out, name, palette),
+        ExportFormat::EmacsTheme => emacs_theme(&mut out, name, palette),
+    }
+    .expect("writing to a String cannot fail");
+    out
+}
+
+/// Turns a swatch name into a lowercase identifier, with words separated by
+/// `separator`.
+///
+/// Identifiers starting with a digit get a `color` prefix, as most formats
+/// don't accept them.
+pub fn slug(name: &str, separator: char) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with(separator) {
+            slug.push(separator);
+        }
+    }
+    while slug.ends_with(separator) {
+        slug.pop();
+    }
+    if slug.is_empty() {
+        slug.push_str("color");
+    } else if slug.starts_with(|c: char| c.is_numeric()) {
+        slug.insert(0, separator);
+        slug.insert_str(0, "color");
+    }
+    slug
+}
+
+/// Makes `text` fit on one line of a comment, replacing the control
+/// characters (line breaks included) with spaces.
+fn one_line(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+/// Same as [`one_line`], also breaking the `*/` that would end a block
+/// comment.
+fn block_comment(text: &str) -> String {
+    one_line(text).replace("*/", "* /")
+}
+
+/// Escapes `text` for an Emacs Lisp string.
+fn elisp_string(text: &str) -> String {
+    one_line(text).replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Unique identifiers for all the swatches, in order.
+///
+/// Identifiers that would clash get a numbered suffix.
+fn identifiers(palette: &[Swatch], separator: char) -> Vec<String> {
+    let mut identifiers: Vec<String> = Vec::with_capacity(palette.len());
+    for swatch in palette {
+        let base = slug(&swatch.name, separator);
+        let mut identifier = base.clone();
+        let mut suffix = 2;
+        while identifiers.contains(&identifier) {
+            identifier = format!("{base}{separator}{suffix}");
+            suffix += 1;
+        }
+        identifiers.push(identifier);
+    }
+    identifiers
+}
+
+fn hex(swatch: &Swatch) -> String {
+    Notation::Hex.format(swatch.color)
+}
+
+/// Finds the swatch with the given name, ignoring case and separators.
+fn find<'a>(palette: &'a [Swatch], name: &str) -> Option<&'a Swatch> {
+    palette
+        .iter()
+        .find(|swatch| slug(&swatch.name, '_') == name)
+}
+
+/// Finds the swatch of the ANSI color `index`, named `color{index}` or after
+/// the color.
+fn find_ansi(palette: &[Swatch], index: usize) -> Option<&Swatch> {
+    find(palette, &format!("color{index}")).or_else(|| find(palette, ANSI_NAMES[index]))
+}
+
+fn css(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "/* {} */", block_comment(name))?;
+    writeln!(out, ":root {{")?;
+    for (identifier, swatch) in identifiers(palette, '-').iter().zip(palette) {
+        writeln!(out, "  --{identifier}: {};", hex(swatch))?;
+    }
+    writeln!(out, "}}")
+}
+
+fn scss(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "// {}", one_line(name))?;
+    for (identifier, swatch) in identifiers(palette, '-').iter().zip(palette) {
+        writeln!(out, "${identifier}: {};", hex(swatch))?;
+    }
+    Ok(())
+}
+
+fn design_tokens(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    /// A group of color tokens named after the palette, keeping its order
+    struct Group<'a>(&'a str, &'a [Swatch]);
+
+    impl Serialize for Group<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let Self(name, palette) = *self;
+            let tokens = Tokens(palette);
+            serializer.collect_map([(slug(name, '-'), tokens)])
+        }
+    }
+
+    struct Tokens<'a>(&'a [Swatch]);
+
+    impl Serialize for Tokens<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.0.len()))?;
+            for (identifier, swatch) in identifiers(self.0, '-').iter().zip(self.0) {
+                let mut token = serde_json::Map::new();
+                token.insert("$type".to_owned(), "color".into());
+                token.insert("$value".to_owned(), hex(swatch).into());
+                map.serialize_entry(identifier, &token)?;
+            }
+            map.end()
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&Group(name, palette)).map_err(|_| fmt::Error)?;
+    writeln!(out, "{json}")
+}
+
+fn gpl(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "GIMP Palette")?;
+    writeln!(out, "Name: {}", one_line(name))?;
+    writeln!(out, "Columns: 0")?;
+    writeln!(out, "#")?;
+    for swatch in palette {
+        let (r, g, b) = rgb_u8(swatch.color);
+        writeln!(out, "{r:3} {g:3} {b:3}\t{}", one_line(&swatch.name))?;
+    }
+    Ok(())
+}
+
+fn alacritty(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "# {}", one_line(name))?;
+
+    let section = |out: &mut String, header: &str, entries: &[(&str, Option<&Swatch>)]| {
+        if entries.iter().all(|(_, swatch)| swatch.is_none()) {
+            return Ok(());
+        }
+        writeln!(out, "\n[{header}]")?;
+        for (key, swatch) in entries {
+            if let Some(swatch) = swatch {
+                writeln!(out, "{key} = \"{}\"", hex(swatch))?;
+            }
+        }
+        Ok(())
+    };
+
+    section(
+        out,
+        "colors.primary",
+        &[
+            ("background", find(palette, "background")),
+            ("foreground", find(palette, "foreground")),
+        ],
+    )?;
+    section(
+        out,
+        "colors.selection",
+        &[("background", find(palette, "selection"))],
+    )?;
+    for (header, offset) in [("colors.normal", 0), ("colors.bright", 8)] {
+        let entries: Vec<_> = ANSI_NAMES[..8]
+            .iter()
+            .enumerate()
+            .map(|(index, key)| (*key, find_ansi(palette, offset + index)))
+            .collect();
+        section(out, header, &entries)?;
+    }
+    Ok(())
+}
+
+fn kitty(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "# {}", one_line(name))?;
+    let special = [
+        ("background", "background"),
+        ("foreground", "foreground"),
+        ("selection_background", "selection"),
+        ("cursor", "accent"),
+        ("url_color", "accent"),
+    ];
+    for (key, swatch) in special {
+        if let Some(swatch) = find(palette, swatch) {
+            writeln!(out, "{key:<21}{}", hex(swatch))?;
+        }
+    }
+    for index in 0..ANSI_NAMES.len() {
+        if let Some(swatch) = find_ansi(palette, index) {
+            writeln!(out, "{:<21}{}", format!("color{index}"), hex(swatch))?;
+        }
+    }
+    Ok(())
+}
+
+fn xresources(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    writeln!(out, "! {}", one_line(name))?;
+    for (key, swatch) in [
+        ("background", "background"),
+        ("foreground", "foreground"),
+        ("cursorColor", "accent"),
+    ] {
+        if let Some(swatch) = find(palette, swatch) {
+            writeln!(out, "*.{key}: {}", hex(swatch))?;
+        }
+    }
+    for index in 0..ANSI_NAMES.len() {
+        if let Some(swatch) = find_ansi(palette, index) {
+            writeln!(out, "*.color{index}: {}", hex(swatch))?;
+        }
+    }
+    Ok(())
+}
+
+fn emacs_theme(out: &mut String, name: &str, palette: &[Swatch]) -> fmt::Result {
+    let theme = slug(name, '-');
+    let identifiers = identifiers(palette, '-');
+    let variable = |swatch: &str| {
+        palette
+            .iter()
+            .position(|candidate| slug(&candidate.name, '_') == swatch)
+            .map(|index| identifiers[index].as_str())
+    };
+
+    writeln!(
+        out,
+        ";;; {theme}-theme.el --- {} -*- lexical-binding: t -*-",
+        one_line(name)
+    )?;
+    writeln!(out)?;
+    writeln!(out, "(deftheme {theme} \"{}\")", elisp_string(name))?;
+    writeln!(out)?;
+    writeln!(out, "(let (")?;
+    for (identifier, swatch) in identifiers.iter().zip(palette) {
+        writeln!(out, "      ({identifier} \"{}\")", hex(swatch))?;
+    }
+    writeln!(out, "      )")?;
+    writeln!(out, "  (custom-theme-set-faces")?;
+    writeln!(out, "   '{theme}")?;
+
+    let faces = [
+        ("default", ":foreground", "foreground"),
+        ("default", ":background", "background"),
+        ("region", ":background", "selection"),
+        ("cursor", ":background", "accent"),
+        ("font-lock-comment-face", ":foreground", "comment"),
+        ("font-lock-keyword-face", ":foreground", "accent"),
+    ];
+    let mut previous_face = None;
+    for (face, attribute, swatch) in faces {
+        let Some(variable) = variable(swatch) else {
+            continue;
+        };
+        if previous_face != Some(face) {
+            if previous_face.is_some() {
+                writeln!(out, "))))")?;
+            }
+            write!(out, "   `({face} ((t (")?;
+            previous_face = Some(face);
+        } else {
+            write!(out, " ")?;
+        }
+        write!(out, "{attribute} ,{variable}")?;
+    }
+    if previous_face.is_some() {
+        writeln!(out, "))))")?;
+    }
+    writeln!(out, "   ))")?;
+    writeln!(out)?;
+    writeln!(out, "(provide-theme '{theme})")?;
+    writeln!(out)?;
+    writeln!(out, ";;; {theme}-theme.el ends here")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::Srgb;
+
+    fn palette() -> Vec<Swatch> {
+        let gray = |value: f64| Srgb {
+            red: value,
+            green: value,
+            blue: value,
+        };
+        vec![
+            Swatch::with_name("background", gray(0.0)),
+            Swatch::with_name("Foreground", gray(1.0)),
+            Swatch::with_name(
+                "color1",
+                Srgb {
+                    red: 1.0,
+                    green: 0.0,
+                    blue: 0.0,
+                },
+            ),
+            Swatch::with_name(
+                "Bright Blue",
+                Srgb {
+                    red: 0.4,
+                    green: 0.4,
+                    blue: 0.7,
+                },
+            ),
+            Swatch::with_name("bright blue", gray(0.5)),
+        ]
+    }
+
+    #[test]
+    fn identifiers_are_unique() {
+        assert_eq!(slug("  Deep sky-blue!  ", '-'), "deep-sky-blue");
+        assert_eq!(slug("###", '_'), "color");
+        assert_eq!(slug("1 Accent", '-'), "color-1-accent");
+        assert_eq!(slug("2", '_'), "color_2");
+        assert_eq!(
+            identifiers(&palette(), '-'),
+            [
+                "background",
+                "foreground",
+                "color1",
+                "bright-blue",
+                "bright-blue-2"
+            ]
+        );
+    }
+
+    #[test]
+    fn stylesheets() {
+        let css = export(ExportFormat::Css, "Test", &palette());
+        assert!(css.contains(":root {\n  --background: #000000;\n"));
+        assert!(css.contains("  --bright-blue-2: #808080;\n}"));
+
+        let scss = export(ExportFormat::Scss, "Test", &palette());
+        assert!(scss.contains("$bright-blue: #6666B3;\n"));
+    }
+
+    #[test]
+    fn design_tokens() {
+        let json = export(ExportFormat::DesignTokens, "My theme", &palette());
+        let tokens: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(tokens["my-theme"]["color1"]["$value"], "#FF0000");
+        assert_eq!(tokens["my-theme"]["color1"]["$type"], "color");
+        // The palette order is kept
+        assert!(json.find("foreground").unwrap() < json.find("color1").unwrap());
+    }
+
+    #[test]
+    fn gpl() {
+        let gpl = export(ExportFormat::Gpl, "Test", &palette());
+        assert!(gpl.starts_with("GIMP Palette\nName: Test\n"));
+        assert!(gpl.contains("102 102 179\tBright Blue\n"));
+    }
+
+    #[test]
+    fn terminals() {
+        let alacritty = export(ExportFormat::Alacritty, "Test", &palette());
+        assert!(alacritty
+            .contains("[colors.primary]\nbackground = \"#000000\"\nforeground = \"#FFFFFF\"\n"));
+        assert!(alacritty.contains("[colors.normal]\nred = \"#FF0000\"\n"));
+        assert!(alacritty.contains("[colors.bright]\nblue = \"#6666B3\"\n"));
+        assert!(!alacritty.contains("[colors.selection]"));
+
+        let kitty = export(ExportFormat::Kitty, "Test", &palette());
+        assert!(kitty.contains("color1               #FF0000\n"));
+        assert!(kitty.contains("color12              #6666B3\n"));
+
+        let xresources = export(ExportFormat::Xresources, "Test", &palette());
+        assert!(xresources.contains("*.background: #000000\n"));
+        assert!(xresources.contains("*.color12: #6666B3\n"));
+    }
+
+    #[test]
+    fn emacs_theme() {
+        let theme = export(ExportFormat::EmacsTheme, "My theme", &palette());
+        assert!(theme.contains("(deftheme my-theme \"My theme\")"));
+        assert!(theme.contains("(bright-blue-2 \"#808080\")"));
+        assert!(
+            theme.contains("`(default ((t (:foreground ,foreground :background ,background))))")
+        );
+        assert!(theme.contains("(provide-theme 'my-theme)"));
+    }
+
+    #[test]
+    fn hostile_names() {
+        let name = "*/ body { color: red } /*\n[evil]\r\"quoted\" \\";
+        let mut palette = palette();
+        let black = Srgb {
+            red: 0.0,
+            green: 0.0,
+            blue: 0.0,
+        };
+        palette.push(Swatch::with_name("1st\nline", black));
+
+        for format in ExportFormat::ALL {
+            let exported = export(format, name, &palette);
+            // The name never starts a line of its own
+            for line in exported.lines() {
+                assert!(!line.starts_with("[evil]"), "{format}: {exported}");
+                assert!(!line.starts_with("line"), "{format}: {exported}");
+            }
+        }
+
+        let css = export(ExportFormat::Css, name, &palette);
+        assert!(css.starts_with("/* * / body { color: red } /* [evil] \"quoted\" \\ */\n"));
+        assert!(css.contains("  --color-1st-line: #000000;\n"));
+
+        let scss = export(ExportFormat::Scss, name, &palette);
+        assert!(scss.contains("\n$color-1st-line: #000000;\n"));
+
+        let gpl = export(ExportFormat::Gpl, name, &palette);
+        assert!(gpl.contains("\t1st line\n"));
+
+        let theme = export(ExportFormat::EmacsTheme, name, &palette);
+        assert!(theme.contains(
+            r#"(deftheme body-color-red-evil-quoted "*/ body { color: red } /* [evil] \"quoted\" \\")"#
+        ));
+        assert!(theme.contains("(color-1st-line \"#000000\")"));
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Extraction of the dominant colors of an image
+//!
+//! The pixels are clustered with k-means in OkLab, where the euclidean
+//! distance matches the perceived difference, so that each cluster groups
+//! colors that look alike. The clusters are seeded with k-means++ from a
+//! fixed seed: the same image always gives the same palette.
+
+use crate::colors::{
+    batch::rgb8_to_oklab_slice,
+    gamut::{self, ClipStrategy},
+    OkLab, Srgb,
+};
+
+/// Number of pixels clustered at most, larger images are subsampled.
+pub const MAX_SAMPLES: usize = 1 << 16;
+
+/// Number of colors extracted by default.
+pub const DEFAULT_COUNT: usize = 8;
+
+/// Pixels more transparent than this are ignored.
+const MIN_ALPHA: u8 = 128;
+
+/// Rounds of k-means at most, it usually converges much sooner.
+const MAX_ITERATIONS: usize = 32;
+
+/// A color representative of part of an image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dominant {
+    pub color: Srgb,
+    /// Fraction of the (opaque) pixels closest to this color, from 0 to 1
+    pub coverage: f64,
+}
+
+/// Finds up to `count` colors representative of an interleaved RGBA8 buffer,
+/// sorted by decreasing coverage.
+///
+/// Fewer colors are returned when the image has fewer distinct colors, and
+/// none when it is empty or fully transparent.
+pub fn dominant_colors(rgba: &[u8], count: usize) -> Vec<Dominant> {
+    assert!(
+        rgba.len() % 4 == 0,
+        "{} bytes aren't 4 channel pixels",
+        rgba.len()
+    );
+
+    let stride = (rgba.len() / 4).div_ceil(MAX_SAMPLES).max(1);
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .step_by(stride)
+        .filter(|pixel| pixel[3] >= MIN_ALPHA)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    let mut samples = vec![OkLab::default(); rgb.len() / 3];
+    rgb8_to_oklab_slice(&rgb, &mut samples);
+
+    if samples.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let mut centers = seed_centers(&samples, count);
+    let mut clusters = vec![0; samples.len()];
+    for iteration in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (sample, cluster) in samples.iter().zip(&mut clusters) {
+            let closest = closest(&centers, *sample);
+            changed |= closest != *cluster;
+            *cluster = closest;
+        }
+        // The first round has to assign the samples even if all of them
+        // belong to the first center
+        if !changed && iteration > 0 {
+            break;
+        }
+
+        let mut sums = vec![(OkLab::default(), 0_usize); centers.len()];
+        for (sample, &cluster) in samples.iter().zip(&clusters) {
+            let (sum, size) = &mut sums[cluster];
+            sum.lightness += sample.lightness;
+            sum.a += sample.a;
+            sum.b += sample.b;
+            *size += 1;
+        }
+        for (center, (sum, size)) in centers.iter_mut().zip(sums) {
+            // An emptied cluster keeps its center, and may get samples back
+            if size > 0 {
+                let size = size as f64;
+                *center = OkLab {
+                    lightness: sum.lightness / size,
+                    a: sum.a / size,
+                    b: sum.b / size,
+                };
+            }
+        }
+    }
+
+    let mut sizes = vec![0_usize; centers.len()];
+    for &cluster in &clusters {
+        sizes[cluster] += 1;
+    }
+    let mut dominants: Vec<Dominant> = centers
+        .into_iter()
+        .zip(sizes)
+        .filter(|&(_, size)| size > 0)
+        .map(|(center, size)| Dominant {
+            // The mean of in gamut colors can be slightly out of it in OkLab
+            color: Srgb::from(gamut::clip(center, ClipStrategy::default())),
+            coverage: size as f64 / samples.len() as f64,
+        })
+        .collect();
+    dominants.sort_by(|first, second| second.coverage.total_cmp(&first.coverage));
+    dominants
+}
+
+fn distance_squared(first: OkLab, second: OkLab) -> f64 {
+    (first.lightness - second.lightness).powi(2)
+        + (first.a - second.a).powi(2)
+        + (first.b - second.b).powi(2)
+}
+
+/// Index of the center closest to `sample`.
+fn closest(centers: &[OkLab], sample: OkLab) -> usize {
+    centers
+        .iter()
+        .map(|center| distance_squared(*center, sample))
+        .enumerate()
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Picks up to `count` initial centers with k-means++: each new center is
+/// drawn with a probability proportional to its squared distance to the
+/// closest center already picked.
+fn seed_centers(samples: &[OkLab], count: usize) -> Vec<OkLab> {
+    let mut random = SplitMix64(0x6f6b_2d70_6963_6b65);
+    let mut centers = vec![samples[random.below(samples.len())]];
+    let mut distances: Vec<f64> = samples
+        .iter()
+        .map(|sample| distance_squared(centers[0], *sample))
+        .collect();
+
+    while centers.len() < count {
+        let total: f64 = distances.iter().sum();
+        // All the samples are already centers
+        if total <= 0.0 {
+            break;
+        }
+        let mut target = random.unit() * total;
+        let index = distances
+            .iter()
+            .position(|&distance| {
+                target -= distance;
+                target < 0.0
+            })
+            .unwrap_or_else(|| {
+                // Rounding can leave the target just above 0 at the end
+                distances
+                    .iter()
+                    .rposition(|&distance| distance > 0.0)
+                    .unwrap_or(0)
+            });
+
+        let center = samples[index];
+        centers.push(center);
+        for (distance, sample) in distances.iter_mut().zip(samples) {
+            *distance = distance.min(distance_squared(center, *sample));
+        }
+    }
+    centers
+}
+
+/// A small pseudo random generator, good enough to seed the clusters.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in [0, 1).
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// A number in [0, `bound`).
+    fn below(&mut self, bound: usize) -> usize {
+        (self.unit() * bound as f64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+
+    use super::*;
+
+    #[test]
+    fn dominant() {
+        // 50% red, 30% blue, 20% white, with slight noise, and a transparent
+        // green row that must be ignored
+        let mut rgba = Vec::new();
+        for index in 0..1000 {
+            let noise = (index % 3) as u8;
+            let pixel = match index % 10 {
+                0..=4 => [250 + noise, 0, noise, 255],
+                5..=7 => [noise, noise, 250 + noise, 255],
+                _ => [255 - noise, 255, 255 - noise, 255],
+            };
+            rgba.extend(pixel);
+        }
+        for _ in 0..500 {
+            rgba.extend([0, 255, 0, 10]);
+        }
+
+        let dominants = dominant_colors(&rgba, 3);
+        assert_eq!(dominants.len(), 3);
+        let expected = [
+            (Color32::from_rgb(251, 0, 1), 0.5),
+            (Color32::from_rgb(1, 1, 251), 0.3),
+            (Color32::from_rgb(254, 255, 254), 0.2),
+        ];
+        for (dominant, (color, coverage)) in dominants.iter().zip(expected) {
+            let found = Color32::from(dominant.color);
+            for (found, expected) in found.to_array().into_iter().zip(color.to_array()) {
+                assert!(found.abs_diff(expected) <= 1, "{dominants:?}");
+            }
+            assert!((dominant.coverage - coverage).abs() < 1e-9, "{dominants:?}");
+        }
+
+        // Asking for more colors than the image has
+        let flat: Vec<u8> = [10, 20, 30, 255].repeat(100);
+        let dominants = dominant_colors(&flat, 8);
+        assert_eq!(dominants.len(), 1);
+        assert_eq!(
+            Color32::from(dominants[0].color),
+            Color32::from_rgb(10, 20, 30)
+        );
+        assert_eq!(dominants[0].coverage, 1.0);
+
+        assert!(dominant_colors(&[], 8).is_empty());
+        assert!(dominant_colors(&[0, 0, 0, 0], 8).is_empty());
+        assert!(dominant_colors(&flat, 0).is_empty());
+    }
+}
@@ -0,0 +1,474 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Rule-based colorscheme generation
+//!
+//! A [`Scheme`] is generated from one or two seed colors, working in OkHsl so
+//! that all the colors of a group share the same perceived lightness:
+//! - the 6 chromatic ANSI colors get evenly spaced hues, anchored on the hue
+//!   of the accent (or the foreground),
+//! - the grays are tinted with the hue of the background,
+//! - every text color is moved away from the background until it reaches the
+//!   minimum contrast ratio of the [`Rules`], or toward the other end of the
+//!   lightness range when it can't.
+
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::Swatch;
+use crate::colors::{contrast::wcag_contrast_ratio, OkHsl, Srgb};
+
+/// Step used when moving lightness to reach a minimum contrast.
+const LIGHTNESS_STEP: f64 = 0.005;
+
+/// Saturation of the tinted grays (background, selection, comments…)
+const GRAY_SATURATION: f64 = 0.12;
+
+/// Hues of the chromatic ANSI colors in OkHsl, in wheel order.
+///
+/// The slots are (ANSI index, reference hue in degrees).
+const CHROMATIC_SLOTS: [(usize, f64); 6] = [
+    (1, 29.0),  // red
+    (3, 110.0), // yellow
+    (2, 142.0), // green
+    (6, 195.0), // cyan
+    (4, 264.0), // blue
+    (5, 328.0), // magenta
+];
+
+/// The colors a scheme is generated from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Seed {
+    /// Only the foreground, the background gets derived from it.
+    Foreground(Srgb),
+    /// The foreground and the background.
+    ForegroundBackground { foreground: Srgb, background: Srgb },
+    /// The foreground and an accent color, the background gets derived from
+    /// the foreground, and the hues are anchored on the accent.
+    ForegroundAccent { foreground: Srgb, accent: Srgb },
+}
+
+/// Rules to follow when generating a scheme.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Minimum WCAG contrast ratio of the foreground and the ANSI colors over
+    /// the background.
+    pub min_contrast: f64,
+    /// Minimum WCAG contrast ratio of comments over the background.
+    pub min_comment_contrast: f64,
+    /// OkHsl saturation of the chromatic ANSI colors.
+    pub saturation: f64,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            min_contrast: 4.5,
+            min_comment_contrast: 3.0,
+            saturation: 0.75,
+        }
+    }
+}
+
+/// A terminal/editor colorscheme.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scheme {
+    pub background: Srgb,
+    pub foreground: Srgb,
+    pub selection: Srgb,
+    pub comment: Srgb,
+    pub accent: Srgb,
+    /// The 16 ANSI colors, in the usual order (black, red, green, yellow,
+    /// blue, magenta, cyan, white, then their bright variants).
+    pub ansi: [Srgb; 16],
+    /// Whether all the text colors reach the minimum contrasts of the
+    /// [`Rules`]. When they can't, they get as close as possible.
+    pub meets_contrast: bool,
+}
+
+/// Names of the ANSI colors, as used in terminal configurations.
+pub const ANSI_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+impl Scheme {
+    /// Returns the scheme as a palette, with the special colors first and
+    /// the ANSI colors named `color0` to `color15`.
+    pub fn swatches(&self) -> Vec<Swatch> {
+        let mut swatches = vec![
+            Swatch::with_name("background", self.background),
+            Swatch::with_name("foreground", self.foreground),
+            Swatch::with_name("selection", self.selection),
+            Swatch::with_name("comment", self.comment),
+            Swatch::with_name("accent", self.accent),
+        ];
+        swatches.extend(
+            self.ansi
+                .iter()
+                .enumerate()
+                .map(|(index, color)| Swatch::with_name(format!("color{index}"), *color)),
+        );
+        swatches
+    }
+}
+
+/// Generates a full colorscheme from the seed colors.
+pub fn generate(seed: Seed, rules: &Rules) -> Scheme {
+    let (foreground, background, accent) = match seed {
+        Seed::Foreground(foreground) => (foreground, derive_background(foreground), None),
+        Seed::ForegroundBackground {
+            foreground,
+            background,
+        } => (foreground, background, None),
+        Seed::ForegroundAccent { foreground, accent } => {
+            (foreground, derive_background(foreground), Some(accent))
+        }
+    };
+
+    let fg = OkHsl::from(foreground);
+    let bg = OkHsl::from(background);
+    let dark = bg.lightness < fg.lightness || (bg.lightness == fg.lightness && bg.lightness < 0.5);
+
+    let (foreground, mut meets_contrast) = ensure_contrast([fg], background, rules.min_contrast);
+    let foreground = foreground[0];
+
+    let anchor_hue = accent.map_or(fg.hue, |accent| OkHsl::from(accent).hue);
+    let hues = chromatic_hues(anchor_hue);
+
+    let (normal_lightness, bright_lightness) = if dark { (0.70, 0.80) } else { (0.50, 0.40) };
+    let chromatic = |lightness: f64, saturation: f64| {
+        let colors = hues.map(|hue| OkHsl {
+            hue,
+            saturation,
+            lightness,
+        });
+        ensure_contrast(colors, background, rules.min_contrast)
+    };
+    let (normal, normal_passes) = chromatic(normal_lightness, rules.saturation);
+    let (bright, bright_passes) = chromatic(bright_lightness, (rules.saturation + 0.1).min(1.0));
+    meets_contrast &= normal_passes && bright_passes;
+
+    let gray = |lightness: f64| {
+        Srgb::from(OkHsl {
+            hue: bg.hue,
+            saturation: GRAY_SATURATION,
+            lightness,
+        })
+    };
+
+    let mut ansi = [Srgb::default(); 16];
+    ansi[0] = gray(0.20);
+    ansi[7] = gray(0.80);
+    ansi[8] = gray(0.45);
+    ansi[15] = gray(0.97);
+    for ((index, _), (normal, bright)) in CHROMATIC_SLOTS.iter().zip(normal.iter().zip(&bright)) {
+        ansi[*index] = *normal;
+        ansi[*index + 8] = *bright;
+    }
+
+    let toward_fg = if dark { 1.0 } else { -1.0 };
+    let selection = Srgb::from(OkHsl {
+        hue: anchor_hue,
+        saturation: GRAY_SATURATION * 2.0,
+        lightness: (bg.lightness + toward_fg * 0.12).clamp(0.0, 1.0),
+    });
+
+    let (comment, comment_passes) = ensure_contrast(
+        [OkHsl {
+            hue: bg.hue,
+            saturation: GRAY_SATURATION,
+            lightness: (bg.lightness + toward_fg * 0.25).clamp(0.0, 1.0),
+        }],
+        background,
+        rules.min_comment_contrast,
+    );
+    let comment = comment[0];
+    meets_contrast &= comment_passes;
+
+    let accent = match accent {
+        Some(accent) => accent,
+        None => {
+            let (accent, passes) = ensure_contrast(
+                [OkHsl {
+                    hue: anchor_hue,
+                    saturation: rules.saturation,
+                    lightness: normal_lightness,
+                }],
+                background,
+                rules.min_contrast,
+            );
+            meets_contrast &= passes;
+            accent[0]
+        }
+    };
+
+    Scheme {
+        background,
+        foreground,
+        selection,
+        comment,
+        accent,
+        ansi,
+        meets_contrast,
+    }
+}
+
+/// Derives a dark background from a light foreground, or a light one from
+/// a dark foreground.
+fn derive_background(foreground: Srgb) -> Srgb {
+    let fg = OkHsl::from(foreground);
+    Srgb::from(OkHsl {
+        hue: fg.hue,
+        saturation: GRAY_SATURATION,
+        lightness: if fg.lightness >= 0.5 { 0.15 } else { 0.96 },
+    })
+}
+
+/// Returns the hues of the chromatic slots, in the order of [`CHROMATIC_SLOTS`].
+///
+/// The hues are 60° apart, and rotated so that the slot closest to `anchor`
+/// gets exactly the anchor hue.
+fn chromatic_hues(anchor: f64) -> [f64; 6] {
+    let distance = |slot_hue: f64| {
+        let diff = (slot_hue.to_radians() - anchor).rem_euclid(2.0 * PI);
+        diff.min(2.0 * PI - diff)
+    };
+    let closest = (0..CHROMATIC_SLOTS.len())
+        .min_by(|&left, &right| {
+            distance(CHROMATIC_SLOTS[left].1).total_cmp(&distance(CHROMATIC_SLOTS[right].1))
+        })
+        .unwrap_or(0);
+
+    let mut hues = [0.0; 6];
+    for (slot, hue) in hues.iter_mut().enumerate() {
+        let steps = slot as f64 - closest as f64;
+        *hue = anchor + steps * PI / 3.0;
+    }
+    hues
+}
+
+/// Moves the lightness of all the colors by the same amount, away from the
+/// background, until they all reach the minimum contrast ratio.
+///
+/// When the colors hit black or white before reaching it, they are moved the
+/// other way instead, past the background. If neither way works, the colors
+/// with the best contrast are kept.
+///
+/// Returns the colors, and whether they reach the minimum contrast.
+fn ensure_contrast<const LEN: usize>(
+    colors: [OkHsl; LEN],
+    background: Srgb,
+    min_contrast: f64,
+) -> ([Srgb; LEN], bool) {
+    let bg_lightness = OkHsl::from(background).lightness;
+    let average = colors.iter().map(|color| color.lightness).sum::<f64>() / LEN as f64;
+    let away = if average >= bg_lightness { 1.0 } else { -1.0 };
+
+    let (first, first_contrast) = move_lightness(colors, background, min_contrast, away);
+    if first_contrast >= min_contrast {
+        return (first, true);
+    }
+    let (second, second_contrast) = move_lightness(colors, background, min_contrast, -away);
+    if second_contrast >= min_contrast {
+        (second, true)
+    } else if second_contrast > first_contrast {
+        (second, false)
+    } else {
+        (first, false)
+    }
+}
+
+/// Moves the lightness of all the colors in `direction` until they all
+/// reach the minimum contrast ratio, or they all hit black or white.
+///
+/// Returns the colors, and the lowest contrast ratio among them.
+fn move_lightness<const LEN: usize>(
+    mut colors: [OkHsl; LEN],
+    background: Srgb,
+    min_contrast: f64,
+    direction: f64,
+) -> ([Srgb; LEN], f64) {
+    loop {
+        let rgb = colors.map(Srgb::from);
+        let contrast = rgb
+            .iter()
+            .map(|color| wcag_contrast_ratio(*color, background))
+            .fold(f64::INFINITY, f64::min);
+        let saturated = colors
+            .iter()
+            .all(|color| color.lightness <= 0.0 || color.lightness >= 1.0);
+        if contrast >= min_contrast || saturated {
+            return (rgb, contrast);
+        }
+
+        for color in colors.iter_mut() {
+            color.lightness = (color.lightness + direction * LIGHTNESS_STEP).clamp(0.0, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeds() -> Vec<Seed> {
+        let light = Srgb {
+            red: 0.85,
+            green: 0.85,
+            blue: 0.8,
+        };
+        let dark = Srgb {
+            red: 0.15,
+            green: 0.1,
+            blue: 0.2,
+        };
+        let accent = Srgb {
+            red: 0.4,
+            green: 0.4,
+            blue: 0.7,
+        };
+        vec![
+            Seed::Foreground(light),
+            Seed::Foreground(dark),
+            Seed::ForegroundBackground {
+                foreground: light,
+                background: dark,
+            },
+            Seed::ForegroundBackground {
+                foreground: dark,
+                background: light,
+            },
+            Seed::ForegroundAccent {
+                foreground: light,
+                accent,
+            },
+        ]
+    }
+
+    #[test]
+    fn minimum_contrast() {
+        let rules = Rules::default();
+        for seed in seeds() {
+            let scheme = generate(seed, &rules);
+            let text_colors = scheme.ansi[1..7]
+                .iter()
+                .chain(scheme.ansi[9..15].iter())
+                .chain(std::iter::once(&scheme.foreground));
+            for color in text_colors {
+                let ratio = wcag_contrast_ratio(*color, scheme.background);
+                assert!(
+                    ratio >= rules.min_contrast,
+                    "{color:?} only has a contrast of {ratio} in {seed:?}"
+                );
+            }
+            let ratio = wcag_contrast_ratio(scheme.comment, scheme.background);
+            assert!(ratio >= rules.min_comment_contrast);
+            assert!(scheme.meets_contrast);
+        }
+    }
+
+    #[test]
+    fn contrast_directions() {
+        let gray = |lightness: f64| OkHsl {
+            hue: 0.0,
+            saturation: 0.0,
+            lightness,
+        };
+
+        // Over a light-ish gray, 7:1 is only reachable by going darker, even
+        // if the text starts lighter than the background
+        let background = Srgb::from(gray(0.65));
+        let (colors, passes) = ensure_contrast([gray(0.8)], background, 7.0);
+        assert!(passes);
+        assert!(wcag_contrast_ratio(colors[0], background) >= 7.0);
+        assert!(OkHsl::from(colors[0]).lightness < 0.65);
+
+        // Neither black nor white reach 7:1 over a mid gray
+        let background = Srgb::from(gray(0.5));
+        let (colors, passes) = ensure_contrast([gray(0.6)], background, 7.0);
+        assert!(!passes);
+        let best = [0.0, 1.0]
+            .map(|lightness| wcag_contrast_ratio(Srgb::from(gray(lightness)), background))
+            .into_iter()
+            .fold(0.0, f64::max);
+        assert!((wcag_contrast_ratio(colors[0], background) - best).abs() < 1e-9);
+
+        let scheme = generate(
+            Seed::ForegroundBackground {
+                foreground: Srgb::from(gray(0.6)),
+                background,
+            },
+            &Rules {
+                min_contrast: 7.0,
+                ..Rules::default()
+            },
+        );
+        assert!(!scheme.meets_contrast);
+    }
+
+    #[test]
+    fn consistent_lightness_and_hues() {
+        let scheme = generate(seeds()[0], &Rules::default());
+        let normal: Vec<OkHsl> = CHROMATIC_SLOTS
+            .iter()
+            .map(|&(index, _)| OkHsl::from(scheme.ansi[index]))
+            .collect();
+
+        for pair in normal.windows(2) {
+            assert!(
+                (pair[0].lightness - pair[1].lightness).abs() < 0.01,
+                "{pair:?} don't have the same lightness"
+            );
+            let step = (pair[1].hue - pair[0].hue).rem_euclid(2.0 * PI);
+            assert!(
+                (step - PI / 3.0).abs() < 0.01,
+                "{pair:?} aren't evenly spaced"
+            );
+        }
+    }
+
+    #[test]
+    fn accent_anchors_the_hues() {
+        let accent = Srgb {
+            red: 0.4,
+            green: 0.4,
+            blue: 0.7,
+        };
+        let scheme = generate(
+            Seed::ForegroundAccent {
+                foreground: Srgb {
+                    red: 0.9,
+                    green: 0.9,
+                    blue: 0.9,
+                },
+                accent,
+            },
+            &Rules::default(),
+        );
+        assert_eq!(scheme.accent, accent);
+
+        // The accent is blue-ish, so it should give its hue to the blue slot
+        let blue = OkHsl::from(scheme.ansi[4]);
+        let diff = (blue.hue - OkHsl::from(accent).hue).rem_euclid(2.0 * PI);
+        assert!(diff.min(2.0 * PI - diff) < 0.01);
+        assert_eq!(scheme.swatches().len(), 21);
+    }
+}
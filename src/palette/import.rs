@@ -0,0 +1,750 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Importing palettes from GIMP, Adobe and JSON files
+//!
+//! The accepted JSON documents are:
+//! - arrays of colors, where each color is either a string in any notation
+//!   supported by [`crate::colors::parse`], or an object with an optional
+//!   `name` and a `color` (a string, or the `red`/`green`/`blue` object the
+//!   app saves),
+//! - objects mapping names to color strings, possibly nested in groups, which
+//!   also covers the design tokens [`super::export`] writes.
+//!
+//! Invalid files give an [`ImportError`] that points at the line (text
+//! formats) or byte offset (binary formats) of the problem.
+
+use std::{fmt, path::Path};
+
+use serde::{
+    de::{self, value::MapAccessDeserializer, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess},
+    Deserialize, Deserializer,
+};
+
+use super::Swatch;
+use crate::colors::Srgb;
+
+/// A file format palettes can be imported from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImportFormat {
+    /// GIMP palette
+    Gpl,
+    /// Adobe Swatch Exchange
+    Ase,
+    Json,
+}
+
+impl ImportFormat {
+    pub const ALL: [Self; 3] = [Self::Gpl, Self::Ase, Self::Json];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gpl => "gpl",
+            Self::Ase => "ase",
+            Self::Json => "json",
+        }
+    }
+
+    /// Finds a format from its name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Guesses the format from the extension of the file, or else from its
+    /// first bytes.
+    pub fn detect(path: Option<&Path>, bytes: &[u8]) -> Option<Self> {
+        let from_extension = path
+            .and_then(Path::extension)
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_name);
+        from_extension.or_else(|| {
+            let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+            if bytes.starts_with(b"ASEF") {
+                Some(Self::Ase)
+            } else if text.starts_with(b"GIMP Palette") {
+                Some(Self::Gpl)
+            } else if text
+                .iter()
+                .find(|byte| !byte.is_ascii_whitespace())
+                .is_some_and(|byte| matches!(byte, b'[' | b'{'))
+            {
+                Some(Self::Json)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A palette read from a file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportedPalette {
+    /// The name of the palette, if the file has one
+    pub name: Option<String>,
+    pub swatches: Vec<Swatch>,
+}
+
+/// Where an [`ImportError`] happened in the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// 1-based line, and column if known, in text formats
+    Line { line: usize, column: Option<usize> },
+    /// Byte offset in binary formats
+    Offset(usize),
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Line { line, column: None } => write!(f, "line {line}"),
+            Self::Line {
+                line,
+                column: Some(column),
+            } => write!(f, "line {line}, column {column}"),
+            Self::Offset(offset) => write!(f, "byte {offset}"),
+        }
+    }
+}
+
+/// The kinds of errors encountered while importing a palette.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportErrorKind {
+    /// The format could not be guessed from the file
+    UnknownFormat,
+    /// The text is not valid UTF-8
+    InvalidUtf8,
+    /// The file doesn't start with the signature of the format
+    MissingHeader,
+    /// A color has invalid or missing components
+    InvalidColor(String),
+    /// The file ends in the middle of a block
+    UnexpectedEnd,
+    /// An ASE block has an unknown type or an inconsistent length
+    InvalidBlock,
+    /// An ASE color uses a model other than RGB, CMYK or Gray
+    UnsupportedColorModel(String),
+    /// The JSON document is invalid, or isn't a palette
+    Json(String),
+}
+
+impl fmt::Display for ImportErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => f.write_str("unknown palette format"),
+            Self::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            Self::MissingHeader => f.write_str("missing file header"),
+            Self::InvalidColor(reason) => write!(f, "invalid color: {reason}"),
+            Self::UnexpectedEnd => f.write_str("unexpected end of file"),
+            Self::InvalidBlock => f.write_str("invalid block"),
+            Self::UnsupportedColorModel(model) => {
+                write!(f, "unsupported color model {model:?}")
+            }
+            Self::Json(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Error returned when a palette cannot be imported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportError {
+    pub kind: ImportErrorKind,
+    pub position: Position,
+}
+
+impl ImportError {
+    fn at_line(kind: ImportErrorKind, line: usize) -> Self {
+        Self {
+            kind,
+            position: Position::Line { line, column: None },
+        }
+    }
+
+    fn at_offset(kind: ImportErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            position: Position::Offset(offset),
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at {})", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Reads a palette in the given format.
+pub fn import(format: ImportFormat, bytes: &[u8]) -> Result<ImportedPalette, ImportError> {
+    match format {
+        ImportFormat::Gpl => gpl(text(bytes)?),
+        ImportFormat::Ase => ase(bytes),
+        ImportFormat::Json => json(text(bytes)?),
+    }
+}
+
+/// Reads a palette, guessing its format with [`ImportFormat::detect`].
+pub fn import_detect(path: Option<&Path>, bytes: &[u8]) -> Result<ImportedPalette, ImportError> {
+    let format = ImportFormat::detect(path, bytes)
+        .ok_or_else(|| ImportError::at_offset(ImportErrorKind::UnknownFormat, 0))?;
+    import(format, bytes)
+}
+
+fn text(bytes: &[u8]) -> Result<&str, ImportError> {
+    let text = std::str::from_utf8(bytes).map_err(|err| {
+        let line = 1 + bytes[..err.valid_up_to()]
+            .iter()
+            .filter(|&&byte| byte == b'\n')
+            .count();
+        ImportError::at_line(ImportErrorKind::InvalidUtf8, line)
+    })?;
+    Ok(text.strip_prefix('\u{FEFF}').unwrap_or(text))
+}
+
+fn gpl(text: &str) -> Result<ImportedPalette, ImportError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+
+    match lines.next() {
+        Some((_, header)) if header.trim_end() == "GIMP Palette" => {}
+        _ => return Err(ImportError::at_line(ImportErrorKind::MissingHeader, 1)),
+    }
+
+    let mut palette = ImportedPalette::default();
+    for (number, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("Name:") {
+            palette.name = Some(name.trim().to_owned());
+            continue;
+        }
+        if line.starts_with("Columns:") {
+            continue;
+        }
+
+        let invalid =
+            |reason: String| ImportError::at_line(ImportErrorKind::InvalidColor(reason), number);
+        let mut rest = line;
+        let mut channels = [0.0; 3];
+        for channel in &mut channels {
+            let (value, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let value: u8 = value
+                .parse()
+                .map_err(|_| invalid(format!("{value:?} is not a 0-255 channel")))?;
+            *channel = f64::from(value) / 255.0;
+            rest = tail.trim_start();
+        }
+
+        let [red, green, blue] = channels;
+        let color = Srgb { red, green, blue };
+        palette.swatches.push(if rest.is_empty() {
+            Swatch::new(color)
+        } else {
+            Swatch::with_name(rest, color)
+        });
+    }
+    Ok(palette)
+}
+
+/// Big endian reader over the bytes of an ASE file, keeping track of the
+/// offset for errors.
+struct AseReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> AseReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ImportError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                ImportError::at_offset(ImportErrorKind::UnexpectedEnd, self.bytes.len())
+            })?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, ImportError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ImportError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, ImportError> {
+        self.u32().map(f32::from_bits)
+    }
+
+    /// A name, as a length in UTF-16 code units (including the terminating
+    /// null) followed by the UTF-16 code units.
+    fn name(&mut self) -> Result<String, ImportError> {
+        let start = self.offset;
+        let len = self.u16()?;
+        let units = (0..len)
+            .map(|_| self.u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        let units = units.strip_suffix(&[0]).unwrap_or(&units);
+        String::from_utf16(units)
+            .map_err(|_| ImportError::at_offset(ImportErrorKind::InvalidBlock, start))
+    }
+}
+
+fn ase(bytes: &[u8]) -> Result<ImportedPalette, ImportError> {
+    const GROUP_START: u16 = 0xC001;
+    const GROUP_END: u16 = 0xC002;
+    const COLOR: u16 = 0x0001;
+
+    let mut reader = AseReader { bytes, offset: 0 };
+    if reader.take(4).ok() != Some(b"ASEF".as_slice()) {
+        return Err(ImportError::at_offset(ImportErrorKind::MissingHeader, 0));
+    }
+    let _version = (reader.u16()?, reader.u16()?);
+    let block_count = reader.u32()?;
+
+    let mut palette = ImportedPalette::default();
+    for _ in 0..block_count {
+        let block_start = reader.offset;
+        let block_type = reader.u16()?;
+        let block_len = reader.u32()? as usize;
+        let content_start = reader.offset;
+
+        match block_type {
+            GROUP_START => {
+                let name = reader.name()?;
+                // Palettes exported from a single group are named after it
+                palette.name.get_or_insert(name);
+            }
+            GROUP_END => {}
+            COLOR => {
+                let name = reader.name()?;
+                let model_offset = reader.offset;
+                let model = reader.take(4)?;
+                let color = match model {
+                    b"RGB " => Srgb {
+                        red: f64::from(reader.f32()?),
+                        green: f64::from(reader.f32()?),
+                        blue: f64::from(reader.f32()?),
+                    },
+                    b"Gray" => {
+                        let gray = f64::from(reader.f32()?);
+                        Srgb {
+                            red: gray,
+                            green: gray,
+                            blue: gray,
+                        }
+                    }
+                    b"CMYK" => {
+                        let [c, m, y, k] =
+                            [reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?]
+                                .map(f64::from);
+                        Srgb {
+                            red: (1.0 - c) * (1.0 - k),
+                            green: (1.0 - m) * (1.0 - k),
+                            blue: (1.0 - y) * (1.0 - k),
+                        }
+                    }
+                    other => {
+                        return Err(ImportError::at_offset(
+                            ImportErrorKind::UnsupportedColorModel(
+                                String::from_utf8_lossy(other).trim().to_owned(),
+                            ),
+                            model_offset,
+                        ))
+                    }
+                };
+                // Global, spot or normal color, which doesn't matter here
+                let _color_type = reader.u16()?;
+
+                if !color.red.is_finite() || !color.green.is_finite() || !color.blue.is_finite() {
+                    return Err(ImportError::at_offset(
+                        ImportErrorKind::InvalidColor("non-finite component".to_owned()),
+                        model_offset,
+                    ));
+                }
+                let color = Srgb {
+                    red: color.red.clamp(0.0, 1.0),
+                    green: color.green.clamp(0.0, 1.0),
+                    blue: color.blue.clamp(0.0, 1.0),
+                };
+                palette.swatches.push(if name.is_empty() {
+                    Swatch::new(color)
+                } else {
+                    Swatch::with_name(name, color)
+                });
+            }
+            _ => {
+                return Err(ImportError::at_offset(
+                    ImportErrorKind::InvalidBlock,
+                    block_start,
+                ))
+            }
+        }
+
+        // Skip whatever the block has left, and reject blocks that overflow
+        let read = reader.offset - content_start;
+        if read > block_len {
+            return Err(ImportError::at_offset(
+                ImportErrorKind::InvalidBlock,
+                block_start,
+            ));
+        }
+        reader.take(block_len - read)?;
+    }
+    Ok(palette)
+}
+
+fn json(text: &str) -> Result<ImportedPalette, ImportError> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let swatches = JsonPalette::deserialize(&mut deserializer)
+        .and_then(|palette| deserializer.end().map(|()| palette.0))
+        .map_err(|err| {
+            let message = err.to_string();
+            // serde_json appends the position, which we keep separately
+            let message = match message.rsplit_once(" at line ") {
+                Some((message, _)) => message.to_owned(),
+                None => message,
+            };
+            ImportError {
+                kind: ImportErrorKind::Json(message),
+                position: Position::Line {
+                    line: err.line(),
+                    column: Some(err.column()),
+                },
+            }
+        })?;
+    Ok(ImportedPalette {
+        name: None,
+        swatches,
+    })
+}
+
+/// A JSON palette, either an array of swatches or a map of names to colors.
+struct JsonPalette(Vec<Swatch>);
+
+impl<'de> Deserialize<'de> for JsonPalette {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PaletteVisitor;
+
+        impl<'de> de::Visitor<'de> for PaletteVisitor {
+            type Value = JsonPalette;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array of colors or an object of named colors")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut swatches = Vec::new();
+                while let Some(JsonSwatch(swatch)) = seq.next_element()? {
+                    swatches.push(swatch);
+                }
+                Ok(JsonPalette(swatches))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let mut swatches = Vec::new();
+                GroupSeed {
+                    name: None,
+                    swatches: &mut swatches,
+                }
+                .visit_map(map)?;
+                Ok(JsonPalette(swatches))
+            }
+        }
+
+        deserializer.deserialize_any(PaletteVisitor)
+    }
+}
+
+/// A color as a string in any notation, or as the object the app saves.
+struct JsonColor(Srgb);
+
+impl<'de> Deserialize<'de> for JsonColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+
+        impl<'de> de::Visitor<'de> for ColorVisitor {
+            type Value = JsonColor;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color string or a red/green/blue object")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value
+                    .parse()
+                    .map(JsonColor)
+                    .map_err(|err| E::custom(format!("invalid color {value:?}: {err}")))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                Srgb::deserialize(MapAccessDeserializer::new(map)).map(JsonColor)
+            }
+        }
+
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+/// An element of a JSON array palette.
+struct JsonSwatch(Swatch);
+
+impl<'de> Deserialize<'de> for JsonSwatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: Option<String>,
+            color: JsonColor,
+        }
+
+        struct SwatchVisitor;
+
+        impl<'de> de::Visitor<'de> for SwatchVisitor {
+            type Value = JsonSwatch;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color string or an object with a name and a color")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                let JsonColor(color) =
+                    JsonColor::deserialize(de::value::StrDeserializer::new(value))?;
+                Ok(JsonSwatch(Swatch::new(color)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let Named { name, color } = Named::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(JsonSwatch(match name {
+                    Some(name) => Swatch::with_name(name, color.0),
+                    None => Swatch::new(color.0),
+                }))
+            }
+        }
+
+        deserializer.deserialize_any(SwatchVisitor)
+    }
+}
+
+/// A value in a map of named colors: a color string, a design token (an
+/// object with a `$value`), or a group of those.
+struct GroupSeed<'a> {
+    /// Name of the entry in its parent map
+    name: Option<String>,
+    swatches: &'a mut Vec<Swatch>,
+}
+
+impl<'de> DeserializeSeed<'de> for GroupSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for GroupSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a color string, a design token or a group of colors")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<(), E> {
+        let JsonColor(color) = JsonColor::deserialize(de::value::StrDeserializer::new(value))?;
+        self.swatches.push(match self.name {
+            Some(name) => Swatch::with_name(name, color),
+            None => Swatch::new(color),
+        });
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut token_value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "$value" {
+                token_value = Some(map.next_value::<JsonColor>()?.0);
+            } else if key.starts_with('$') {
+                // $type, $description and other token properties
+                map.next_value::<IgnoredAny>()?;
+            } else {
+                map.next_value_seed(GroupSeed {
+                    name: Some(key),
+                    swatches: &mut *self.swatches,
+                })?;
+            }
+        }
+        if let Some(color) = token_value {
+            self.swatches.push(match self.name {
+                Some(name) => Swatch::with_name(name, color),
+                None => Swatch::new(color),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::export::{export, ExportFormat};
+
+    fn rgb(red: f64, green: f64, blue: f64) -> Srgb {
+        Srgb { red, green, blue }
+    }
+
+    fn palette() -> Vec<Swatch> {
+        vec![
+            Swatch::with_name("Red", rgb(1.0, 0.0, 0.0)),
+            Swatch::with_name("Deep blue", rgb(0.0, 0.0, 0.2)),
+        ]
+    }
+
+    #[test]
+    fn gpl() {
+        let text = export(ExportFormat::Gpl, "Brand", &palette());
+        let imported = import(ImportFormat::Gpl, text.as_bytes()).unwrap();
+        assert_eq!(imported.name.as_deref(), Some("Brand"));
+        assert_eq!(imported.swatches, palette());
+
+        let imported = import(ImportFormat::Gpl, b"GIMP Palette\n# comment\n\n0 255 0\n").unwrap();
+        assert_eq!(imported.swatches[0].name, "#00FF00");
+
+        let err = import(ImportFormat::Gpl, b"Not a palette\n").unwrap_err();
+        assert_eq!(err.kind, ImportErrorKind::MissingHeader);
+
+        let err = import(
+            ImportFormat::Gpl,
+            b"GIMP Palette\nName: x\n0 0 0 Black\n0 256 0\n",
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind, ImportErrorKind::InvalidColor(_)));
+        assert_eq!(
+            err.position,
+            Position::Line {
+                line: 4,
+                column: None
+            }
+        );
+    }
+
+    /// Builds an ASE file with a group and the given color blocks.
+    fn ase_file(colors: &[(&str, &[u8; 4], &[f32])]) -> Vec<u8> {
+        fn name(name: &str) -> Vec<u8> {
+            let units: Vec<u16> = name.encode_utf16().chain([0]).collect();
+            let mut bytes = (units.len() as u16).to_be_bytes().to_vec();
+            bytes.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+            bytes
+        }
+
+        fn block(block_type: u16, content: Vec<u8>) -> Vec<u8> {
+            let mut bytes = block_type.to_be_bytes().to_vec();
+            bytes.extend((content.len() as u32).to_be_bytes());
+            bytes.extend(content);
+            bytes
+        }
+
+        let mut bytes = b"ASEF".to_vec();
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend(0u16.to_be_bytes());
+        bytes.extend((colors.len() as u32 + 2).to_be_bytes());
+        bytes.extend(block(0xC001, name("Brand")));
+        for (color_name, model, values) in colors {
+            let mut content = name(color_name);
+            content.extend(model.iter());
+            content.extend(values.iter().flat_map(|value| value.to_be_bytes()));
+            content.extend(2u16.to_be_bytes());
+            bytes.extend(block(0x0001, content));
+        }
+        bytes.extend(block(0xC002, Vec::new()));
+        bytes
+    }
+
+    #[test]
+    fn ase() {
+        let bytes = ase_file(&[
+            ("Red", b"RGB ", &[1.0, 0.0, 0.0]),
+            ("Gray", b"Gray", &[0.5]),
+            ("Cyan", b"CMYK", &[1.0, 0.0, 0.0, 0.0]),
+        ]);
+        assert_eq!(ImportFormat::detect(None, &bytes), Some(ImportFormat::Ase));
+
+        let imported = import(ImportFormat::Ase, &bytes).unwrap();
+        assert_eq!(imported.name.as_deref(), Some("Brand"));
+        assert_eq!(
+            imported.swatches,
+            [
+                Swatch::with_name("Red", rgb(1.0, 0.0, 0.0)),
+                Swatch::with_name("Gray", rgb(0.5, 0.5, 0.5)),
+                Swatch::with_name("Cyan", rgb(0.0, 1.0, 1.0)),
+            ]
+        );
+
+        let err = import(ImportFormat::Ase, &bytes[..bytes.len() - 10]).unwrap_err();
+        assert_eq!(err.kind, ImportErrorKind::UnexpectedEnd);
+
+        let bytes = ase_file(&[("Lab", b"LAB ", &[0.5, 0.0, 0.0])]);
+        let err = import(ImportFormat::Ase, &bytes).unwrap_err();
+        assert_eq!(
+            err.kind,
+            ImportErrorKind::UnsupportedColorModel("LAB".to_owned())
+        );
+        // After the header, the group block and the color block header and name
+        assert_eq!(err.position, Position::Offset(12 + 20 + 6 + 10));
+    }
+
+    #[test]
+    fn json() {
+        let saved = serde_json::to_string(&palette()).unwrap();
+        let imported = import(ImportFormat::Json, saved.as_bytes()).unwrap();
+        assert_eq!(imported.swatches, palette());
+
+        // Design tokens are named after slugs of the swatch names
+        let tokens = export(ExportFormat::DesignTokens, "Brand", &palette());
+        let imported = import(ImportFormat::Json, tokens.as_bytes()).unwrap();
+        assert_eq!(
+            imported.swatches,
+            [
+                Swatch::with_name("red", rgb(1.0, 0.0, 0.0)),
+                Swatch::with_name("deep-blue", rgb(0.0, 0.0, 0.2)),
+            ]
+        );
+
+        let imported = import(
+            ImportFormat::Json,
+            br##"{"Red": "#f00", "Deep blue": "rgb(0 0 51)"}"##,
+        )
+        .unwrap();
+        assert_eq!(imported.swatches, palette());
+
+        let imported = import(ImportFormat::Json, br##"["#f00"]"##).unwrap();
+        assert_eq!(imported.swatches, [Swatch::new(rgb(1.0, 0.0, 0.0))]);
+
+        let err = import(ImportFormat::Json, b"[\n  \"#f00\",\n  \"#ff\"\n]").unwrap_err();
+        assert!(matches!(err.kind, ImportErrorKind::Json(_)), "{err}");
+        assert!(
+            matches!(err.position, Position::Line { line: 3, .. }),
+            "{err}"
+        );
+
+        let err = import(ImportFormat::Json, b"{\"a\": 12}").unwrap_err();
+        assert!(matches!(err.kind, ImportErrorKind::Json(_)), "{err}");
+    }
+}
@@ -0,0 +1,148 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Palettes of named colors
+
+pub mod export;
+pub mod extract;
+pub mod generate;
+pub mod import;
+
+use serde::{Deserialize, Serialize};
+
+use crate::colors::{cvd::Simulation, difference::delta_e_ok, format::Notation, OkLab, Srgb};
+
+/// ΔE_OK under which two colors of a palette are hard to tell apart, a bit
+/// over the just noticeable difference.
+pub const DEFAULT_CONFUSION_THRESHOLD: f64 = 0.03;
+
+/// A named color in a palette.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Swatch {
+    pub name: String,
+    pub color: Srgb,
+}
+
+impl Swatch {
+    /// Creates a swatch named after the hex notation of the color.
+    pub fn new(color: Srgb) -> Self {
+        Self {
+            name: Notation::Hex.format(color),
+            color,
+        }
+    }
+
+    pub fn with_name(name: impl Into<String>, color: Srgb) -> Self {
+        Self {
+            name: name.into(),
+            color,
+        }
+    }
+}
+
+/// Finds the swatch of `palette` that looks the closest to `color`.
+///
+/// Returns its index, and its ΔE_OK from `color`, or `None` if the palette is
+/// empty.
+pub fn nearest(palette: &[Swatch], color: Srgb) -> Option<(usize, f64)> {
+    palette
+        .iter()
+        .map(|swatch| delta_e_ok(swatch.color.into(), color.into()))
+        .enumerate()
+        .min_by(|(_, first), (_, second)| first.total_cmp(second))
+}
+
+/// Finds the pairs of swatches that become hard to tell apart with a color
+/// vision deficiency: those at least `threshold` apart in ΔE_OK, but closer
+/// than that once simulated.
+///
+/// Returns the indices of each pair, and their simulated ΔE_OK.
+pub fn confusable_pairs(
+    palette: &[Swatch],
+    simulation: &Simulation,
+    threshold: f64,
+) -> Vec<(usize, usize, f64)> {
+    let colors: Vec<(OkLab, OkLab)> = palette
+        .iter()
+        .map(|swatch| {
+            (
+                swatch.color.into(),
+                simulation.simulate_srgb(swatch.color).into(),
+            )
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (first, (color, simulated)) in colors.iter().enumerate() {
+        for (second, (other, other_simulated)) in colors.iter().enumerate().skip(first + 1) {
+            let delta = delta_e_ok(*simulated, *other_simulated);
+            if delta < threshold && delta_e_ok(*color, *other) >= threshold {
+                pairs.push((first, second, delta));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_swatch() {
+        assert_eq!(nearest(&[], Srgb::default()), None);
+
+        let palette = [
+            Swatch::with_name(
+                "red",
+                Srgb {
+                    red: 1.0,
+                    green: 0.0,
+                    blue: 0.0,
+                },
+            ),
+            Swatch::with_name(
+                "blue",
+                Srgb {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 1.0,
+                },
+            ),
+        ];
+        let purple = Srgb {
+            red: 0.3,
+            green: 0.1,
+            blue: 0.9,
+        };
+        let (index, delta) = nearest(&palette, purple).unwrap();
+        assert_eq!(palette[index].name, "blue");
+        assert!(delta > 0.0);
+
+        let (index, delta) = nearest(&palette, palette[0].color).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(delta, 0.0);
+    }
+
+    #[test]
+    fn confusable_swatches() {
+        let swatch =
+            |name: &str, red, green, blue| Swatch::with_name(name, Srgb { red, green, blue });
+        let palette = [
+            swatch("red", 0.8, 0.2, 0.2),
+            swatch("olive", 0.55, 0.5, 0.15),
+            swatch("blue", 0.1, 0.2, 0.9),
+            // Already indistinguishable without any deficiency
+            swatch("blue too", 0.1, 0.2, 0.9),
+        ];
+
+        let deutan = Simulation::default();
+        let pairs = confusable_pairs(&palette, &deutan, DEFAULT_CONFUSION_THRESHOLD);
+        assert_eq!(pairs.len(), 1, "{pairs:?}");
+        assert_eq!((pairs[0].0, pairs[0].1), (0, 1));
+        assert!(pairs[0].2 < DEFAULT_CONFUSION_THRESHOLD);
+
+        assert!(confusable_pairs(&palette, &deutan, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Numeric fields for the components of the OkHSV and OkHSL pickers
+
+use std::f64::consts::TAU;
+
+use eframe::{
+    egui::{vec2, DragValue, Id, Layout, Slider, Ui},
+    emath::Align,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::colors::{OkHsl, OkHsv};
+
+/// Unit in which hues are typed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HueUnit {
+    #[default]
+    Degrees,
+    Turns,
+    Radians,
+}
+
+impl HueUnit {
+    pub const ALL: [Self; 3] = [Self::Degrees, Self::Turns, Self::Radians];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Degrees => "Degrees",
+            Self::Turns => "Turns",
+            Self::Radians => "Radians",
+        }
+    }
+
+    /// Length of a full turn in the unit.
+    pub fn full_turn(self) -> f64 {
+        match self {
+            Self::Degrees => 360.0,
+            Self::Turns => 1.0,
+            Self::Radians => TAU,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Degrees => "°",
+            Self::Turns => " turn",
+            Self::Radians => " rad",
+        }
+    }
+
+    /// Converts a hue in radians to the unit, from 0 to a full turn.
+    pub fn from_radians(self, hue: f64) -> f64 {
+        hue.rem_euclid(TAU) / TAU * self.full_turn()
+    }
+
+    /// Converts a hue in the unit to radians, in (-π, π] like the pickers.
+    pub fn to_radians(self, hue: f64) -> f64 {
+        let hue = (hue / self.full_turn()).rem_euclid(1.0) * TAU;
+        if hue > TAU / 2.0 {
+            hue - TAU
+        } else {
+            hue
+        }
+    }
+}
+
+/// How the component fields show their values, shared by all the pickers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldFormat {
+    pub hue_unit: HueUnit,
+    /// Number of decimals
+    pub precision: usize,
+}
+
+impl Default for FieldFormat {
+    fn default() -> Self {
+        Self {
+            hue_unit: HueUnit::default(),
+            precision: 3,
+        }
+    }
+}
+
+impl FieldFormat {
+    /// Largest number of decimals offered, past the precision of the pickers.
+    pub const MAX_PRECISION: usize = 8;
+
+    fn id() -> Id {
+        Id::new("component_field_format")
+    }
+
+    /// Loads the format from egui memory, where it is saved with the app.
+    pub fn load(ui: &Ui) -> Self {
+        ui.data().get_persisted(Self::id()).unwrap_or_default()
+    }
+
+    pub fn store(self, ui: &Ui) {
+        ui.data().insert_persisted(Self::id(), self);
+    }
+}
+
+/// Shows editable fields for the hue, saturation and value of `hsv`.
+///
+/// Returns `true` on change.
+pub fn okhsv_fields_ui(ui: &mut Ui, hsv: &mut OkHsv) -> bool {
+    let OkHsv {
+        hue,
+        saturation,
+        value,
+    } = hsv;
+    fields_ui(
+        ui,
+        hue,
+        [("S", saturation), ("V", value)],
+        "Hue Saturation Value, OkHSV",
+    )
+}
+
+/// Shows editable fields for the hue, saturation and lightness of `hsl`.
+///
+/// Returns `true` on change.
+pub fn okhsl_fields_ui(ui: &mut Ui, hsl: &mut OkHsl) -> bool {
+    let OkHsl {
+        hue,
+        saturation,
+        lightness,
+    } = hsl;
+    fields_ui(
+        ui,
+        hue,
+        [("S", saturation), ("L", lightness)],
+        "Hue Saturation Lightness, OkHSL",
+    )
+}
+
+/// Shows a field for `hue`, in radians, and fields for two other components
+/// from 0 to 1.
+fn fields_ui(ui: &mut Ui, hue: &mut f64, others: [(&str, &mut f64); 2], hover: &str) -> bool {
+    let mut format = FieldFormat::load(ui);
+    let unit = format.hue_unit;
+    let mut changed = false;
+
+    ui.allocate_ui_with_layout(
+        vec2(
+            2.0 * ui.spacing().slider_width,
+            ui.spacing().interact_size.y,
+        ),
+        Layout::left_to_right(Align::Center),
+        |ui| {
+            let mut shown_hue = unit.from_radians(*hue);
+            let [(first, first_value), (second, second_value)] = others;
+            let precision = format.precision;
+
+            if ui.button("📋").on_hover_text("Click to copy").clicked() {
+                ui.output().copied_text = format!(
+                    "{shown_hue:.precision$}{}, {first_value:.precision$}, {second_value:.precision$}",
+                    unit.suffix().trim()
+                );
+            }
+
+            let hue_field = ui
+                .add(
+                    DragValue::new(&mut shown_hue)
+                        .prefix("H ")
+                        .suffix(unit.suffix())
+                        .clamp_range(0.0..=unit.full_turn())
+                        .speed(unit.full_turn() / 360.0)
+                        .fixed_decimals(precision),
+                )
+                .on_hover_text(hover);
+            if hue_field.changed() {
+                *hue = unit.to_radians(shown_hue);
+                changed = true;
+            }
+
+            for (label, value) in [(first, first_value), (second, second_value)] {
+                changed |= ui
+                    .add(
+                        DragValue::new(value)
+                            .prefix(format!("{label} "))
+                            .clamp_range(0.0..=1.0)
+                            .speed(0.005)
+                            .fixed_decimals(precision),
+                    )
+                    .on_hover_text(hover)
+                    .changed();
+            }
+
+            ui.menu_button("⚙", |ui| {
+                ui.label("Hue unit");
+                for unit in HueUnit::ALL {
+                    ui.radio_value(&mut format.hue_unit, unit, unit.name());
+                }
+                ui.add(
+                    Slider::new(&mut format.precision, 0..=FieldFormat::MAX_PRECISION)
+                        .text("Decimals"),
+                );
+            })
+            .response
+            .on_hover_text("Format of the fields");
+        },
+    );
+
+    format.store(ui);
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn hue_units() {
+        assert!((HueUnit::Degrees.from_radians(-PI / 2.0) - 270.0).abs() < 1e-9);
+        assert!((HueUnit::Turns.from_radians(PI / 2.0) - 0.25).abs() < 1e-9);
+        assert!((HueUnit::Radians.from_radians(-PI / 2.0) - 1.5 * PI).abs() < 1e-9);
+
+        assert!((HueUnit::Degrees.to_radians(270.0) + PI / 2.0).abs() < 1e-9);
+        assert!((HueUnit::Degrees.to_radians(360.0)).abs() < 1e-9);
+        assert!((HueUnit::Turns.to_radians(0.5) - PI).abs() < 1e-9);
+
+        for unit in HueUnit::ALL {
+            for hue in [-3.0, -1.0, 0.0, 0.5, 3.0] {
+                let back = unit.to_radians(unit.from_radians(hue));
+                assert!((back - hue).abs() < 1e-9, "{unit:?} {hue} {back}");
+            }
+        }
+    }
+}
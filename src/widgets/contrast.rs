@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{vec2, Align2, ComboBox, FontId, Grid, Sense, Ui};
+
+use super::{color_text_edit_ui, CvdPreview};
+use crate::{
+    colors::{
+        contrast::{apca_contrast, wcag_contrast_ratio, WcagLevel},
+        Srgb,
+    },
+    palette::Swatch,
+};
+
+/// Shows the contrast of every color of the palette over a background color.
+///
+/// The background can be typed in any notation, or picked from the palette.
+/// Each color gets its WCAG 2.1 contrast ratio and conformance level, and its
+/// APCA lightness contrast as text over the background.
+///
+/// Returns `true` if the background changed.
+pub fn contrast_panel(ui: &mut Ui, background: &mut Srgb, palette: &[Swatch]) -> bool {
+    let mut changed = false;
+    let preview = CvdPreview::load(ui);
+
+    ui.horizontal(|ui| {
+        ui.label("Background");
+        let size = vec2(
+            2.0 * ui.spacing().interact_size.y,
+            ui.spacing().interact_size.y,
+        );
+        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+        ui.painter().rect(
+            rect,
+            2.0,
+            preview.apply(*background),
+            ui.visuals().widgets.noninteractive.bg_stroke,
+        );
+
+        ComboBox::from_id_source("contrast_background_from_palette")
+            .selected_text("From palette")
+            .show_ui(ui, |ui| {
+                for swatch in palette {
+                    if ui.selectable_label(false, &swatch.name).clicked() {
+                        *background = swatch.color;
+                        changed = true;
+                    }
+                }
+            });
+    });
+    let id = ui.make_persistent_id("contrast_background");
+    changed |= color_text_edit_ui(ui, id, background);
+
+    ui.add_space(5.0);
+
+    if palette.is_empty() {
+        ui.label("Add colors to the palette to compare them.");
+        return changed;
+    }
+
+    Grid::new("contrast_grid")
+        .striped(true)
+        .num_columns(4)
+        .show(ui, |ui| {
+            ui.label("Sample");
+            ui.label("Name");
+            ui.label("WCAG 2.1")
+                .on_hover_text("Contrast ratio and conformance level");
+            ui.label("APCA")
+                .on_hover_text("Lightness contrast (Lc) of the text");
+            ui.end_row();
+
+            for swatch in palette {
+                let size = vec2(
+                    2.0 * ui.spacing().interact_size.y,
+                    ui.spacing().interact_size.y,
+                );
+                let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+                ui.painter()
+                    .rect_filled(rect, 2.0, preview.apply(*background));
+                ui.painter().text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "Aa",
+                    FontId::proportional(rect.height() * 0.75),
+                    preview.apply(swatch.color),
+                );
+
+                ui.label(&swatch.name);
+
+                let ratio = wcag_contrast_ratio(swatch.color, *background);
+                ui.monospace(format!("{:5.2}:1 {}", ratio, WcagLevel::from_ratio(ratio)));
+
+                let lc = apca_contrast(swatch.color, *background);
+                ui.monospace(format!("Lc {:6.1}", lc));
+                ui.end_row();
+            }
+        });
+
+    changed
+}
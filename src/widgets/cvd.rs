@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{vec2, Color32, ComboBox, Sense, Slider, Ui};
+use serde::{Deserialize, Serialize};
+
+use super::CvdPreview;
+use crate::{
+    colors::cvd::{Deficiency, Method, Simulation},
+    palette::{confusable_pairs, Swatch, DEFAULT_CONFUSION_THRESHOLD},
+};
+
+/// Settings of the [`cvd_panel`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CvdCheck {
+    /// Whether the swatches and pickers are painted through the simulation
+    pub preview: bool,
+    pub simulation: Simulation,
+    /// ΔE_OK under which two colors are reported as confusable
+    pub threshold: f64,
+}
+
+impl Default for CvdCheck {
+    fn default() -> Self {
+        Self {
+            preview: false,
+            simulation: Simulation::default(),
+            threshold: DEFAULT_CONFUSION_THRESHOLD,
+        }
+    }
+}
+
+impl CvdCheck {
+    /// The preview to store for the widgets, see [`CvdPreview::store`].
+    pub fn preview(&self) -> CvdPreview {
+        CvdPreview(self.preview.then_some(self.simulation))
+    }
+}
+
+/// Shows the settings of a color vision deficiency simulation, and the
+/// palette colors that become hard to tell apart with it.
+pub fn cvd_panel(ui: &mut Ui, settings: &mut CvdCheck, palette: &[Swatch]) {
+    ui.checkbox(
+        &mut settings.preview,
+        "Simulate on the swatches and pickers",
+    );
+
+    let simulation = &mut settings.simulation;
+    ui.horizontal_wrapped(|ui| {
+        ComboBox::from_id_source("cvd_deficiency")
+            .selected_text(simulation.deficiency.name())
+            .show_ui(ui, |ui| {
+                for deficiency in Deficiency::ALL {
+                    ui.selectable_value(&mut simulation.deficiency, deficiency, deficiency.name());
+                }
+            })
+            .response
+            .on_hover_text("Cones affected by the deficiency");
+        ComboBox::from_id_source("cvd_method")
+            .selected_text(simulation.method.name())
+            .show_ui(ui, |ui| {
+                for method in Method::ALL {
+                    ui.selectable_value(&mut simulation.method, method, method.name());
+                }
+            })
+            .response
+            .on_hover_text(
+                "Brettel works for all the deficiencies, Viénot only for protans and deutans, \
+                 Machado is made for partial deficiencies",
+            );
+    });
+    ui.add(Slider::new(&mut simulation.severity, 0.0..=1.0).text("Severity"))
+        .on_hover_text("From normal vision to dichromacy");
+    ui.add(
+        Slider::new(&mut settings.threshold, 0.005..=0.1)
+            .text("Threshold")
+            .fixed_decimals(3),
+    )
+    .on_hover_text("ΔE_OK under which two colors are hard to tell apart");
+
+    ui.add_space(5.0);
+    let pairs = confusable_pairs(palette, &settings.simulation, settings.threshold);
+    if pairs.is_empty() {
+        ui.label(format!(
+            "No palette colors become confusable with {}.",
+            settings.simulation.name()
+        ));
+        return;
+    }
+
+    ui.colored_label(
+        ui.visuals().warn_fg_color,
+        format!(
+            "⚠ {} pairs of colors look alike with {}:",
+            pairs.len(),
+            settings.simulation.name()
+        ),
+    );
+    let preview = CvdPreview(Some(settings.simulation));
+    for (first, second, delta) in pairs {
+        ui.horizontal(|ui| {
+            for swatch in [&palette[first], &palette[second]] {
+                let size = vec2(
+                    2.0 * ui.spacing().interact_size.y,
+                    ui.spacing().interact_size.y,
+                );
+                let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+                ui.painter().rect(
+                    rect,
+                    2.0,
+                    preview.apply(swatch.color),
+                    ui.visuals().widgets.noninteractive.bg_stroke,
+                );
+                response.on_hover_ui(|ui| {
+                    ui.label(format!("{}, without the deficiency:", swatch.name));
+                    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+                    ui.painter().rect(
+                        rect,
+                        2.0,
+                        Color32::from(swatch.color),
+                        ui.visuals().widgets.noninteractive.bg_stroke,
+                    );
+                });
+            }
+            ui.label(format!(
+                "{} and {} (ΔE {delta:.3})",
+                palette[first].name, palette[second].name
+            ));
+        });
+    }
+}
+
+/// Shows a one line warning when palette colors become hard to tell apart
+/// with the simulated deficiency, listing them on hover.
+pub fn confusion_warning_ui(ui: &mut Ui, settings: &CvdCheck, palette: &[Swatch]) {
+    let pairs = confusable_pairs(palette, &settings.simulation, settings.threshold);
+    if pairs.is_empty() {
+        return;
+    }
+    ui.colored_label(
+        ui.visuals().warn_fg_color,
+        format!(
+            "⚠ {} confusable pairs with {}",
+            pairs.len(),
+            settings.simulation.name()
+        ),
+    )
+    .on_hover_ui(|ui| {
+        for (first, second, delta) in pairs {
+            ui.label(format!(
+                "{} and {} (ΔE {delta:.3})",
+                palette[first].name, palette[second].name
+            ));
+        }
+    });
+}
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{ComboBox, ScrollArea, TextEdit, TextStyle, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::palette::{
+    export::{export, ExportFormat},
+    Swatch,
+};
+
+/// Settings of the [`export_panel`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Export {
+    pub format: ExportFormat,
+    /// Name of the palette in the exported file
+    pub name: String,
+    /// Where the file gets saved
+    pub path: String,
+    /// Outcome of the last save, to show to the user
+    #[serde(skip)]
+    status: Option<Result<String, String>>,
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        let format = ExportFormat::default();
+        Self {
+            format,
+            name: "Ok Picker".to_owned(),
+            path: format!("ok-picker.{}", format.extension()),
+            status: None,
+        }
+    }
+}
+
+impl Export {
+    /// Changes the format, and the extension of the path along with it.
+    fn set_format(&mut self, format: ExportFormat) {
+        let extension = format!(".{}", self.format.extension());
+        if let Some(stem) = self.path.strip_suffix(&extension) {
+            self.path = format!("{stem}.{}", format.extension());
+        }
+        self.format = format;
+    }
+}
+
+/// Shows the export settings, a preview of the exported palette, and buttons
+/// to copy it or save it to a file.
+pub fn export_panel(ui: &mut Ui, settings: &mut Export, palette: &[Swatch]) {
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.text_edit_singleline(&mut settings.name)
+            .on_hover_text("Name of the palette in the file");
+    });
+
+    let mut format = settings.format;
+    ComboBox::from_label("Format")
+        .selected_text(format.name())
+        .show_ui(ui, |ui| {
+            for candidate in ExportFormat::ALL {
+                ui.selectable_value(&mut format, candidate, candidate.name());
+            }
+        });
+    if format != settings.format {
+        settings.set_format(format);
+    }
+
+    let text = export(settings.format, &settings.name, palette);
+
+    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        ui.add(
+            TextEdit::multiline(&mut text.as_str())
+                .font(TextStyle::Monospace)
+                .desired_width(f32::INFINITY),
+        );
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("📋 Copy").clicked() {
+            ui.output().copied_text = text.clone();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.add(TextEdit::singleline(&mut settings.path).hint_text("File to save to"));
+            if ui.button("💾 Save").clicked() {
+                settings.status = Some(
+                    std::fs::write(&settings.path, &text)
+                        .map(|()| format!("Saved to {}", settings.path))
+                        .map_err(|err| format!("Could not save to {}: {err}", settings.path)),
+                );
+            }
+        }
+    });
+
+    match &settings.status {
+        Some(Ok(message)) => {
+            ui.label(message);
+        }
+        Some(Err(message)) => {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+        None => {}
+    }
+}
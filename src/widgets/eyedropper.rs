@@ -0,0 +1,405 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::Path;
+
+use eframe::egui::{
+    vec2, Color32, ColorImage, Context, DragValue, Image, Pos2, Rect, Sense, Slider, TextureFilter,
+    TextureHandle, Ui,
+};
+use image::{imageops, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::{palette::add_swatch_button, CvdPreview};
+use crate::{
+    colors::{LinSrgb, Srgb},
+    palette::{
+        extract::{dominant_colors, DEFAULT_COUNT},
+        Swatch,
+    },
+};
+
+/// Extensions of the image files the eyedropper loads.
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// Number of image pixels across the loupe.
+const LOUPE_PIXELS: u32 = 15;
+
+/// An image loaded in the [`eyedropper_panel`].
+pub struct LoadedImage {
+    name: String,
+    /// The full resolution pixels, to sample from
+    pixels: RgbaImage,
+    /// The displayed image, downscaled to fit in a texture
+    texture: TextureHandle,
+}
+
+/// Settings of the [`eyedropper_panel`].
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct Eyedropper {
+    /// The image to load, on native platforms
+    pub path: String,
+    /// Width of the square of pixels averaged on each pick
+    pub sample_size: u32,
+    /// Number of dominant colors extracted from the image
+    pub colors: usize,
+    #[serde(skip)]
+    image: Option<LoadedImage>,
+    /// The last picked color
+    #[serde(skip)]
+    picked: Option<Srgb>,
+    /// Error of the last load, to show to the user
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+impl Default for Eyedropper {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            sample_size: 3,
+            colors: DEFAULT_COUNT,
+            image: None,
+            picked: None,
+            error: None,
+        }
+    }
+}
+
+/// Whether `path` looks like an image the eyedropper can load.
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            IMAGE_EXTENSIONS
+                .iter()
+                .any(|image| extension.eq_ignore_ascii_case(image))
+        })
+}
+
+impl Eyedropper {
+    /// Decodes a PNG or JPEG image and uploads it to show it in the panel.
+    ///
+    /// Images larger than the textures of the GPU are shown downscaled, and
+    /// still sampled at full resolution.
+    ///
+    /// Returns `true` on success, otherwise the error is shown in the panel.
+    pub fn load(&mut self, ctx: &Context, name: &str, bytes: &[u8]) -> bool {
+        match image::load_from_memory(bytes) {
+            Ok(image) => {
+                let pixels = image.to_rgba8();
+                let max_side = ctx.input().max_texture_side;
+                let displayed = fit_texture(&pixels, max_side);
+                let displayed = displayed.as_ref().unwrap_or(&pixels);
+                let size = [displayed.width() as usize, displayed.height() as usize];
+                let texture = ctx.load_texture(
+                    name,
+                    ColorImage::from_rgba_unmultiplied(size, displayed.as_raw()),
+                    TextureFilter::Linear,
+                );
+                self.image = Some(LoadedImage {
+                    name: name.to_owned(),
+                    pixels,
+                    texture,
+                });
+                self.error = None;
+                true
+            }
+            Err(err) => {
+                self.error = Some(format!("Could not load {name}: {err}"));
+                false
+            }
+        }
+    }
+
+    /// Loads the image files dropped on the window, and leaves the other
+    /// files for the palette import.
+    ///
+    /// Returns `true` if images were dropped, successfully loaded or not.
+    pub fn load_dropped_images(&mut self, ctx: &Context) -> bool {
+        let images: Vec<_> = {
+            let mut input = ctx.input_mut();
+            let (images, others) = std::mem::take(&mut input.raw.dropped_files)
+                .into_iter()
+                .partition(|file| {
+                    is_image(
+                        file.path
+                            .as_deref()
+                            .unwrap_or_else(|| Path::new(&file.name)),
+                    )
+                });
+            input.raw.dropped_files = others;
+            images
+        };
+
+        for file in &images {
+            let name = file
+                .path
+                .as_ref()
+                .map_or_else(|| file.name.clone(), |path| path.display().to_string());
+            match (&file.bytes, &file.path) {
+                (Some(bytes), _) => {
+                    self.load(ctx, &name, bytes);
+                }
+                (None, Some(path)) => match std::fs::read(path) {
+                    Ok(bytes) => {
+                        self.load(ctx, &name, &bytes);
+                    }
+                    Err(err) => self.error = Some(format!("Could not read {name}: {err}")),
+                },
+                (None, None) => {}
+            }
+        }
+        !images.is_empty()
+    }
+}
+
+/// Downscales `image` so that no side is longer than `max_side`, keeping its
+/// aspect ratio.
+///
+/// Returns `None` if the image already fits.
+fn fit_texture(image: &RgbaImage, max_side: usize) -> Option<RgbaImage> {
+    let max_side = u32::try_from(max_side).unwrap_or(u32::MAX).max(1);
+    let (width, height) = image.dimensions();
+    if width <= max_side && height <= max_side {
+        return None;
+    }
+    let scale = f64::from(max_side) / f64::from(width.max(height));
+    let fit = |side: u32| ((f64::from(side) * scale).round() as u32).clamp(1, max_side);
+    Some(imageops::resize(
+        image,
+        fit(width),
+        fit(height),
+        imageops::FilterType::Triangle,
+    ))
+}
+
+/// Averages the `size`×`size` pixels around (`x`, `y`) in linear light.
+///
+/// With an even `size`, the square has one more pixel after (`x`, `y`) than
+/// before. The pixels are weighted by their alpha, and the square is cropped
+/// to the image. Returns `None` if all the pixels are transparent.
+pub fn sample(image: &RgbaImage, x: u32, y: u32, size: u32) -> Option<Srgb> {
+    let (before, after) = (size.saturating_sub(1) / 2, size / 2);
+    let columns = x.saturating_sub(before)..=(x + after).min(image.width().checked_sub(1)?);
+    let rows = y.saturating_sub(before)..=(y + after).min(image.height().checked_sub(1)?);
+
+    let mut sum = LinSrgb::default();
+    let mut weight = 0.0;
+    for row in rows {
+        for column in columns.clone() {
+            let [red, green, blue, alpha] = image.get_pixel(column, row).0;
+            let alpha = f64::from(alpha) / 255.0;
+            let linear = LinSrgb::from(Srgb::from(Color32::from_rgb(red, green, blue)));
+            sum.red += alpha * linear.red;
+            sum.green += alpha * linear.green;
+            sum.blue += alpha * linear.blue;
+            weight += alpha;
+        }
+    }
+
+    (weight > 0.0).then(|| {
+        Srgb::from(LinSrgb {
+            red: sum.red / weight,
+            green: sum.green / weight,
+            blue: sum.blue / weight,
+        })
+    })
+}
+
+/// Shows an image file, to pick colors by clicking on it.
+///
+/// Hovering the image shows a loupe, with the pixels that get averaged
+/// outlined. PNG and JPEG files can also be dropped on the window. The
+/// dominant colors of the image can be added to the palette all at once.
+///
+/// Returns the color picked this frame, if any.
+pub fn eyedropper_panel(
+    ui: &mut Ui,
+    settings: &mut Eyedropper,
+    palette: &mut Vec<Swatch>,
+) -> Option<Srgb> {
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.add(eframe::egui::TextEdit::singleline(&mut settings.path).hint_text("Image to load"));
+        if ui.button("📂 Load").clicked() {
+            match std::fs::read(&settings.path) {
+                Ok(bytes) => {
+                    let path = settings.path.clone();
+                    settings.load(ui.ctx(), &path, &bytes);
+                }
+                Err(err) => {
+                    settings.error = Some(format!("Could not read {}: {err}", settings.path));
+                }
+            }
+        }
+    });
+    ui.label("Drop PNG or JPEG files on the window to load them.");
+    if let Some(error) = &settings.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    ui.add(Slider::new(&mut settings.sample_size, 1..=LOUPE_PIXELS).text("Sample size"))
+        .on_hover_text("Width of the square of pixels averaged on each pick");
+
+    let mut picked = None;
+    if let Some(image) = &settings.image {
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut settings.colors).clamp_range(1..=32));
+            if ui
+                .button("🎨 Extract palette")
+                .on_hover_text("Add the dominant colors of the image to the palette")
+                .clicked()
+            {
+                palette.extend(
+                    dominant_colors(image.pixels.as_raw(), settings.colors)
+                        .into_iter()
+                        .map(|dominant| Swatch::new(dominant.color)),
+                );
+            }
+        });
+
+        ui.label(&image.name);
+        // The size of the full image, the texture may be smaller
+        let image_size = vec2(image.pixels.width() as f32, image.pixels.height() as f32);
+        let scale = (ui.available_width() / image_size.x).min(1.0);
+        let response = ui.add(Image::new(&image.texture, image_size * scale).sense(Sense::click()));
+
+        // Image pixel under the pointer
+        let pixel_at = |pos: Pos2| {
+            let offset = (pos - response.rect.min) / scale;
+            (offset.x >= 0.0 && offset.y >= 0.0)
+                .then_some((offset.x as u32, offset.y as u32))
+                .filter(|&(x, y)| x < image.pixels.width() && y < image.pixels.height())
+        };
+
+        if let Some((x, y)) = response.hover_pos().and_then(pixel_at) {
+            let sample_size = settings.sample_size;
+            response.clone().on_hover_ui_at_pointer(|ui| {
+                loupe_ui(ui, &image.pixels, x, y, sample_size);
+            });
+        }
+        if response.clicked() {
+            if let Some((x, y)) = response.interact_pointer_pos().and_then(pixel_at) {
+                picked = sample(&image.pixels, x, y, settings.sample_size);
+            }
+        }
+    }
+
+    if picked.is_some() {
+        settings.picked = picked;
+    }
+    if let Some(color) = settings.picked {
+        ui.horizontal(|ui| {
+            let size = vec2(
+                2.0 * ui.spacing().interact_size.y,
+                ui.spacing().interact_size.y,
+            );
+            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            ui.painter().rect(
+                rect,
+                2.0,
+                CvdPreview::load(ui).apply(color),
+                ui.visuals().widgets.noninteractive.bg_stroke,
+            );
+            add_swatch_button(ui, palette, color);
+        });
+    }
+
+    picked
+}
+
+/// Paints the pixels around (`x`, `y`) magnified, with the sampled square
+/// outlined.
+fn loupe_ui(ui: &mut Ui, image: &RgbaImage, x: u32, y: u32, sample_size: u32) {
+    let cell = 8.0;
+    let side = LOUPE_PIXELS as f32 * cell;
+    let (rect, _) = ui.allocate_exact_size(vec2(side, side), Sense::hover());
+    let painter = ui.painter();
+
+    let half = LOUPE_PIXELS / 2;
+    for row in 0..LOUPE_PIXELS {
+        for column in 0..LOUPE_PIXELS {
+            let (Some(image_x), Some(image_y)) =
+                ((x + column).checked_sub(half), (y + row).checked_sub(half))
+            else {
+                continue;
+            };
+            let Some(pixel) = image.get_pixel_checked(image_x, image_y) else {
+                continue;
+            };
+            let [red, green, blue, alpha] = pixel.0;
+            let min = rect.min + vec2(column as f32, row as f32) * cell;
+            painter.rect_filled(
+                Rect::from_min_size(min, vec2(cell, cell)),
+                0.0,
+                Color32::from_rgba_unmultiplied(red, green, blue, alpha),
+            );
+        }
+    }
+
+    let first = (half - (sample_size - 1) / 2) as f32 * cell;
+    let sampled = sample_size as f32 * cell;
+    painter.rect_stroke(
+        Rect::from_min_size(rect.min + vec2(first, first), vec2(sampled, sampled)),
+        0.0,
+        ui.visuals().widgets.active.fg_stroke,
+    );
+    painter.rect_stroke(rect, 0.0, ui.visuals().widgets.noninteractive.bg_stroke);
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn sampling() {
+        // Black and white columns
+        let image = RgbaImage::from_fn(4, 4, |x, _| {
+            let value = if x % 2 == 0 { 0 } else { 255 };
+            Rgba([value, value, value, 255])
+        });
+
+        let single = sample(&image, 1, 1, 1).unwrap();
+        assert_eq!(Color32::from(single), Color32::WHITE);
+
+        // Averaging in linear light gives a lighter gray than the mean of
+        // the sRGB bytes
+        let gray = sample(&image, 0, 0, 2).unwrap();
+        assert!((gray.red - 0.7354).abs() < 1e-3, "{gray:?}");
+
+        // The corner only averages the pixels inside the image
+        let corner = sample(&image, 0, 0, 3).unwrap();
+        assert!((corner.red - gray.red).abs() < 1e-9);
+
+        let third = Srgb::from(LinSrgb {
+            red: 1.0 / 3.0,
+            green: 1.0 / 3.0,
+            blue: 1.0 / 3.0,
+        });
+        let centered = sample(&image, 1, 1, 3).unwrap();
+        assert!((centered.red - third.red).abs() < 1e-9, "{centered:?}");
+
+        let transparent = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 0]));
+        assert_eq!(sample(&transparent, 0, 0, 3), None);
+    }
+
+    #[test]
+    fn texture_size() {
+        let image = RgbaImage::from_pixel(5000, 1250, Rgba([10, 20, 30, 255]));
+        let fit = fit_texture(&image, 4096).unwrap();
+        assert_eq!(fit.dimensions(), (4096, 1024));
+        assert_eq!(fit.get_pixel(100, 100), &Rgba([10, 20, 30, 255]));
+
+        let tall = RgbaImage::new(10, 3000);
+        assert_eq!(fit_texture(&tall, 2048).unwrap().dimensions(), (7, 2048));
+        let thin = RgbaImage::new(1, 5000);
+        assert_eq!(fit_texture(&thin, 2048).unwrap().dimensions(), (1, 2048));
+
+        assert!(fit_texture(&RgbaImage::new(4096, 4096), 4096).is_none());
+    }
+}
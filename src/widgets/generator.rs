@@ -0,0 +1,198 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{pos2, vec2, Align2, FontId, Rect, Sense, Slider, TextStyle, Ui};
+use serde::{Deserialize, Serialize};
+
+use super::{color_text_edit_ui, CvdPreview};
+use crate::{
+    colors::Srgb,
+    palette::{
+        generate::{generate, Rules, Scheme, Seed},
+        Swatch,
+    },
+};
+
+/// Which colors the scheme is generated from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeedMode {
+    #[default]
+    Foreground,
+    ForegroundBackground,
+    ForegroundAccent,
+}
+
+impl SeedMode {
+    pub const ALL: [Self; 3] = [
+        Self::Foreground,
+        Self::ForegroundBackground,
+        Self::ForegroundAccent,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Foreground => "Foreground",
+            Self::ForegroundBackground => "Foreground + background",
+            Self::ForegroundAccent => "Foreground + accent",
+        }
+    }
+}
+
+/// Settings of the [`generator_panel`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Generator {
+    pub mode: SeedMode,
+    pub foreground: Srgb,
+    /// The background or the accent, depending on the mode
+    pub second: Srgb,
+    pub rules: Rules,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self {
+            mode: SeedMode::default(),
+            foreground: Srgb {
+                red: 0.85,
+                green: 0.85,
+                blue: 0.8,
+            },
+            second: Srgb::default(),
+            rules: Rules::default(),
+        }
+    }
+}
+
+impl Generator {
+    pub fn seed(&self) -> Seed {
+        match self.mode {
+            SeedMode::Foreground => Seed::Foreground(self.foreground),
+            SeedMode::ForegroundBackground => Seed::ForegroundBackground {
+                foreground: self.foreground,
+                background: self.second,
+            },
+            SeedMode::ForegroundAccent => Seed::ForegroundAccent {
+                foreground: self.foreground,
+                accent: self.second,
+            },
+        }
+    }
+}
+
+/// Shows the settings of the palette generator, and a live preview of the
+/// generated scheme.
+///
+/// Returns the swatches of the scheme when the user asks to use it as the
+/// palette.
+pub fn generator_panel(ui: &mut Ui, generator: &mut Generator) -> Option<Vec<Swatch>> {
+    ui.horizontal_wrapped(|ui| {
+        for mode in SeedMode::ALL {
+            ui.radio_value(&mut generator.mode, mode, mode.name());
+        }
+    });
+
+    ui.label("Foreground");
+    let id = ui.make_persistent_id("generator_foreground");
+    color_text_edit_ui(ui, id, &mut generator.foreground);
+    let second = match generator.mode {
+        SeedMode::Foreground => None,
+        SeedMode::ForegroundBackground => Some("Background"),
+        SeedMode::ForegroundAccent => Some("Accent"),
+    };
+    if let Some(label) = second {
+        ui.label(label);
+        let id = ui.make_persistent_id("generator_second");
+        color_text_edit_ui(ui, id, &mut generator.second);
+    }
+
+    ui.add(Slider::new(&mut generator.rules.min_contrast, 1.0..=21.0).text("Min contrast"))
+        .on_hover_text("Minimum WCAG contrast ratio of the text colors");
+    ui.add(
+        Slider::new(&mut generator.rules.min_comment_contrast, 1.0..=21.0)
+            .text("Min comment contrast"),
+    )
+    .on_hover_text("Minimum WCAG contrast ratio of the comments");
+    ui.add(Slider::new(&mut generator.rules.saturation, 0.0..=1.0).text("Saturation"))
+        .on_hover_text("OkHsl saturation of the ANSI colors");
+
+    let scheme = generate(generator.seed(), &generator.rules);
+    scheme_preview(ui, &scheme);
+    if !scheme.meets_contrast {
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            "⚠ Some colors can't reach the minimum contrast over this background",
+        );
+    }
+
+    ui.button("Replace the palette")
+        .on_hover_text("Use the generated scheme as the palette")
+        .clicked()
+        .then(|| scheme.swatches())
+}
+
+/// Paints a sample of text and the ANSI colors over the scheme background.
+fn scheme_preview(ui: &mut Ui, scheme: &Scheme) {
+    let row_height = ui.text_style_height(&TextStyle::Monospace) * 1.5;
+    let font = FontId::monospace(row_height / 1.5);
+    let size = vec2(ui.available_width().max(8.0 * row_height), 5.0 * row_height);
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+
+    let preview = CvdPreview::load(ui);
+    let painter = ui.painter();
+    painter.rect(
+        rect,
+        2.0,
+        preview.apply(scheme.background),
+        ui.visuals().widgets.noninteractive.bg_stroke,
+    );
+
+    let margin = row_height / 3.0;
+    let row = |index: usize| {
+        Rect::from_min_size(
+            pos2(rect.left() + margin, rect.top() + index as f32 * row_height),
+            vec2(rect.width() - 2.0 * margin, row_height),
+        )
+    };
+
+    let lines = [
+        ("// A comment", scheme.comment),
+        ("let text = \"Foreground\";", scheme.foreground),
+        ("fn accent()", scheme.accent),
+    ];
+    for (index, (text, color)) in lines.into_iter().enumerate() {
+        let row = row(index);
+        if index == 1 {
+            painter.rect_filled(row, 0.0, preview.apply(scheme.selection));
+        }
+        painter.text(
+            row.left_center(),
+            Align2::LEFT_CENTER,
+            text,
+            font.clone(),
+            preview.apply(color),
+        );
+    }
+
+    for (index, colors) in scheme.ansi.chunks(8).enumerate() {
+        let row = row(3 + index);
+        let width = row.width() / 8.0;
+        for (slot, color) in colors.iter().enumerate() {
+            let cell = Rect::from_min_size(
+                pos2(row.left() + slot as f32 * width, row.top()),
+                vec2(width, row.height()),
+            );
+            painter.text(
+                cell.center(),
+                Align2::CENTER_CENTER,
+                "Aa",
+                font.clone(),
+                preview.apply(*color),
+            );
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{
+    pos2, remap_clamp, vec2, Button, ComboBox, Rect, Sense, Shape, Slider, Stroke, Ui,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    background_checkers, color_text_edit_ui, contrast_color, horizontal_gradient, CvdPreview,
+};
+use crate::{
+    colors::interpolate::{Easing, Gradient, HueInterpolation, MixSpace, Stop},
+    palette::Swatch,
+};
+
+/// Settings of the [`gradient_panel`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GradientEditor {
+    pub gradient: Gradient,
+    /// Number of colors sampled from the gradient
+    pub samples: usize,
+}
+
+impl Default for GradientEditor {
+    fn default() -> Self {
+        Self {
+            gradient: Gradient::default(),
+            samples: 5,
+        }
+    }
+}
+
+/// Shows an editor of a multi-stop gradient, with a preview of the gradient
+/// and of the colors sampled from it.
+///
+/// Stops are selected by clicking their marker under the preview, moved by
+/// dragging them, and added by double clicking the preview.
+///
+/// Returns the sampled colors when the user asks to add them to the palette.
+pub fn gradient_panel(ui: &mut Ui, editor: &mut GradientEditor) -> Option<Vec<Swatch>> {
+    let id = ui.make_persistent_id("gradient_selected_stop");
+    let mut selected = ui.data().get_temp::<usize>(id).unwrap_or_default();
+
+    let gradient = &mut editor.gradient;
+    ui.horizontal_wrapped(|ui| {
+        ComboBox::from_id_source("gradient_space")
+            .selected_text(gradient.space.name())
+            .show_ui(ui, |ui| {
+                for space in MixSpace::ALL {
+                    ui.selectable_value(&mut gradient.space, space, space.name());
+                }
+            })
+            .response
+            .on_hover_text("Color space in which the stops are mixed");
+        ui.add_enabled_ui(gradient.space.is_polar(), |ui| {
+            ComboBox::from_id_source("gradient_hue")
+                .selected_text(gradient.hue.name())
+                .show_ui(ui, |ui| {
+                    for hue in HueInterpolation::ALL {
+                        ui.selectable_value(&mut gradient.hue, hue, hue.name());
+                    }
+                })
+                .response
+                .on_hover_text("Way around the hue circle");
+        });
+        ComboBox::from_id_source("gradient_easing")
+            .selected_text(gradient.easing.name())
+            .show_ui(ui, |ui| {
+                for easing in Easing::ALL {
+                    ui.selectable_value(&mut gradient.easing, easing, easing.name());
+                }
+            });
+    });
+
+    gradient_bar(ui, gradient, &mut selected);
+
+    if let Some(stop) = gradient.stops.get_mut(selected) {
+        let text_id = ui.make_persistent_id("gradient_stop_color");
+        color_text_edit_ui(ui, text_id, &mut stop.color);
+        let moved = ui
+            .add(Slider::new(&mut stop.position, 0.0..=1.0).text("Position"))
+            .changed();
+        if moved {
+            selected = move_stop(gradient, selected);
+        }
+        let removable = gradient.stops.len() > 2;
+        if ui
+            .add_enabled(removable, Button::new("➖ Remove stop"))
+            .on_hover_text("A gradient keeps at least two stops")
+            .clicked()
+        {
+            gradient.stops.remove(selected);
+            selected = selected.saturating_sub(1);
+        }
+    }
+    ui.data().insert_temp(id, selected);
+
+    ui.add(Slider::new(&mut editor.samples, 2..=32).text("Samples"));
+    let samples = editor.gradient.sample(editor.samples);
+    ui.horizontal_wrapped(|ui| {
+        let size = vec2(
+            2.0 * ui.spacing().interact_size.y,
+            ui.spacing().interact_size.y,
+        );
+        let preview = CvdPreview::load(ui);
+        for color in &samples {
+            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            ui.painter().rect(
+                rect,
+                2.0,
+                preview.apply(*color),
+                ui.visuals().widgets.noninteractive.bg_stroke,
+            );
+        }
+    });
+
+    ui.button("➕ Add to palette")
+        .on_hover_text("Add the sampled colors to the palette")
+        .clicked()
+        .then(|| samples.into_iter().map(Swatch::new).collect())
+}
+
+/// Paints the gradient and a marker for each of its stops, and handles the
+/// clicks and drags on them.
+fn gradient_bar(ui: &mut Ui, gradient: &mut Gradient, selected: &mut usize) {
+    let width = ui.available_width().max(2.0 * ui.spacing().slider_width);
+    let height = ui.spacing().interact_size.y;
+    let (bar, bar_response) = ui.allocate_exact_size(vec2(width, 1.5 * height), Sense::click());
+    let (markers, markers_response) =
+        ui.allocate_exact_size(vec2(width, height), Sense::click_and_drag());
+
+    let position_at = |x: f32| f64::from(remap_clamp(x, bar.left()..=bar.right(), 0.0..=1.0));
+    let x_at = |position: f64| bar.left() + position as f32 * bar.width();
+
+    if bar_response.double_clicked() {
+        if let Some(pointer) = bar_response.interact_pointer_pos() {
+            let position = position_at(pointer.x);
+            gradient
+                .stops
+                .push(Stop::new(position, gradient.at(position)));
+            *selected = move_stop(gradient, gradient.stops.len() - 1);
+        }
+    }
+
+    if markers_response.drag_started() || markers_response.clicked() {
+        if let Some(pointer) = markers_response.interact_pointer_pos() {
+            let closest = gradient.stops.iter().enumerate().min_by(|first, second| {
+                let distance = |stop: &Stop| (x_at(stop.position) - pointer.x).abs();
+                distance(first.1).total_cmp(&distance(second.1))
+            });
+            if let Some((index, _)) = closest {
+                *selected = index;
+            }
+        }
+    }
+    if markers_response.dragged() {
+        if let (Some(pointer), Some(stop)) = (
+            markers_response.interact_pointer_pos(),
+            gradient.stops.get_mut(*selected),
+        ) {
+            stop.position = position_at(pointer.x);
+            *selected = move_stop(gradient, *selected);
+        }
+    }
+
+    if !ui.is_rect_visible(bar.union(markers)) {
+        return;
+    }
+    let visuals = ui.style().interact(&markers_response);
+    let preview = CvdPreview::load(ui);
+    background_checkers(ui.painter(), bar);
+    horizontal_gradient(ui.painter(), bar, |t| preview.apply(gradient.at(t)));
+    ui.painter().rect_stroke(bar, 0.0, visuals.bg_stroke);
+
+    let r = markers.height() / 2.0;
+    for (index, stop) in gradient.stops.iter().enumerate() {
+        let x = x_at(stop.position);
+        let color = preview.apply(stop.color);
+        let stroke = if index == *selected {
+            Stroke::new(2.0 * visuals.fg_stroke.width, contrast_color(color))
+        } else {
+            Stroke::new(visuals.fg_stroke.width, contrast_color(color))
+        };
+        ui.painter().add(Shape::convex_polygon(
+            vec![
+                pos2(x, markers.top()),
+                pos2(x + r, markers.top() + r),
+                pos2(x - r, markers.top() + r),
+            ],
+            color,
+            stroke,
+        ));
+        ui.painter().rect(
+            Rect::from_min_max(
+                pos2(x - r, markers.top() + r),
+                pos2(x + r, markers.bottom()),
+            ),
+            0.0,
+            color,
+            stroke,
+        );
+    }
+}
+
+/// Sorts the stops after the stop at `index` moved, and returns its new index.
+fn move_stop(gradient: &mut Gradient, index: usize) -> usize {
+    let moved = gradient.stops[index];
+    gradient.sort();
+    gradient
+        .stops
+        .iter()
+        .position(|stop| *stop == moved)
+        .unwrap_or(index)
+}
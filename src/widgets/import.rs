@@ -0,0 +1,118 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::Path;
+
+use eframe::egui::{Context, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::palette::{
+    import::{import_detect, ImportedPalette},
+    Swatch,
+};
+
+/// Settings of the [`import_panel`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Import {
+    /// The file to import, on native platforms
+    pub path: String,
+    /// Whether the imported colors replace the palette or get added to it
+    pub replace: bool,
+    /// Outcome of the last import, to show to the user
+    #[serde(skip)]
+    status: Option<Result<String, String>>,
+}
+
+impl Import {
+    /// Adds the colors of a palette file to `palette`, or replaces it,
+    /// and remembers the outcome to show it in the panel.
+    ///
+    /// Returns `true` if the palette changed.
+    pub fn load(&mut self, path: Option<&Path>, bytes: &[u8], palette: &mut Vec<Swatch>) -> bool {
+        let source = path.map_or_else(|| "the file".to_owned(), |path| path.display().to_string());
+        match import_detect(path, bytes) {
+            Ok(ImportedPalette { name, swatches }) => {
+                let count = swatches.len();
+                if self.replace {
+                    *palette = swatches;
+                } else {
+                    palette.extend(swatches);
+                }
+                let name = name.map_or_else(String::new, |name| format!(" from {name:?}"));
+                self.status = Some(Ok(format!("Imported {count} colors{name}")));
+                true
+            }
+            Err(err) => {
+                self.status = Some(Err(format!("Could not import {source}: {err}")));
+                false
+            }
+        }
+    }
+
+    /// Imports the files dropped on the window.
+    ///
+    /// Returns `true` if files were dropped, successfully or not.
+    pub fn load_dropped_files(&mut self, ctx: &Context, palette: &mut Vec<Swatch>) -> bool {
+        let dropped = std::mem::take(&mut ctx.input_mut().raw.dropped_files);
+        for file in &dropped {
+            match (&file.bytes, &file.path) {
+                (Some(bytes), path) => {
+                    let path = path.clone().unwrap_or_else(|| file.name.clone().into());
+                    self.load(Some(&path), bytes, palette);
+                }
+                (None, Some(path)) => match std::fs::read(path) {
+                    Ok(bytes) => {
+                        self.load(Some(path), &bytes, palette);
+                    }
+                    Err(err) => {
+                        self.status =
+                            Some(Err(format!("Could not read {}: {err}", path.display())));
+                    }
+                },
+                (None, None) => {}
+            }
+        }
+        !dropped.is_empty()
+    }
+}
+
+/// Shows the import settings, and a button to load a palette file into
+/// `palette`.
+///
+/// GIMP `.gpl`, Adobe `.ase` and JSON files are accepted, and can also be
+/// dropped on the window.
+pub fn import_panel(ui: &mut Ui, settings: &mut Import, palette: &mut Vec<Swatch>) {
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut settings.replace, false, "Add to the palette");
+        ui.radio_value(&mut settings.replace, true, "Replace the palette");
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.horizontal(|ui| {
+        ui.add(eframe::egui::TextEdit::singleline(&mut settings.path).hint_text("File to import"));
+        if ui.button("📂 Load").clicked() {
+            let path = std::path::PathBuf::from(&settings.path);
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    settings.load(Some(&path), &bytes, palette);
+                }
+                Err(err) => {
+                    settings.status = Some(Err(format!("Could not read {}: {err}", settings.path)));
+                }
+            }
+        }
+    });
+    ui.label("Drop .gpl, .ase or .json files on the window to import them.");
+
+    match &settings.status {
+        Some(Ok(message)) => {
+            ui.label(message);
+        }
+        Some(Err(message)) => {
+            ui.colored_label(ui.visuals().error_fg_color, message);
+        }
+        None => {}
+    }
+}
@@ -2,25 +2,89 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::{f64::consts, fmt::Debug, hash::Hash};
+
 use eframe::{
     egui::{
-        lerp, pos2, remap_clamp, vec2, Color32, Layout, Mesh, Painter, Rect, Response, Rgba, Sense,
-        Shape, Stroke, Ui, Vec2,
+        lerp, pos2, remap_clamp, text::LayoutJob, vec2, Button, Color32, Context, Id, Key, Layout,
+        Mesh, Modifiers, Painter, Rect, Response, Rgba, Sense, Shape, Stroke, TextEdit, TextFormat,
+        TextStyle, Ui, Vec2,
     },
     emath::Align,
     epaint,
 };
 
-use crate::colors::{OkHsl, OkHsv, Srgb};
+use crate::colors::{
+    contrast::wcag_contrast_ratio,
+    cvd::Simulation,
+    difference::delta_e_ok,
+    format::Notation,
+    parse::{ParseColorErrorKind, ParsedColor},
+    Alpha, LinSrgb, OkHsl, OkHsv, OkLCh, Srgb,
+};
 
+pub mod components;
+pub mod contrast;
+pub mod cvd;
+pub mod export;
+pub mod eyedropper;
+pub mod generator;
+pub mod gradient;
+pub mod import;
 pub mod okhsl;
 pub mod okhsv;
+pub mod oklch;
+pub mod palette;
 
 /// Number of vertices per dimension in the color sliders.
 /// We need at least 6 for hues, and more for smooth 2D areas.
 /// Should always be a multiple of 6 to hit the peak hues in HSV/HSL (every 60°).
 const N: u32 = 6 * 6;
 
+/// Default number of cells per dimension of the 2D picker meshes.
+///
+/// Like [`N`], other resolutions should be multiples of 6 for the hues.
+pub const DEFAULT_MESH_RESOLUTION: u32 = N;
+
+/// Color vision deficiency simulated on the colors the widgets paint, shared
+/// by all the widgets of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CvdPreview(pub Option<Simulation>);
+
+impl CvdPreview {
+    fn id() -> Id {
+        Id::new("cvd_preview")
+    }
+
+    pub fn load(ui: &Ui) -> Self {
+        ui.data().get_temp(Self::id()).unwrap_or_default()
+    }
+
+    /// Sets the preview of the widgets painted after this call.
+    pub fn store(self, ctx: &Context) {
+        ctx.data().insert_temp(Self::id(), self);
+    }
+
+    /// The color to paint for `color`.
+    pub fn apply(self, color: impl Into<Color32>) -> Color32 {
+        let color = color.into();
+        let Some(simulation) = self.0 else {
+            return color;
+        };
+        // The simulations are linear, so they also apply to premultiplied
+        // colors
+        let rgba = Rgba::from(color);
+        let mut rgb = simulation.simulate(LinSrgb {
+            red: rgba.r().into(),
+            green: rgba.g().into(),
+            blue: rgba.b().into(),
+        });
+        rgb.clamp();
+        Rgba::from_rgba_premultiplied(rgb.red as f32, rgb.green as f32, rgb.blue as f32, rgba.a())
+            .into()
+    }
+}
+
 fn color_text_rgb_dec_ui(ui: &mut Ui, color: impl Into<Srgb>) {
     let color = color.into();
     ui.allocate_ui_with_layout(
@@ -55,17 +119,13 @@ fn color_text_rgb_hex_ui(ui: &mut Ui, color: impl Into<Srgb>) {
         ),
         Layout::left_to_right(Align::Center),
         |ui| {
-            let Srgb { red, green, blue } = color;
-
-            let r = (256.0 * red).floor() as u8;
-            let g = (256.0 * green).floor() as u8;
-            let b = (256.0 * blue).floor() as u8;
+            let hex = Notation::Hex.format(color);
 
             if ui.button("📋").on_hover_text("Click to copy").clicked() {
-                ui.output().copied_text = format!("#{:02X}{:02X}{:02X}", r, g, b);
+                ui.output().copied_text = hex.clone();
             }
 
-            ui.label(format!("rgb(#{:02X}{:02X}{:02X})", r, g, b))
+            ui.label(format!("rgb({})", hex))
                 .on_hover_text("Red Green Blue, Hex");
         },
     );
@@ -125,6 +185,251 @@ fn color_text_okhsl_ui(ui: &mut Ui, color: impl Into<OkHsl>) {
     );
 }
 
+/// Text buffer of a [`color_text_edit_ui`], kept in egui memory between frames.
+#[derive(Clone, Debug)]
+struct ColorTextEditState {
+    text: String,
+    /// The color the text was last synchronized with.
+    synced: Alpha<Srgb>,
+}
+
+impl ColorTextEditState {
+    fn new(color: Alpha<Srgb>) -> Self {
+        let notation = if color.alpha < 1.0 {
+            Notation::HexAlpha
+        } else {
+            Notation::Hex
+        };
+        Self {
+            text: notation.format_alpha(color),
+            synced: color,
+        }
+    }
+}
+
+/// Shows an editable text field that sets `color` from any notation the user
+/// types or pastes (see [`crate::colors::parse`]).
+///
+/// When the input cannot be parsed, the offending part is highlighted and
+/// the error is shown under the field. The field follows the color when it
+/// gets changed elsewhere, as long as it isn't being edited.
+///
+/// Returns `true` on change.
+fn color_text_edit_ui(ui: &mut Ui, id: Id, color: &mut Srgb) -> bool {
+    let mut translucent = Alpha::opaque(*color);
+    let changed = color_text_edit_alpha_ui(ui, id, &mut translucent);
+    *color = translucent.color;
+    changed
+}
+
+/// Same as [`color_text_edit_ui`], keeping the alpha the user types.
+fn color_text_edit_alpha_ui(ui: &mut Ui, id: Id, color: &mut Alpha<Srgb>) -> bool {
+    let text_edit_id = id.with("text_edit");
+    let mut state = ui
+        .data()
+        .get_temp::<ColorTextEditState>(id)
+        .unwrap_or_else(|| ColorTextEditState::new(*color));
+
+    if !ui.memory().has_focus(text_edit_id) && state.synced != *color {
+        state = ColorTextEditState::new(*color);
+    }
+
+    let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let normal = TextFormat::simple(font_id.clone(), ui.visuals().text_color());
+        let highlighted = TextFormat {
+            color: ui.visuals().error_fg_color,
+            underline: Stroke::new(1.0, ui.visuals().error_fg_color),
+            ..TextFormat::simple(font_id, ui.visuals().text_color())
+        };
+
+        let mut job = LayoutJob::default();
+        job.wrap.max_width = wrap_width;
+        match text.parse::<ParsedColor>() {
+            Err(err)
+                if text.get(err.span.clone()).is_some()
+                    && err.kind != ParseColorErrorKind::Empty =>
+            {
+                job.append(&text[..err.span.start], 0.0, normal.clone());
+                job.append(&text[err.span.clone()], 0.0, highlighted);
+                job.append(&text[err.span.end..], 0.0, normal);
+            }
+            _ => job.append(text, 0.0, normal),
+        }
+        ui.fonts().layout_job(job)
+    };
+
+    let response = ui
+        .add(
+            TextEdit::singleline(&mut state.text)
+                .id(text_edit_id)
+                .desired_width(2.0 * ui.spacing().slider_width)
+                .hint_text("#RRGGBB, rgb(), okhsv(), okhsl(), oklch()")
+                .layouter(&mut layouter),
+        )
+        .on_hover_text("Type or paste a color");
+
+    let mut changed = false;
+    match state.text.parse::<Alpha<Srgb>>() {
+        Ok(new_color) => {
+            if response.changed() {
+                *color = new_color;
+                state.synced = new_color;
+                changed = true;
+            }
+        }
+        Err(err) if err.kind != ParseColorErrorKind::Empty => {
+            ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+        }
+        Err(_) => {}
+    }
+
+    ui.data().insert_temp(id, state);
+    changed
+}
+
+/// Shows the color in the notations that keep its alpha.
+fn color_text_alpha_ui(ui: &mut Ui, color: Alpha<Srgb>) {
+    for notation in [Notation::HexAlpha, Notation::Rgba] {
+        ui.allocate_ui_with_layout(
+            vec2(
+                2.0 * ui.spacing().slider_width,
+                ui.spacing().interact_size.y,
+            ),
+            Layout::left_to_right(Align::Center),
+            |ui| {
+                let text = notation.format_alpha(color);
+
+                if ui.button("📋").on_hover_text("Click to copy").clicked() {
+                    ui.output().copied_text = text.clone();
+                }
+
+                ui.label(text).on_hover_text("Red Green Blue Alpha");
+            },
+        );
+    }
+}
+
+/// Shows a slider for the alpha of the color, over a checkerboard.
+///
+/// Returns `true` on change.
+fn alpha_slider_ui(ui: &mut Ui, color: &mut Alpha<Srgb>) -> bool {
+    let previous = color.alpha;
+    let opaque = color.color;
+    color_slider_1d(ui, &mut color.alpha, 0.0, 1.0, |alpha| {
+        Alpha::new(opaque, alpha)
+    })
+    .on_hover_text("Alpha");
+    color_text_alpha_ui(ui, *color);
+    color.alpha != previous
+}
+
+/// Differences under this ΔE_OK are conversion noise rather than user edits.
+///
+/// It is well under the just noticeable difference, around 0.02.
+const SAME_COLOR_DELTA_E: f64 = 0.001;
+
+/// Whether `new` is close enough to `current` to ignore the change.
+fn is_same_color(current: Srgb, new: Srgb) -> bool {
+    delta_e_ok(current.into(), new.into()) < SAME_COLOR_DELTA_E
+}
+
+/// Stores `new` in `current`, unless it is conversion noise from a drag.
+///
+/// `exact` edits, typed in a field or stepped with the keyboard or the
+/// stepper buttons, are kept however small they are.
+///
+/// Returns `true` on change.
+fn apply_edit(current: &mut Srgb, new: Srgb, exact: bool) -> bool {
+    if *current == new || (!exact && is_same_color(*current, new)) {
+        return false;
+    }
+    *current = new;
+    true
+}
+
+fn exact_edit_id() -> Id {
+    Id::new("exact_edit")
+}
+
+/// Records that the value of a picker was typed or stepped this frame, see
+/// [`apply_edit`].
+fn mark_exact_edit(ui: &Ui) {
+    ui.data().insert_temp(exact_edit_id(), true);
+}
+
+/// Whether [`mark_exact_edit`] was called since the last call, and resets it.
+fn take_exact_edit(ui: &Ui) -> bool {
+    let mut data = ui.data();
+    let exact = data.get_temp(exact_edit_id()).unwrap_or(false);
+    data.remove::<bool>(exact_edit_id());
+    exact
+}
+
+/// Shows a picker working in the `C` color space, followed by the alpha
+/// slider if `with_alpha` is set, and a text field.
+///
+/// `picker` edits the color. Edits smaller than [`SAME_COLOR_DELTA_E`] are
+/// dropped, unless they are typed in a field or stepped. The text field keeps
+/// its state under `id_source`, so it must be unique in `ui`.
+///
+/// Returns `true` on change.
+fn picker_ui<C>(
+    ui: &mut Ui,
+    id_source: impl Hash + Debug,
+    current_color: &mut Alpha<Srgb>,
+    with_alpha: bool,
+    picker: impl FnOnce(&mut Ui, &mut C),
+) -> bool
+where
+    C: From<Srgb>,
+    Srgb: From<C>,
+{
+    let mut new_color = C::from(current_color.color);
+
+    let id = ui.make_persistent_id(id_source);
+
+    take_exact_edit(ui);
+    picker(ui, &mut new_color);
+
+    let exact = take_exact_edit(ui);
+    let mut changed = apply_edit(&mut current_color.color, Srgb::from(new_color), exact);
+
+    if with_alpha {
+        changed |= alpha_slider_ui(ui, current_color);
+        changed |= color_text_edit_alpha_ui(ui, id, current_color);
+    } else {
+        changed |= color_text_edit_ui(ui, id, &mut current_color.color);
+    }
+    changed
+}
+
+fn color_text_oklch_ui(ui: &mut Ui, color: impl Into<OkLCh>) {
+    let lch = color.into();
+    ui.allocate_ui_with_layout(
+        vec2(
+            2.0 * ui.spacing().slider_width,
+            ui.spacing().interact_size.y,
+        ),
+        Layout::left_to_right(Align::Center),
+        |ui| {
+            // CSS Color 4 notation, with the hue in degrees
+            let text = format!(
+                "oklch({:.4} {:.4} {:.2})",
+                lch.lightness,
+                lch.chroma,
+                lch.hue.to_degrees().rem_euclid(360.0)
+            );
+
+            if ui.button("📋").on_hover_text("Click to copy").clicked() {
+                ui.output().copied_text = text.clone();
+            }
+
+            ui.label(text).on_hover_text("Lightness Chroma Hue, OkLCh");
+        },
+    );
+}
+
 pub fn color_text_ui(ui: &mut Ui, color: impl Into<Srgb>) {
     let color = color.into();
     color_text_okhsl_ui(ui, color);
@@ -133,51 +438,221 @@ pub fn color_text_ui(ui: &mut Ui, color: impl Into<Srgb>) {
     color_text_rgb_hex_ui(ui, color);
 }
 
-fn color_slider_1d(
+/// Fraction of a slider range moved by an arrow key or a stepper button.
+const STEP: f64 = 0.01;
+/// Step with shift held.
+const COARSE_STEP: f64 = 0.1;
+/// Step with ctrl (⌘ on macOS) held.
+const FINE_STEP: f64 = 0.001;
+/// Fraction of a slider range moved by Page Up and Page Down.
+const PAGE_STEP: f64 = 0.1;
+
+fn step_fraction(modifiers: Modifiers) -> f64 {
+    if modifiers.shift {
+        COARSE_STEP
+    } else if modifiers.command {
+        FINE_STEP
+    } else {
+        STEP
+    }
+}
+
+/// Keys that move one axis of a focused slider, as `[decrease, increase]`
+/// pairs.
+struct AxisKeys {
+    step: [Key; 2],
+    page: Option<[Key; 2]>,
+    /// Whether Home and End go to the ends of the range
+    ends: bool,
+    /// Whether the value wraps around the range, for angles
+    wrap: bool,
+}
+
+const HORIZONTAL_KEYS: AxisKeys = AxisKeys {
+    step: [Key::ArrowLeft, Key::ArrowRight],
+    page: Some([Key::PageDown, Key::PageUp]),
+    ends: true,
+    wrap: false,
+};
+
+/// Moves `value` within `min..=max` with the keys pressed this frame.
+///
+/// Arrow keys move by [`STEP`] of the range, more with shift and less with
+/// ctrl (⌘ on macOS). The move is marked as an exact edit, see
+/// [`mark_exact_edit`].
+///
+/// Returns `true` on change.
+fn keyboard_ui(ui: &Ui, keys: &AxisKeys, value: &mut f64, min: f64, max: f64) -> bool {
+    let previous = *value;
+    {
+        let input = ui.input();
+        let presses = |[decrease, increase]: [Key; 2]| {
+            input.num_presses(increase) as f64 - input.num_presses(decrease) as f64
+        };
+        let fraction = presses(keys.step) * step_fraction(input.modifiers)
+            + keys.page.map_or(0.0, presses) * PAGE_STEP;
+
+        if fraction != 0.0 {
+            step_value(value, min, max, fraction, keys.wrap);
+        }
+        if keys.ends && input.key_pressed(Key::Home) {
+            *value = min;
+        } else if keys.ends && input.key_pressed(Key::End) {
+            *value = max;
+        }
+    }
+
+    let changed = *value != previous;
+    if changed {
+        mark_exact_edit(ui);
+    }
+    changed
+}
+
+/// Moves `value` by `fraction` of `min..=max`, clamping or wrapping it in the
+/// range.
+fn step_value(value: &mut f64, min: f64, max: f64, fraction: f64, wrap: bool) {
+    let span = max - min;
+    let moved = *value + fraction * span;
+    *value = if wrap {
+        min + (moved - min).rem_euclid(span)
+    } else {
+        moved.clamp(min, max)
+    };
+}
+
+/// Shows a small button that moves `value` by a step of `min..=max` in the
+/// direction of `sign`, as an exact edit.
+fn stepper_button(ui: &mut Ui, text: &str, sign: f64, value: &mut f64, range: SliderRange) {
+    let size = Vec2::splat(ui.spacing().interact_size.y);
+    let response = ui
+        .add_sized(size, Button::new(text))
+        .on_hover_text("Shift for a larger step, ctrl for a finer one");
+    if response.clicked() {
+        let fraction = sign * step_fraction(ui.input().modifiers);
+        step_value(value, range.min, range.max, fraction, range.wrap);
+        mark_exact_edit(ui);
+    }
+}
+
+/// Range of a 1D slider.
+#[derive(Clone, Copy)]
+struct SliderRange {
+    min: f64,
+    max: f64,
+    /// Whether the keyboard and the stepper buttons wrap around, for hues
+    wrap: bool,
+}
+
+impl SliderRange {
+    fn new(min: f64, max: f64) -> Self {
+        Self {
+            min,
+            max,
+            wrap: false,
+        }
+    }
+
+    /// A hue in radians.
+    fn hue() -> Self {
+        Self {
+            min: -consts::PI,
+            max: consts::PI,
+            wrap: true,
+        }
+    }
+}
+
+/// A horizontal slider, with stepper buttons on each side.
+///
+/// Once clicked, the slider also moves with the keyboard: left and right
+/// arrows, Page Down and Page Up, Home and End.
+fn color_slider_1d<T>(
     ui: &mut Ui,
     value: &mut f64,
     min: f64,
     max: f64,
-    color_at: impl Fn(f64) -> OkHsv,
-) -> Response {
-    #![allow(clippy::identity_op)]
+    color_at: impl Fn(f64) -> T,
+) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
+    slider_1d(ui, value, SliderRange::new(min, max), color_at)
+}
 
+/// Same as [`color_slider_1d`] for a hue in radians, wrapping around when
+/// moved with the keyboard or the stepper buttons.
+fn hue_slider_1d<T>(ui: &mut Ui, hue: &mut f64, color_at: impl Fn(f64) -> T) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
+    slider_1d(ui, hue, SliderRange::hue(), color_at)
+}
+
+fn slider_1d<T>(
+    ui: &mut Ui,
+    value: &mut f64,
+    range: SliderRange,
+    color_at: impl Fn(f64) -> T,
+) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
+    let width = 2.0 * ui.spacing().slider_width;
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        let bar_width = width - 2.0 * ui.spacing().interact_size.y;
+        stepper_button(ui, "−", -1.0, value, range);
+        let response = color_slider_1d_bar(ui, value, range, bar_width, color_at);
+        stepper_button(ui, "+", 1.0, value, range);
+        response
+    })
+    .inner
+}
+
+fn color_slider_1d_bar<T>(
+    ui: &mut Ui,
+    value: &mut f64,
+    SliderRange { min, max, wrap }: SliderRange,
+    width: f32,
+    color_at: impl Fn(f64) -> T,
+) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
     let span = max - min;
 
-    let desired_size = vec2(
-        2.0 * ui.spacing().slider_width,
-        ui.spacing().interact_size.y,
-    );
-    let (rect, response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
+    let desired_size = vec2(width, ui.spacing().interact_size.y);
+    let (rect, mut response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
 
+    if response.drag_started() || response.clicked() {
+        response.request_focus();
+    }
     if let Some(mpos) = response.interact_pointer_pos() {
         *value = min + span * remap_clamp(mpos.x, rect.left()..=rect.right(), 0.0..=1.0) as f64;
     }
+    let keys = AxisKeys {
+        wrap,
+        ..HORIZONTAL_KEYS
+    };
+    if response.has_focus() && keyboard_ui(ui, &keys, value, min, max) {
+        response.mark_changed();
+    }
 
     if ui.is_rect_visible(rect) {
         let visuals = ui.style().interact(&response);
 
         background_checkers(ui.painter(), rect); // for alpha:
 
-        {
-            // fill color:
-            let mut mesh = Mesh::default();
-            for i in 0..=N {
-                let t = min + (i as f64 * span / (N as f64));
-                let color = color_at(t);
-                let x = lerp(
-                    rect.left()..=rect.right(),
-                    (t as f32 - min as f32) / span as f32,
-                );
-                mesh.colored_vertex(pos2(x, rect.top()), color.into());
-                mesh.colored_vertex(pos2(x, rect.bottom()), color.into());
-                if i < N {
-                    mesh.add_triangle(2 * i + 0, 2 * i + 1, 2 * i + 2);
-                    mesh.add_triangle(2 * i + 1, 2 * i + 2, 2 * i + 3);
-                }
-            }
-            ui.painter().add(Shape::mesh(mesh));
-        }
+        // fill color:
+        let preview = CvdPreview::load(ui);
+        horizontal_gradient(ui.painter(), rect, |t| {
+            preview.apply(color_at(min + t * span))
+        });
 
         ui.painter().rect_stroke(rect, 0.0, visuals.bg_stroke); // outline
 
@@ -188,7 +663,7 @@ fn color_slider_1d(
                 (*value as f32 - min as f32) / span as f32,
             );
             let r = rect.height() / 4.0;
-            let picked_color = color_at(*value);
+            let picked_color = preview.apply(color_at(*value));
             ui.painter().add(Shape::convex_polygon(
                 vec![
                     pos2(x, rect.center().y),   // tip
@@ -204,44 +679,78 @@ fn color_slider_1d(
     response
 }
 
-fn color_slider_vertical_1d(
+/// Same as [`hue_slider_1d`], going from -π at the top to π at the bottom,
+/// with the up and down arrows.
+fn hue_slider_vertical_1d<T>(ui: &mut Ui, hue: &mut f64, color_at: impl Fn(f64) -> T) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
+    let range = SliderRange::hue();
+    let height = 2.0 * ui.spacing().slider_width;
+    ui.vertical(|ui| {
+        ui.spacing_mut().item_spacing.y = 0.0;
+        let bar_height = height - 2.0 * ui.spacing().interact_size.y;
+        stepper_button(ui, "−", -1.0, hue, range);
+        let response = color_slider_vertical_1d_bar(ui, hue, range, bar_height, color_at);
+        stepper_button(ui, "+", 1.0, hue, range);
+        response
+    })
+    .inner
+}
+
+fn color_slider_vertical_1d_bar<T>(
     ui: &mut Ui,
     value: &mut f64,
-    min: f64,
-    max: f64,
-    color_at: impl Fn(f64) -> OkHsv,
-) -> Response {
+    SliderRange { min, max, wrap }: SliderRange,
+    height: f32,
+    color_at: impl Fn(f64) -> T,
+) -> Response
+where
+    T: Into<Color32> + Copy,
+    eframe::egui::Rgba: std::convert::From<T>,
+{
     #![allow(clippy::identity_op)]
 
     let span = max - min;
 
-    let desired_size = vec2(
-        ui.spacing().interact_size.y,
-        2.0 * ui.spacing().slider_width,
-    );
-    let (rect, response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
+    let desired_size = vec2(ui.spacing().interact_size.y, height);
+    let (rect, mut response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
 
+    if response.drag_started() || response.clicked() {
+        response.request_focus();
+    }
     if let Some(mpos) = response.interact_pointer_pos() {
         *value = min + span * remap_clamp(mpos.y, rect.top()..=rect.bottom(), 0.0..=1.0) as f64;
     }
+    let keys = AxisKeys {
+        step: [Key::ArrowUp, Key::ArrowDown],
+        page: Some([Key::PageUp, Key::PageDown]),
+        ends: true,
+        wrap,
+    };
+    if response.has_focus() && keyboard_ui(ui, &keys, value, min, max) {
+        response.mark_changed();
+    }
 
     if ui.is_rect_visible(rect) {
         let visuals = ui.style().interact(&response);
 
         background_checkers(ui.painter(), rect); // for alpha:
+        let preview = CvdPreview::load(ui);
 
         {
             // fill color:
             let mut mesh = Mesh::default();
             for i in 0..=N {
                 let t = min + (i as f64 * span / (N as f64));
-                let color = color_at(t);
+                let color = preview.apply(color_at(t));
                 let y = lerp(
                     rect.top()..=rect.bottom(),
                     (t as f32 - min as f32) / span as f32,
                 );
-                mesh.colored_vertex(pos2(rect.left(), y), color.into());
-                mesh.colored_vertex(pos2(rect.right(), y), color.into());
+                mesh.colored_vertex(pos2(rect.left(), y), color);
+                mesh.colored_vertex(pos2(rect.right(), y), color);
                 if i < N {
                     mesh.add_triangle(2 * i + 0, 2 * i + 1, 2 * i + 2);
                     mesh.add_triangle(2 * i + 1, 2 * i + 2, 2 * i + 3);
@@ -259,7 +768,7 @@ fn color_slider_vertical_1d(
                 (*value as f32 - min as f32) / span as f32,
             );
             let r = rect.width() / 4.0;
-            let picked_color = color_at(*value);
+            let picked_color = preview.apply(color_at(*value));
             ui.painter().add(Shape::convex_polygon(
                 vec![
                     pos2(rect.center().x, y), // tip
@@ -275,10 +784,17 @@ fn color_slider_vertical_1d(
     response
 }
 
+/// A 2D slider, painted with a mesh of `resolution`² cells.
+///
+/// `color_at` must only depend on its arguments and on `fixed`, the value of
+/// the other axis of the color space: the mesh is cached until `fixed`
+/// changes.
 fn color_slider_2d<T>(
     ui: &mut Ui,
     x_value: &mut f64,
     y_value: &mut f64,
+    fixed: f64,
+    resolution: u32,
     color_at: impl Fn(f64, f64) -> T,
 ) -> Response
 where
@@ -286,35 +802,57 @@ where
     eframe::egui::Rgba: std::convert::From<T>,
 {
     let desired_size = Vec2::splat(2.0 * ui.spacing().slider_width);
-    let (rect, response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
+    let (rect, mut response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
 
+    if response.drag_started() || response.clicked() {
+        response.request_focus();
+    }
     if let Some(mpos) = response.interact_pointer_pos() {
         *x_value = remap_clamp(mpos.x, rect.left()..=rect.right(), 0.0..=1.0) as f64;
         *y_value = remap_clamp(mpos.y, rect.bottom()..=rect.top(), 0.0..=1.0) as f64;
     }
+    if response.has_focus() {
+        let x_keys = AxisKeys {
+            page: None,
+            ..HORIZONTAL_KEYS
+        };
+        let y_keys = AxisKeys {
+            step: [Key::ArrowDown, Key::ArrowUp],
+            page: Some([Key::PageDown, Key::PageUp]),
+            ends: false,
+            wrap: false,
+        };
+        let moved_x = keyboard_ui(ui, &x_keys, x_value, 0.0, 1.0);
+        let moved_y = keyboard_ui(ui, &y_keys, y_value, 0.0, 1.0);
+        if moved_x || moved_y {
+            response.mark_changed();
+        }
+    }
 
     if ui.is_rect_visible(rect) {
         let visuals = ui.style().interact(&response);
-        let mut mesh = Mesh::default();
-
-        for xi in 0..=N {
-            for yi in 0..=N {
-                let xt = xi as f64 / (N as f64);
-                let yt = yi as f64 / (N as f64);
-                let color = color_at(xt, yt);
-                let x = lerp(rect.left()..=rect.right(), xt as f32);
-                let y = lerp(rect.bottom()..=rect.top(), yt as f32);
-                mesh.colored_vertex(pos2(x, y), color.into());
-
-                if xi < N && yi < N {
-                    let x_offset = 1;
-                    let y_offset = N + 1;
-                    let tl = yi * y_offset + xi;
-                    mesh.add_triangle(tl, tl + x_offset, tl + y_offset);
-                    mesh.add_triangle(tl + x_offset, tl + y_offset, tl + y_offset + x_offset);
+        let mesh = cached_mesh(ui, response.id, fixed, rect, resolution, || {
+            let mut mesh = Mesh::default();
+            for xi in 0..=resolution {
+                for yi in 0..=resolution {
+                    let xt = f64::from(xi) / f64::from(resolution);
+                    let yt = f64::from(yi) / f64::from(resolution);
+                    let color = color_at(xt, yt);
+                    let x = lerp(rect.left()..=rect.right(), xt as f32);
+                    let y = lerp(rect.bottom()..=rect.top(), yt as f32);
+                    mesh.colored_vertex(pos2(x, y), color.into());
+
+                    if xi < resolution && yi < resolution {
+                        let x_offset = 1;
+                        let y_offset = resolution + 1;
+                        let tl = yi * y_offset + xi;
+                        mesh.add_triangle(tl, tl + x_offset, tl + y_offset);
+                        mesh.add_triangle(tl + x_offset, tl + y_offset, tl + y_offset + x_offset);
+                    }
                 }
             }
-        }
+            mesh
+        });
         ui.painter().add(Shape::mesh(mesh)); // fill
 
         ui.painter().rect_stroke(rect, 0.0, visuals.bg_stroke); // outline
@@ -322,11 +860,11 @@ where
         // Show where the slider is at:
         let x = lerp(rect.left()..=rect.right(), *x_value as f32);
         let y = lerp(rect.bottom()..=rect.top(), *y_value as f32);
-        let picked_color = color_at(*x_value, *y_value);
+        let picked_color = CvdPreview::load(ui).apply(color_at(*x_value, *y_value));
         ui.painter().add(epaint::CircleShape {
             center: pos2(x, y),
             radius: rect.width() / 12.0,
-            fill: picked_color.into(),
+            fill: picked_color,
             stroke: Stroke::new(visuals.fg_stroke.width, contrast_color(picked_color)),
         });
     }
@@ -334,10 +872,15 @@ where
     response
 }
 
+/// A polar 2D slider, painted with a mesh of `resolution`² cells.
+///
+/// Like in [`color_slider_2d`], the mesh is cached until `fixed` changes.
 fn color_slider_circle<T>(
     ui: &mut Ui,
     r: &mut f64,
     angle: &mut f64,
+    fixed: f64,
+    resolution: u32,
     color_at: impl Fn(f64, f64) -> T,
 ) -> Response
 where
@@ -345,48 +888,73 @@ where
     eframe::egui::Rgba: std::convert::From<T>,
 {
     let desired_size = Vec2::splat(2.0 * ui.spacing().slider_width);
-    let (rect, response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
+    let (rect, mut response) = ui.allocate_at_least(desired_size, Sense::click_and_drag());
     let r_max = rect.width().min(rect.height()) / 2.0;
 
+    if response.drag_started() || response.clicked() {
+        response.request_focus();
+    }
     if let Some(mpos) = response.interact_pointer_pos() {
         let current_pos = mpos - rect.center();
         let current_r = current_pos.length();
         *r = remap_clamp(current_r.into(), 0.0..=r_max.into(), 0.0..=1.0);
         // y goes down, so we flip the angle to get the
         // trigonometry normal direction
-        *angle = (-1.0 * current_pos.angle()).into();
+        *angle = (-current_pos.angle()).into();
+    }
+    if response.has_focus() {
+        // Left turns counterclockwise, like the angle
+        let angle_keys = AxisKeys {
+            step: [Key::ArrowRight, Key::ArrowLeft],
+            page: None,
+            ends: false,
+            wrap: true,
+        };
+        let r_keys = AxisKeys {
+            step: [Key::ArrowDown, Key::ArrowUp],
+            page: Some([Key::PageDown, Key::PageUp]),
+            ends: true,
+            wrap: false,
+        };
+        let moved_angle = keyboard_ui(ui, &angle_keys, angle, -consts::PI, consts::PI);
+        let moved_r = keyboard_ui(ui, &r_keys, r, 0.0, 1.0);
+        if moved_angle || moved_r {
+            response.mark_changed();
+        }
     }
 
     if ui.is_rect_visible(rect) {
         let visuals = ui.style().interact(&response);
-        let mut mesh = Mesh::default();
-
-        for ri in 0..=N {
-            for anglei in 0..=N {
-                let rt = ri as f64 / (N as f64);
-                let anglet = 2.0 * std::f64::consts::PI * anglei as f64 / (N as f64);
-                let color = color_at(rt, anglet);
-                let (x_norm, y_norm) = (
-                    (rt * anglet.cos() + 1.0) / 2.0,
-                    (rt * anglet.sin() + 1.0) / 2.0,
-                );
-                let x = lerp(rect.left()..=rect.right(), x_norm as f32);
-                let y = lerp(rect.bottom()..=rect.top(), y_norm as f32);
-                mesh.colored_vertex(pos2(x, y), color.into());
-
-                if ri < N && anglei < N {
-                    let r_offset = 1;
-                    let angle_offset = N + 1;
-                    let tl = anglei * angle_offset + ri;
-                    mesh.add_triangle(tl, tl + r_offset, tl + angle_offset);
-                    mesh.add_triangle(
-                        tl + r_offset,
-                        tl + angle_offset,
-                        tl + angle_offset + r_offset,
+        let mesh = cached_mesh(ui, response.id, fixed, rect, resolution, || {
+            let mut mesh = Mesh::default();
+            for ri in 0..=resolution {
+                for anglei in 0..=resolution {
+                    let rt = f64::from(ri) / f64::from(resolution);
+                    let anglet = 2.0 * consts::PI * f64::from(anglei) / f64::from(resolution);
+                    let color = color_at(rt, anglet);
+                    let (x_norm, y_norm) = (
+                        (rt * anglet.cos() + 1.0) / 2.0,
+                        (rt * anglet.sin() + 1.0) / 2.0,
                     );
+                    let x = lerp(rect.left()..=rect.right(), x_norm as f32);
+                    let y = lerp(rect.bottom()..=rect.top(), y_norm as f32);
+                    mesh.colored_vertex(pos2(x, y), color.into());
+
+                    if ri < resolution && anglei < resolution {
+                        let r_offset = 1;
+                        let angle_offset = resolution + 1;
+                        let tl = anglei * angle_offset + ri;
+                        mesh.add_triangle(tl, tl + r_offset, tl + angle_offset);
+                        mesh.add_triangle(
+                            tl + r_offset,
+                            tl + angle_offset,
+                            tl + angle_offset + r_offset,
+                        );
+                    }
                 }
             }
-        }
+            mesh
+        });
         ui.painter().add(Shape::mesh(mesh)); // fill
 
         ui.painter().rect_stroke(rect, 0.0, visuals.bg_stroke); // outline
@@ -399,11 +967,11 @@ where
         );
         let x = lerp(rect.left()..=rect.right(), x_norm as f32);
         let y = lerp(rect.bottom()..=rect.top(), y_norm as f32);
-        let picked_color = color_at(*r, *angle);
+        let picked_color = CvdPreview::load(ui).apply(color_at(*r, *angle));
         ui.painter().add(epaint::CircleShape {
             center: pos2(x, y),
             radius: rect.width() / 12.0,
-            fill: picked_color.into(),
+            fill: picked_color,
             stroke: Stroke::new(visuals.fg_stroke.width, contrast_color(picked_color)),
         });
     }
@@ -411,6 +979,81 @@ where
     response
 }
 
+/// The mesh of a 2D slider, kept in egui memory between frames.
+#[derive(Clone)]
+struct CachedMesh {
+    fixed: f64,
+    rect: Rect,
+    resolution: u32,
+    preview: CvdPreview,
+    mesh: Mesh,
+}
+
+/// Returns the mesh of the slider `id`, only calling `build` when `fixed`,
+/// `rect`, `resolution` or the [`CvdPreview`] changed since the last frame.
+///
+/// Building the mesh converts a color for each vertex, which is too slow to
+/// do on every repaint. The preview gets applied to the built mesh.
+fn cached_mesh(
+    ui: &Ui,
+    id: Id,
+    fixed: f64,
+    rect: Rect,
+    resolution: u32,
+    build: impl FnOnce() -> Mesh,
+) -> Mesh {
+    let id = id.with("mesh");
+    let preview = CvdPreview::load(ui);
+    let cached = ui.data().get_temp::<CachedMesh>(id);
+    match cached {
+        Some(cached)
+            if cached.fixed == fixed
+                && cached.rect == rect
+                && cached.resolution == resolution
+                && cached.preview == preview =>
+        {
+            cached.mesh
+        }
+        _ => {
+            let mut mesh = build();
+            if preview.0.is_some() {
+                for vertex in &mut mesh.vertices {
+                    vertex.color = preview.apply(vertex.color);
+                }
+            }
+            ui.data().insert_temp(
+                id,
+                CachedMesh {
+                    fixed,
+                    rect,
+                    resolution,
+                    preview,
+                    mesh: mesh.clone(),
+                },
+            );
+            mesh
+        }
+    }
+}
+
+/// Paints a horizontal gradient over `rect`, `color_at` taking the
+/// position from 0 (left) to 1 (right).
+fn horizontal_gradient(painter: &Painter, rect: Rect, color_at: impl Fn(f64) -> Color32) {
+    let mut mesh = Mesh::default();
+    for i in 0..=N {
+        let t = f64::from(i) / f64::from(N);
+        let color = color_at(t);
+        let x = lerp(rect.left()..=rect.right(), t as f32);
+        mesh.colored_vertex(pos2(x, rect.top()), color);
+        mesh.colored_vertex(pos2(x, rect.bottom()), color);
+        if i < N {
+            mesh.add_triangle(2 * i, 2 * i + 1, 2 * i + 2);
+            mesh.add_triangle(2 * i + 1, 2 * i + 2, 2 * i + 3);
+        }
+    }
+    painter.add(Shape::mesh(mesh));
+}
+
 fn background_checkers(painter: &Painter, rect: Rect) {
     let rect = rect.shrink(0.5); // Small hack to avoid the checkers from peeking through the sides
     if !rect.is_positive() {
@@ -440,10 +1083,82 @@ fn background_checkers(painter: &Painter, rect: Rect) {
     painter.add(Shape::mesh(mesh));
 }
 
+/// Returns black or white, whichever contrasts the most with `color`.
 fn contrast_color(color: impl Into<Rgba>) -> Color32 {
-    if color.into().intensity() < 0.5 {
+    let rgba = color.into();
+    let rgb = Srgb::from(LinSrgb {
+        red: rgba.r().into(),
+        green: rgba.g().into(),
+        blue: rgba.b().into(),
+    });
+    let white = Srgb {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+    let black = Srgb {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+
+    if wcag_contrast_ratio(rgb, white) > wcag_contrast_ratio(rgb, black) {
         Color32::WHITE
     } else {
         Color32::BLACK
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_edits() {
+        let start = OkHsv {
+            hue: 1.0,
+            saturation: 0.5,
+            value: 0.5,
+        };
+        let edited = Srgb::from(OkHsv {
+            saturation: 0.501,
+            ..start
+        });
+        let mut current = Srgb::from(start);
+        assert!(is_same_color(current, edited));
+
+        // Dropped as noise when dragged, kept when typed
+        assert!(!apply_edit(&mut current, edited, false));
+        assert_eq!(current, Srgb::from(start));
+        assert!(apply_edit(&mut current, edited, true));
+        assert_eq!(current, edited);
+        assert!((OkHsv::from(current).saturation - 0.501).abs() < 1e-6);
+
+        assert!(!apply_edit(&mut current, edited, true));
+    }
+
+    #[test]
+    fn fine_steps() {
+        let start = OkHsv {
+            hue: 1.0,
+            saturation: 0.5,
+            value: 0.5,
+        };
+        // Each frame starts again from the stored color
+        let mut current = Srgb::from(start);
+        for _ in 0..10 {
+            let mut okhsv = OkHsv::from(current);
+            step_value(&mut okhsv.saturation, 0.0, 1.0, FINE_STEP, false);
+            assert!(apply_edit(&mut current, okhsv.into(), true));
+        }
+        let saturation = OkHsv::from(current).saturation;
+        assert!((saturation - 0.51).abs() < 1e-4, "{saturation}");
+
+        let range = SliderRange::hue();
+        let mut hue = consts::PI - 0.01;
+        step_value(&mut hue, range.min, range.max, COARSE_STEP, range.wrap);
+        assert!((hue - (-consts::PI - 0.01 + 0.2 * consts::PI)).abs() < 1e-9);
+        step_value(&mut hue, range.min, range.max, -COARSE_STEP, range.wrap);
+        assert!((hue - (consts::PI - 0.01)).abs() < 1e-9);
+    }
+}
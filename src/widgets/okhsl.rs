@@ -2,50 +2,73 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::f64::consts::PI;
-
 use eframe::{
     egui::{color_picker::show_color, vec2, Layout, Ui},
     emath::Align,
 };
 
 use super::{
-    color_slider_1d, color_slider_circle, color_slider_vertical_1d, color_text_okhsl_ui,
-    color_text_rgb_hex_ui,
+    color_slider_1d, color_slider_circle, color_text_rgb_hex_ui, components,
+    hue_slider_vertical_1d, mark_exact_edit, picker_ui, CvdPreview, DEFAULT_MESH_RESOLUTION,
 };
-use crate::colors::{OkHsl, OkHsv, Srgb};
+use crate::colors::{Alpha, OkHsl, OkHsv, Srgb};
 
-//// Shows a color picker where the user can change the given [`OkHsl`] color.
+/// Shows a color picker where the user can change the given [`OkHsl`] color.
+///
+/// The text field under the picker accepts any notation supported by
+/// [`crate::colors::parse`].
 ///
 /// Returns `true` on change.
 pub fn color_picker_circle(ui: &mut Ui, current_color: &mut Srgb) -> bool {
-    let mut new_okhsl = OkHsl::from(*current_color);
+    color_picker_circle_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
+}
 
-    color_picker_circle_impl(ui, &mut new_okhsl);
+/// Same as [`color_picker_circle`], painted with `resolution`² cells instead of
+/// [`DEFAULT_MESH_RESOLUTION`].
+pub fn color_picker_circle_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Srgb,
+    resolution: u32,
+) -> bool {
+    let mut color = Alpha::opaque(*current_color);
+    let changed = picker_ui(ui, "okhsl_picker_circle", &mut color, false, |ui, okhsl| {
+        color_picker_circle_impl(ui, okhsl, resolution)
+    });
+    *current_color = color.color;
+    changed
+}
 
-    let new_color = Srgb::from(new_okhsl);
-    let sq_distance = (current_color.red - new_color.red).powi(2)
-        + (current_color.green - new_color.green).powi(2)
-        + (current_color.blue - new_color.blue).powi(2);
-    let sq_norm =
-        current_color.red.powi(2) + current_color.green.powi(2) + current_color.blue.powi(2);
+/// Same as [`color_picker_circle`], with a slider for the alpha.
+pub fn color_picker_circle_alpha(ui: &mut Ui, current_color: &mut Alpha<Srgb>) -> bool {
+    color_picker_circle_alpha_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
+}
 
-    if sq_norm.is_normal() && sq_distance / sq_norm < 0.001 {
-        false
-    } else {
-        *current_color = new_color;
-        true
-    }
+/// Same as [`color_picker_circle_with_resolution`], with a slider for the alpha.
+pub fn color_picker_circle_alpha_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Alpha<Srgb>,
+    resolution: u32,
+) -> bool {
+    picker_ui(
+        ui,
+        "okhsl_picker_circle",
+        current_color,
+        true,
+        |ui, okhsl| color_picker_circle_impl(ui, okhsl, resolution),
+    )
 }
 
-fn color_picker_circle_impl(ui: &mut Ui, okhsl: &mut OkHsl) {
+fn color_picker_circle_impl(ui: &mut Ui, okhsl: &mut OkHsl, resolution: u32) {
     let current_color_size = vec2(
         2.0 * ui.spacing().slider_width,
         2.0 * ui.spacing().interact_size.y,
     );
-    show_color(ui, *okhsl, current_color_size).on_hover_text("Selected color");
+    show_color(ui, CvdPreview::load(ui).apply(*okhsl), current_color_size)
+        .on_hover_text("Selected color");
 
-    color_text_okhsl_ui(ui, *okhsl);
+    if components::okhsl_fields_ui(ui, okhsl) {
+        mark_exact_edit(ui);
+    }
     color_text_rgb_hex_ui(ui, *okhsl);
 
     let current = *okhsl;
@@ -63,13 +86,20 @@ fn color_picker_circle_impl(ui: &mut Ui, okhsl: &mut OkHsl) {
         ),
         Layout::left_to_right(Align::Center),
         |ui| {
-            color_slider_circle(ui, saturation, hue, |saturation, hue| OkHsl {
-                saturation: saturation as f64,
-                hue: hue as f64,
-                ..current
-            });
+            color_slider_circle(
+                ui,
+                saturation,
+                hue,
+                current.lightness,
+                resolution,
+                |saturation, hue| OkHsl {
+                    saturation,
+                    hue,
+                    ..current
+                },
+            );
 
-            color_slider_vertical_1d(ui, hue, -PI, PI, |hue| {
+            hue_slider_vertical_1d(ui, hue, |hue| {
                 OkHsv::from(Srgb::from(OkHsl { hue, ..current }))
             })
             .on_hover_text("Hue");
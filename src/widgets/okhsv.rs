@@ -2,69 +2,111 @@
 //
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::f64::consts::PI;
-
 use eframe::egui::{color_picker::show_color, vec2, Ui};
 
 use super::{
-    color_slider_1d, color_slider_2d, color_slider_circle, color_text_okhsv_ui,
-    color_text_rgb_hex_ui,
+    color_slider_1d, color_slider_2d, color_slider_circle, color_text_rgb_hex_ui, components,
+    hue_slider_1d, mark_exact_edit, picker_ui, CvdPreview, DEFAULT_MESH_RESOLUTION,
 };
-use crate::colors::{OkHsv, Srgb};
-//// Shows a color picker where the user can change the given [`OkHsv`] color.
+use crate::colors::{Alpha, OkHsv, Srgb};
+
+/// Shows a color picker where the user can change the given [`OkHsv`] color.
+///
+/// The text field under the picker accepts any notation supported by
+/// [`crate::colors::parse`].
 ///
 /// Returns `true` on change.
 pub fn color_picker_2d(ui: &mut Ui, current_color: &mut Srgb) -> bool {
-    let mut new_okhsv = OkHsv::from(*current_color);
-
-    color_picker_2d_impl(ui, &mut new_okhsv);
-
-    let new_color = Srgb::from(new_okhsv);
-    let sq_distance = (current_color.red - new_color.red).powi(2)
-        + (current_color.green - new_color.green).powi(2)
-        + (current_color.blue - new_color.blue).powi(2);
-    let sq_norm =
-        current_color.red.powi(2) + current_color.green.powi(2) + current_color.blue.powi(2);
-
-    if sq_norm.is_normal() && sq_distance / sq_norm < 0.001 {
-        false
-    } else {
-        *current_color = new_color;
-        true
-    }
+    color_picker_2d_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
+}
+
+/// Same as [`color_picker_2d`], painted with `resolution`² cells instead of
+/// [`DEFAULT_MESH_RESOLUTION`].
+pub fn color_picker_2d_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Srgb,
+    resolution: u32,
+) -> bool {
+    let mut color = Alpha::opaque(*current_color);
+    let changed = picker_ui(ui, "okhsv_picker_2d", &mut color, false, |ui, okhsv| {
+        color_picker_2d_impl(ui, okhsv, resolution)
+    });
+    *current_color = color.color;
+    changed
+}
+
+/// Same as [`color_picker_2d`], with a slider for the alpha.
+pub fn color_picker_2d_alpha(ui: &mut Ui, current_color: &mut Alpha<Srgb>) -> bool {
+    color_picker_2d_alpha_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
 }
 
-//// Shows a color picker where the user can change the given [`OkHsv`] color.
+/// Same as [`color_picker_2d_with_resolution`], with a slider for the alpha.
+pub fn color_picker_2d_alpha_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Alpha<Srgb>,
+    resolution: u32,
+) -> bool {
+    picker_ui(ui, "okhsv_picker_2d", current_color, true, |ui, okhsv| {
+        color_picker_2d_impl(ui, okhsv, resolution)
+    })
+}
+
+/// Shows a color picker where the user can change the given [`OkHsv`] color.
+///
+/// The text field under the picker accepts any notation supported by
+/// [`crate::colors::parse`].
 ///
 /// Returns `true` on change.
 pub fn color_picker_circle(ui: &mut Ui, current_color: &mut Srgb) -> bool {
-    let mut new_okhsv = OkHsv::from(*current_color);
-
-    color_picker_circle_impl(ui, &mut new_okhsv);
-
-    let new_color = Srgb::from(new_okhsv);
-    let sq_distance = (current_color.red - new_color.red).powi(2)
-        + (current_color.green - new_color.green).powi(2)
-        + (current_color.blue - new_color.blue).powi(2);
-    let sq_norm =
-        current_color.red.powi(2) + current_color.green.powi(2) + current_color.blue.powi(2);
-
-    if sq_norm.is_normal() && sq_distance / sq_norm < 0.001 {
-        false
-    } else {
-        *current_color = new_color;
-        true
-    }
+    color_picker_circle_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
 }
 
-fn color_picker_2d_impl(ui: &mut Ui, okhsv: &mut OkHsv) {
+/// Same as [`color_picker_circle`], painted with `resolution`² cells instead of
+/// [`DEFAULT_MESH_RESOLUTION`].
+pub fn color_picker_circle_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Srgb,
+    resolution: u32,
+) -> bool {
+    let mut color = Alpha::opaque(*current_color);
+    let changed = picker_ui(ui, "okhsv_picker_circle", &mut color, false, |ui, okhsv| {
+        color_picker_circle_impl(ui, okhsv, resolution)
+    });
+    *current_color = color.color;
+    changed
+}
+
+/// Same as [`color_picker_circle`], with a slider for the alpha.
+pub fn color_picker_circle_alpha(ui: &mut Ui, current_color: &mut Alpha<Srgb>) -> bool {
+    color_picker_circle_alpha_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
+}
+
+/// Same as [`color_picker_circle_with_resolution`], with a slider for the alpha.
+pub fn color_picker_circle_alpha_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Alpha<Srgb>,
+    resolution: u32,
+) -> bool {
+    picker_ui(
+        ui,
+        "okhsv_picker_circle",
+        current_color,
+        true,
+        |ui, okhsv| color_picker_circle_impl(ui, okhsv, resolution),
+    )
+}
+
+fn color_picker_2d_impl(ui: &mut Ui, okhsv: &mut OkHsv, resolution: u32) {
     let current_color_size = vec2(
         2.0 * ui.spacing().slider_width,
         2.0 * ui.spacing().interact_size.y,
     );
-    show_color(ui, *okhsv, current_color_size).on_hover_text("Selected color");
+    show_color(ui, CvdPreview::load(ui).apply(*okhsv), current_color_size)
+        .on_hover_text("Selected color");
 
-    color_text_okhsv_ui(ui, *okhsv);
+    if components::okhsv_fields_ui(ui, okhsv) {
+        mark_exact_edit(ui);
+    }
     color_text_rgb_hex_ui(ui, *okhsv);
 
     let current = *okhsv;
@@ -75,19 +117,26 @@ fn color_picker_2d_impl(ui: &mut Ui, okhsv: &mut OkHsv) {
         value,
     } = okhsv;
 
-    color_slider_1d(ui, hue, -PI, PI, |hue| OkHsv {
+    hue_slider_1d(ui, hue, |hue| OkHsv {
         hue,
         saturation: 1.0,
         value: 1.0,
     })
     .on_hover_text("Hue fully saturated");
-    color_slider_1d(ui, hue, -PI, PI, |hue| OkHsv { hue, ..current }).on_hover_text("Hue");
+    hue_slider_1d(ui, hue, |hue| OkHsv { hue, ..current }).on_hover_text("Hue");
 
-    color_slider_2d(ui, value, saturation, |value, saturation| OkHsv {
-        saturation: saturation as f64,
-        value: value as f64,
-        ..current
-    });
+    color_slider_2d(
+        ui,
+        value,
+        saturation,
+        current.hue,
+        resolution,
+        |value, saturation| OkHsv {
+            saturation,
+            value,
+            ..current
+        },
+    );
 
     if true {
         color_slider_1d(ui, saturation, 0.0, 1.0, |saturation| OkHsv {
@@ -103,14 +152,17 @@ fn color_picker_2d_impl(ui: &mut Ui, okhsv: &mut OkHsv) {
     }
 }
 
-fn color_picker_circle_impl(ui: &mut Ui, okhsv: &mut OkHsv) {
+fn color_picker_circle_impl(ui: &mut Ui, okhsv: &mut OkHsv, resolution: u32) {
     let current_color_size = vec2(
         2.0 * ui.spacing().slider_width,
         2.0 * ui.spacing().interact_size.y,
     );
-    show_color(ui, *okhsv, current_color_size).on_hover_text("Selected color");
+    show_color(ui, CvdPreview::load(ui).apply(*okhsv), current_color_size)
+        .on_hover_text("Selected color");
 
-    color_text_okhsv_ui(ui, *okhsv);
+    if components::okhsv_fields_ui(ui, okhsv) {
+        mark_exact_edit(ui);
+    }
     color_text_rgb_hex_ui(ui, *okhsv);
 
     let current = *okhsv;
@@ -121,13 +173,20 @@ fn color_picker_circle_impl(ui: &mut Ui, okhsv: &mut OkHsv) {
         value,
     } = okhsv;
 
-    color_slider_1d(ui, hue, -PI, PI, |hue| OkHsv { hue, ..current }).on_hover_text("Hue");
+    hue_slider_1d(ui, hue, |hue| OkHsv { hue, ..current }).on_hover_text("Hue");
 
-    color_slider_circle(ui, saturation, hue, |saturation, hue| OkHsv {
-        hue: hue as f64,
-        saturation: saturation as f64,
-        ..current
-    });
+    color_slider_circle(
+        ui,
+        saturation,
+        hue,
+        current.value,
+        resolution,
+        |saturation, hue| OkHsv {
+            hue,
+            saturation,
+            ..current
+        },
+    );
 
     if true {
         color_slider_1d(ui, value, 0.0, 1.0, |value| OkHsv { value, ..current })
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{color_picker::show_color, lerp, pos2, vec2, Color32, Shape, Ui};
+
+use super::{
+    apply_edit, color_slider_1d, color_slider_2d, color_text_edit_ui, color_text_oklch_ui,
+    color_text_rgb_hex_ui, hue_slider_1d, take_exact_edit, CvdPreview, DEFAULT_MESH_RESOLUTION, N,
+};
+use crate::colors::{conversions::max_chroma, rgb::RgbSpace, OkLCh, OkLab, Srgb};
+
+/// Upper bound of the chroma axis, slightly above the highest chroma
+/// reachable in sRGB.
+const CHROMA_MAX: f64 = 0.33;
+
+/// Color used for the parts of the sliders that are out of the sRGB gamut.
+const OUT_OF_GAMUT: Color32 = Color32::from_gray(40);
+
+/// Shows a color picker where the user can change the given [`OkLCh`] color.
+///
+/// The 2D plane shows lightness and chroma at the current hue, with the
+/// colors that don't fit in sRGB masked out. Chroma is always clamped to the
+/// gamut boundary, so the resulting color is never out of gamut.
+///
+/// The text field under the picker accepts any notation supported by
+/// [`crate::colors::parse`].
+///
+/// Returns `true` on change.
+pub fn color_picker_2d(ui: &mut Ui, current_color: &mut Srgb) -> bool {
+    color_picker_2d_with_resolution(ui, current_color, DEFAULT_MESH_RESOLUTION)
+}
+
+/// Same as [`color_picker_2d`], painted with `resolution`² cells instead of
+/// [`DEFAULT_MESH_RESOLUTION`].
+pub fn color_picker_2d_with_resolution(
+    ui: &mut Ui,
+    current_color: &mut Srgb,
+    resolution: u32,
+) -> bool {
+    let id = ui.make_persistent_id("oklch_picker_2d");
+    // Grays have no hue, so the last edited OkLCh is kept as long as it still
+    // gives the current color, instead of snapping the hue back to 0
+    let mut new_oklch = match ui.data().get_temp::<(Srgb, OkLCh)>(id) {
+        Some((color, oklch)) if color == *current_color => oklch,
+        _ => OkLCh::from(OkLab::from(*current_color)),
+    };
+
+    take_exact_edit(ui);
+    color_picker_2d_impl(ui, &mut new_oklch, resolution);
+
+    new_oklch.chroma = new_oklch.chroma.min(max_chroma(
+        &RgbSpace::SRGB,
+        new_oklch.lightness,
+        new_oklch.hue,
+    ));
+    let new_color = Srgb::from(OkLab::from(new_oklch));
+    let changed = apply_edit(current_color, new_color, take_exact_edit(ui));
+    ui.data().insert_temp(id, (*current_color, new_oklch));
+
+    color_text_edit_ui(ui, id, current_color) || changed
+}
+
+/// Returns the displayed color for `lch`, masking it if it's out of gamut.
+fn masked(lch: OkLCh) -> Color32 {
+    if lch.chroma > max_chroma(&RgbSpace::SRGB, lch.lightness, lch.hue) {
+        OUT_OF_GAMUT
+    } else {
+        Srgb::from(OkLab::from(lch)).into()
+    }
+}
+
+fn color_picker_2d_impl(ui: &mut Ui, oklch: &mut OkLCh, resolution: u32) {
+    let current_color_size = vec2(
+        2.0 * ui.spacing().slider_width,
+        2.0 * ui.spacing().interact_size.y,
+    );
+    show_color(
+        ui,
+        CvdPreview::load(ui).apply(masked(*oklch)),
+        current_color_size,
+    )
+    .on_hover_text("Selected color");
+
+    color_text_oklch_ui(ui, *oklch);
+    color_text_rgb_hex_ui(ui, Srgb::from(OkLab::from(*oklch)));
+
+    let current = *oklch;
+
+    let OkLCh {
+        lightness,
+        chroma,
+        hue,
+    } = oklch;
+
+    hue_slider_1d(ui, hue, |hue| masked(OkLCh { hue, ..current })).on_hover_text("Hue");
+
+    let mut chroma_t = *chroma / CHROMA_MAX;
+    let plane = color_slider_2d(
+        ui,
+        &mut chroma_t,
+        lightness,
+        current.hue,
+        resolution,
+        |chroma_t, lightness| {
+            masked(OkLCh {
+                lightness,
+                chroma: chroma_t * CHROMA_MAX,
+                ..current
+            })
+        },
+    );
+    *chroma = chroma_t * CHROMA_MAX;
+
+    if ui.is_rect_visible(plane.rect) {
+        // Draw the gamut boundary, to get a sharper edge than the mesh
+        let rect = plane.rect;
+        let boundary = (0..=N)
+            .map(|i| {
+                let lightness = i as f64 / N as f64;
+                let chroma_t =
+                    (max_chroma(&RgbSpace::SRGB, lightness, current.hue) / CHROMA_MAX).min(1.0);
+                pos2(
+                    lerp(rect.left()..=rect.right(), chroma_t as f32),
+                    lerp(rect.bottom()..=rect.top(), lightness as f32),
+                )
+            })
+            .collect();
+        let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+        ui.painter().add(Shape::line(boundary, stroke));
+    }
+
+    color_slider_1d(ui, chroma, 0.0, CHROMA_MAX, |chroma| {
+        masked(OkLCh { chroma, ..current })
+    })
+    .on_hover_text("Chroma");
+
+    color_slider_1d(ui, lightness, 0.0, 1.0, |lightness| {
+        masked(OkLCh {
+            lightness,
+            ..current
+        })
+    })
+    .on_hover_text("Lightness");
+}
@@ -0,0 +1,219 @@
+// SPDX-FileCopyrightText: 2022 Gerry Agbobada <git@gagbo.net>
+//
+// SPDX-License-Identifier: GPL-3.0-only
+
+use eframe::egui::{pos2, vec2, CursorIcon, Rect, Sense, Shape, TextEdit, Ui};
+
+use super::CvdPreview;
+use crate::{
+    colors::{format::Notation, Srgb},
+    palette::{nearest, Swatch},
+};
+
+/// State of a [`palette_panel`], kept in egui memory between frames.
+#[derive(Clone, Copy, Debug, Default)]
+struct PaletteState {
+    /// Index of the swatch being dragged around
+    dragged: Option<usize>,
+    /// Index of the last selected swatch
+    selected: Option<usize>,
+}
+
+/// Shows a button that adds `color` at the end of the palette.
+///
+/// Returns `true` if the color was added.
+pub fn add_swatch_button(ui: &mut Ui, palette: &mut Vec<Swatch>, color: Srgb) -> bool {
+    let clicked = ui
+        .button("➕ Add to palette")
+        .on_hover_text("Add the current color to the palette")
+        .clicked();
+    if clicked {
+        palette.push(Swatch::new(color));
+    }
+    clicked
+}
+
+/// Shows the palette color closest to `color`, and how far it is in ΔE_OK.
+///
+/// Clicking the swatch returns its color, to pick it.
+pub fn nearest_swatch_ui(ui: &mut Ui, palette: &[Swatch], color: Srgb) -> Option<Srgb> {
+    let (index, delta) = nearest(palette, color)?;
+    let swatch = &palette[index];
+    ui.horizontal(|ui| {
+        ui.label("Nearest:");
+        let size = vec2(
+            2.0 * ui.spacing().interact_size.y,
+            ui.spacing().interact_size.y,
+        );
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+        if ui.is_rect_visible(rect) {
+            let stroke = ui.style().interact(&response).bg_stroke;
+            ui.painter()
+                .rect(rect, 2.0, CvdPreview::load(ui).apply(swatch.color), stroke);
+        }
+        ui.label(format!("{} (ΔE {delta:.3})", swatch.name));
+        response
+            .on_hover_text("Closest palette color, click to pick it")
+            .clicked()
+            .then_some(swatch.color)
+    })
+    .inner
+}
+
+/// Shows the palette as a list of swatches.
+///
+/// Swatches can be selected by clicking them, renamed, removed, and
+/// reordered by dragging them around. Right clicking a swatch allows to copy
+/// its color in any [`Notation`].
+///
+/// Returns the color of the swatch that just got selected, if any.
+pub fn palette_panel(ui: &mut Ui, palette: &mut Vec<Swatch>) -> Option<Srgb> {
+    let id = ui.make_persistent_id("palette_panel");
+    let mut state = ui.data().get_temp::<PaletteState>(id).unwrap_or_default();
+    let preview = CvdPreview::load(ui);
+
+    if palette.is_empty() {
+        ui.label("The palette is empty, add colors from the pickers.");
+    }
+
+    let mut picked = None;
+    let mut removed = None;
+    let mut rows: Vec<Rect> = Vec::with_capacity(palette.len());
+
+    for (index, swatch) in palette.iter_mut().enumerate() {
+        let row = ui.horizontal(|ui| {
+            let size = vec2(
+                2.0 * ui.spacing().interact_size.y,
+                ui.spacing().interact_size.y,
+            );
+            let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+
+            if ui.is_rect_visible(rect) {
+                let stroke = if state.selected == Some(index) {
+                    ui.visuals().selection.stroke
+                } else {
+                    ui.style().interact(&response).bg_stroke
+                };
+                ui.painter()
+                    .rect(rect, 2.0, preview.apply(swatch.color), stroke);
+            }
+
+            let response =
+                response.on_hover_text("Click to select, drag to reorder, right click to copy");
+            if response.clicked() {
+                state.selected = Some(index);
+                picked = Some(swatch.color);
+            }
+            if response.drag_started() {
+                state.dragged = Some(index);
+            }
+            response.context_menu(|ui| {
+                for notation in Notation::ALL {
+                    let text = notation.format(swatch.color);
+                    if ui.button(format!("Copy {text}")).clicked() {
+                        ui.output().copied_text = text;
+                        ui.close_menu();
+                    }
+                }
+            });
+
+            ui.add(TextEdit::singleline(&mut swatch.name).desired_width(ui.spacing().slider_width))
+                .on_hover_text("Name of the color");
+
+            if ui
+                .button("🗑")
+                .on_hover_text("Remove from the palette")
+                .clicked()
+            {
+                removed = Some(index);
+            }
+        });
+        rows.push(row.response.rect);
+    }
+
+    if let Some(from) = state.dragged {
+        ui.output().cursor_icon = CursorIcon::Grabbing;
+
+        let target = ui.input().pointer.interact_pos().and_then(|pos| {
+            let first = rows.first()?;
+            let last = rows.last()?;
+            if pos.y < first.top() {
+                Some(0)
+            } else if pos.y > last.bottom() {
+                Some(rows.len() - 1)
+            } else {
+                rows.iter().position(|row| row.y_range().contains(&pos.y))
+            }
+        });
+
+        if let Some(to) = target.filter(|&to| to != from) {
+            // Show where the swatch would land
+            let row = rows[to];
+            let y = if to < from { row.top() } else { row.bottom() };
+            ui.painter().add(Shape::line_segment(
+                [pos2(row.left(), y), pos2(row.right(), y)],
+                ui.visuals().selection.stroke,
+            ));
+        }
+
+        if ui.input().pointer.any_released() {
+            if let Some(to) = target.filter(|&to| to != from && from < palette.len()) {
+                let swatch = palette.remove(from);
+                palette.insert(to, swatch);
+                state.selected = state.selected.map(|index| moved_index(index, from, to));
+            }
+            state.dragged = None;
+        }
+    }
+
+    if let Some(index) = removed {
+        palette.remove(index);
+        state.selected = match state.selected {
+            Some(selected) if selected == index => None,
+            Some(selected) if selected > index => Some(selected - 1),
+            selected => selected,
+        };
+    }
+
+    ui.data().insert_temp(id, state);
+    picked
+}
+
+/// Where the swatch at `index` ends up after moving the one at `from` to
+/// `to`.
+fn moved_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && index <= to {
+        index - 1
+    } else if to <= index && index < from {
+        index + 1
+    } else {
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_after_move() {
+        // The moved swatch itself
+        assert_eq!(moved_index(1, 1, 4), 4);
+        assert_eq!(moved_index(4, 4, 1), 1);
+
+        // Moved down: the swatches in between go up
+        assert_eq!(moved_index(2, 1, 4), 1);
+        assert_eq!(moved_index(4, 1, 4), 3);
+        // Moved up: the swatches in between go down
+        assert_eq!(moved_index(1, 4, 1), 2);
+        assert_eq!(moved_index(3, 4, 1), 4);
+
+        // The others stay
+        for (from, to) in [(1, 4), (4, 1)] {
+            assert_eq!(moved_index(0, from, to), 0);
+            assert_eq!(moved_index(5, from, to), 5);
+        }
+    }
+}